@@ -1,12 +1,196 @@
+/// Dataset of one wafer (or lot): each row is a die at (column, row), each data column a measured test
+/// This is the format to build datasets programmatically, it is validated on construction
+#[derive(Clone, Debug, PartialEq)]
 pub struct DataFormat {
     row_count: usize,
     column: Vec<i32>,
     row: Vec<i32>,
     header: Vec<String>,
     data: std::collections::HashMap<String, Vec<f32>>,
+    title: String,
+    info: std::collections::HashMap<String, ColumnInfo>,
 }
+
+/// Limits and description of a data column, a die passes if lower <= value <= upper
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnInfo {
+    pub lower: Option<f32>,
+    pub upper: Option<f32>,
+    pub info: String,
+    pub unit: Option<String>,
+}
+impl ColumnInfo {
+    pub fn new(lower: Option<f32>, upper: Option<f32>) -> Self {
+        Self {
+            lower,
+            upper,
+            ..Default::default()
+        }
+    }
+    pub fn with_info(self, info: impl Into<String>) -> Self {
+        Self {
+            info: info.into(),
+            ..self
+        }
+    }
+    pub fn with_unit(self, unit: impl Into<String>) -> Self {
+        Self {
+            unit: Some(unit.into()),
+            ..self
+        }
+    }
+}
+
+/// Labels of the coordinate columns, when the dataset is written as mv01 file
+pub const COLUMN_LABEL: &str = "X";
+pub const ROW_LABEL: &str = "Y";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// The coordinates or the data of a column do not have one value per die
+    LengthMismatch {
+        label: String,
+        expected: usize,
+        found: usize,
+    },
+    DuplicateLabel(String),
+    EmptyLabel,
+    /// Text which would break the mv01 layout (delimiter or line break)
+    InvalidText {
+        label: String,
+        text: String,
+    },
+    NonFiniteLimit {
+        label: String,
+    },
+    LimitsReversed {
+        label: String,
+        lower: f32,
+        upper: f32,
+    },
+}
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::LengthMismatch {
+                label,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{label}' has {found} values, but {expected} are expected"
+            ),
+            ValidationError::DuplicateLabel(label) => {
+                write!(f, "Label '{label}' occurs multiple times")
+            }
+            ValidationError::EmptyLabel => write!(f, "Label is empty"),
+            ValidationError::InvalidText { label, text } => {
+                write!(f, "'{label}' contains a delimiter or line break: '{text}'")
+            }
+            ValidationError::NonFiniteLimit { label } => {
+                write!(f, "'{label}' has a limit which is not a finite number")
+            }
+            ValidationError::LimitsReversed {
+                label,
+                lower,
+                upper,
+            } => write!(
+                f,
+                "'{label}' has a lower limit {lower} above its upper limit {upper}"
+            ),
+        }
+    }
+}
+impl std::error::Error for ValidationError {}
+
 // Constructors
 impl DataFormat {
+    /// Dataset without data columns, one die per coordinate pair
+    pub fn new(
+        title: impl Into<String>,
+        column: Vec<i32>,
+        row: Vec<i32>,
+    ) -> Result<Self, ValidationError> {
+        let title = title.into();
+        if title.contains(['\n', '\r']) {
+            return Err(ValidationError::InvalidText {
+                label: "title".into(),
+                text: title,
+            });
+        }
+        if row.len() != column.len() {
+            return Err(ValidationError::LengthMismatch {
+                label: ROW_LABEL.into(),
+                expected: column.len(),
+                found: row.len(),
+            });
+        }
+        Ok(Self {
+            row_count: column.len(),
+            column,
+            row,
+            header: Vec::new(),
+            data: Default::default(),
+            title,
+            info: Default::default(),
+        })
+    }
+
+    /// Append a data column, values may be NaN for dies which were not measured
+    pub fn add_data(
+        &mut self,
+        label: impl Into<String>,
+        values: Vec<f32>,
+        info: ColumnInfo,
+    ) -> Result<(), ValidationError> {
+        let label = label.into();
+        if label.is_empty() {
+            return Err(ValidationError::EmptyLabel);
+        }
+        if self.header.contains(&label) || label == COLUMN_LABEL || label == ROW_LABEL {
+            return Err(ValidationError::DuplicateLabel(label));
+        }
+        if values.len() != self.row_count {
+            return Err(ValidationError::LengthMismatch {
+                label,
+                expected: self.row_count,
+                found: values.len(),
+            });
+        }
+        let texts = [Some(&label), Some(&info.info), info.unit.as_ref()];
+        if let Some(text) = texts
+            .into_iter()
+            .flatten()
+            .find(|t| t.contains([';', '\n', '\r']))
+        {
+            return Err(ValidationError::InvalidText {
+                text: text.clone(),
+                label: label.clone(),
+            });
+        }
+        if info
+            .lower
+            .into_iter()
+            .chain(info.upper)
+            .any(|l| !l.is_finite())
+        {
+            return Err(ValidationError::NonFiniteLimit { label });
+        }
+        if let (Some(lower), Some(upper)) = (info.lower, info.upper) {
+            if lower > upper {
+                return Err(ValidationError::LimitsReversed {
+                    label,
+                    lower,
+                    upper,
+                });
+            }
+        }
+        self.header.push(label.clone());
+        self.data.insert(label.clone(), values);
+        self.info.insert(label, info);
+        Ok(())
+    }
+
     pub fn example_rectangle_simple(row_count: usize) -> Self {
         Self::example_rectangle(
             row_count,
@@ -25,26 +209,17 @@ impl DataFormat {
         let (column, row) = (0..(row_count as i32))
             .map(|x| (top_left_column + x, top_left_row + x))
             .unzip();
-        let mut header = Vec::with_capacity(data.len());
-        let mut data_ = std::collections::HashMap::with_capacity(data.len());
+        let mut dataset = Self::new("", column, row).map_err(|e| e.to_string())?;
         for (label, min, max) in data {
-            if header.contains(&label) {
-                return Err(format!("Label '{label}' occurs multiple times"));
-            }
-            header.push(label.clone());
             let delta = max - min;
             let d = (0..row_count)
                 .map(|i| min + (i as f32) * delta / (row_count - 1) as f32)
                 .collect();
-            data_.insert(label, d);
+            dataset
+                .add_data(label, d, ColumnInfo::default())
+                .map_err(|e| e.to_string())?;
         }
-        Ok(Self {
-            row_count,
-            column,
-            row,
-            header,
-            data: data_,
-        })
+        Ok(dataset)
     }
 }
 // Getter
@@ -64,4 +239,140 @@ impl DataFormat {
     pub fn data(&self, header: &str) -> Option<&[f32]> {
         self.data.get(header).map(|x| x.as_slice())
     }
+    /// Free-text description of the dataset, e.g. lot and wafer
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    pub fn info(&self, header: &str) -> Option<&ColumnInfo> {
+        self.info.get(header)
+    }
+}
+// Export
+impl DataFormat {
+    /// Write as mv01 file, which the visualizer opens directly
+    /// A unit row is appended to the limit block if any column has a unit
+    pub fn write_mv01(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+        let infos = self
+            .header
+            .iter()
+            .map(|label| &self.info[label])
+            .collect::<Vec<_>>();
+        let limit = |l: Option<f32>| l.map(|l| l.to_string()).unwrap_or("-".to_string());
+        writeln!(w, "{}", self.title)?;
+        let mut metadata = vec![
+            [COLUMN_LABEL, ROW_LABEL]
+                .into_iter()
+                .map(String::from)
+                .chain(self.header.iter().cloned())
+                .collect::<Vec<_>>(),
+            ["-", "-"]
+                .into_iter()
+                .map(String::from)
+                .chain(infos.iter().map(|i| limit(i.lower)))
+                .collect(),
+            ["-", "-"]
+                .into_iter()
+                .map(String::from)
+                .chain(infos.iter().map(|i| limit(i.upper)))
+                .collect(),
+            ["die column", "die row"]
+                .into_iter()
+                .map(String::from)
+                .chain(infos.iter().map(|i| i.info.clone()))
+                .collect(),
+        ];
+        if infos.iter().any(|i| i.unit.is_some()) {
+            metadata.push(
+                ["", ""]
+                    .into_iter()
+                    .map(String::from)
+                    .chain(infos.iter().map(|i| i.unit.clone().unwrap_or_default()))
+                    .collect(),
+            );
+        }
+        for line in metadata {
+            writeln!(w, "{}", line.join(";"))?;
+        }
+        let columns = self
+            .header
+            .iter()
+            .map(|h| &self.data[h])
+            .collect::<Vec<_>>();
+        for i in 0..self.row_count {
+            write!(w, "{};{}", self.column[i], self.row[i])?;
+            for column in &columns {
+                write!(w, ";{}", column[i])?;
+            }
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ColumnInfo, DataFormat, ValidationError};
+
+    #[test]
+    fn build_and_validate() {
+        let mut data = DataFormat::new("Lot 17", vec![0, 1, 2], vec![5, 5, 5]).unwrap();
+        data.add_data(
+            "Vth",
+            vec![0.5, 0.7, f32::NAN],
+            ColumnInfo::new(Some(0.1), Some(0.6)).with_unit("V"),
+        )
+        .unwrap();
+        assert_eq!(data.header(), ["Vth"]);
+        assert_eq!(data.info("Vth").unwrap().unit.as_deref(), Some("V"));
+
+        let mut add = |label: &str, values: Vec<f32>, info| data.add_data(label, values, info);
+        assert_eq!(
+            add("Vth", vec![0.; 3], Default::default()),
+            Err(ValidationError::DuplicateLabel("Vth".into()))
+        );
+        assert_eq!(
+            add("X", vec![0.; 3], Default::default()),
+            Err(ValidationError::DuplicateLabel("X".into()))
+        );
+        assert_eq!(
+            add("Idd", vec![0.; 2], Default::default()),
+            Err(ValidationError::LengthMismatch {
+                label: "Idd".into(),
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            add("Idd", vec![0.; 3], ColumnInfo::new(Some(2.), Some(1.))),
+            Err(ValidationError::LimitsReversed {
+                label: "Idd".into(),
+                lower: 2.,
+                upper: 1.
+            })
+        );
+        assert_eq!(
+            add("Idd", vec![0.; 3], ColumnInfo::new(Some(f32::NAN), None)),
+            Err(ValidationError::NonFiniteLimit {
+                label: "Idd".into()
+            })
+        );
+        assert!(matches!(
+            add("I;dd", vec![0.; 3], Default::default()),
+            Err(ValidationError::InvalidText { .. })
+        ));
+        assert!(DataFormat::new("", vec![0], vec![]).is_err());
+    }
+
+    #[test]
+    fn write_mv01() {
+        let mut data = DataFormat::new("Lot 17", vec![0, 1], vec![5, 6]).unwrap();
+        data.add_data("Vth", vec![0.5, 0.7], ColumnInfo::new(Some(0.1), None))
+            .unwrap();
+        let mut bytes = Vec::new();
+        data.write_mv01(&mut bytes).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "Lot 17\nX;Y;Vth\n-;-;0.1\n-;-;-\ndie column;die row;\n0;5;0.5\n1;6;0.7\n"
+        );
+    }
 }
@@ -4,15 +4,21 @@ mod _tabs;
 mod dummy;
 mod file_loader;
 mod files;
+mod formulas;
 mod heatmap;
+mod history;
 mod limits;
+mod pareto;
 mod plot;
+mod project;
+mod render;
+mod report;
+mod scenarios;
 mod selection;
+mod statistics;
 mod violinplot;
 mod distribution;
 
-use std::collections::HashMap;
-
 use crate::{
     data_types::{FileKey, FileKeyGenerator, LimitKey},
     Language, LocalizableStr,
@@ -72,7 +78,14 @@ impl Default for LockableLimitKey {
 
 use _tabs::TabTrait;
 
-type Filtering = Vec<bool>;
+pub(super) use render::run as render;
+pub(super) use report::run as report;
+
+/// Headless engine which holds loaded files, applied limits and the resulting filtering
+type DataCenter =
+    michael_visualizer_basic::DataCenter<FileKey, LimitKey, files::FileData, limits::Limit>;
+/// Limits which were removed from the data center, with their keys to restore them
+type PrunedLimits = Vec<(LimitKey, limits::Limit)>;
 static RESET: crate::LocalizableStr<'static> = crate::LocalizableStr { english: "Reset" };
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
@@ -84,21 +97,43 @@ pub(super) struct App {
     files: files::FileContainer,
     selected: Option<selection::Selection>,
     file_key_generator: crate::data_types::FileKeyGenerator,
-    limit_key_generator: crate::data_types::LimitKeyGenerator,
     file_loader: file_loader::FileLoader,
     #[serde(skip)]
     data_events: Vec<DataEvent>,
-    #[serde(skip)]
-    filterings: HashMap<(LimitKey, FileKey), Filtering>,
-    #[serde(skip)]
-    total_filterings: HashMap<FileKey, Box<[u32]>>,
+    center: DataCenter,
     locked_limits: Vec<LimitKey>,
     #[serde(skip)]
     requested_screenshot: Option<egui::Rect>,
+    #[serde(skip)]
+    history: history::History,
+    /// Derived columns, added to every loaded file
+    #[serde(default)]
+    formulas: formulas::Formulas,
+    /// Named limit configurations, compared with the current limits
+    #[serde(default)]
+    scenarios: scenarios::Scenarios,
+}
+
+/// App with the example rectangle of 10 dies loaded as its only file
+#[cfg(test)]
+fn app_with_example(label: &str) -> (App, FileKey) {
+    let mut app = App::default();
+    let key = app.file_key_generator.next();
+    app.data_events.push(DataEvent::File(files::FileEvent::FromData {
+        key: key.clone(),
+        label: label.into(),
+        data: data_format::DataFormat::example_rectangle_simple(10),
+    }));
+    app.process_data_events();
+    (app, key)
 }
 
 impl App {
     pub(super) fn init(&mut self, cc: &eframe::CreationContext) {
+        self.restore(&cc.egui_ctx);
+    }
+    /// Completes a deserialized app, i.e. after a restart or when a project is opened
+    fn restore(&mut self, ctx: &egui::Context) {
         let mut kinds = _tabs::TabKind::kinds();
         for kind in self.tabs.tabs.tabs().map(|(_, x)| x.kind()) {
             if let Some(index) = kinds.iter().position(|&x| kind == x) {
@@ -108,14 +143,23 @@ impl App {
         for kind in kinds {
             self.tabs.push(kind);
         }
-        cc.egui_ctx.set_visuals(match self.mode {
+        ctx.set_visuals(match self.mode {
             _dark_light::DarkLightMode::Dark => egui::Visuals::dark(),
             _dark_light::DarkLightMode::Light => egui::Visuals::light(),
         });
         self.data_events.extend(self.files.init());
+        self.history.reset(&self.center);
     }
     pub(super) fn show(&mut self, ui: &mut egui::Ui) -> Vec<AppEvent> {
         self.data_events.extend(self.file_loader.check_progress());
+        // changed limits are applied in the background, plots keep the previous filtering meanwhile
+        if self.center.poll().limits {
+            self.data_events.push(DataEvent::Filtering);
+        }
+        if self.center.is_filtering() {
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_millis(15));
+        }
         ui.ctx()
             .request_repaint_after(std::time::Duration::from_secs(3));
         {
@@ -123,6 +167,8 @@ impl App {
             self.data_events.extend(events);
         }
         let mut app_events = Vec::new();
+        let mut opened = None;
+        let (mut undo_requested, mut redo_requested) = (false, false);
         egui::menu::bar(ui, |ui| {
             ui.menu_button(
                 LocalizableStr { english: "File" }.localize(self.language),
@@ -165,6 +211,56 @@ impl App {
                             ui.ctx().set_visuals(mode.visuals());
                         }
                     }
+                    // projects
+                    {
+                        if ui
+                            .button(
+                                LocalizableStr {
+                                    english: "Save Project …",
+                                }
+                                .localize(self.language),
+                            )
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter(
+                                    LocalizableStr { english: "Project" }.localize(self.language),
+                                    &[project::EXTENSION],
+                                )
+                                .save_file()
+                            {
+                                if let Err(message) = project::save(self, &path) {
+                                    app_events.push(project_dialog(message, self.language));
+                                }
+                            }
+                        }
+                        if ui
+                            .button(
+                                LocalizableStr {
+                                    english: "Open Project …",
+                                }
+                                .localize(self.language),
+                            )
+                            .clicked()
+                        {
+                            ui.close_menu();
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter(
+                                    LocalizableStr { english: "Project" }.localize(self.language),
+                                    &[project::EXTENSION],
+                                )
+                                .pick_file()
+                            {
+                                match project::load(&path) {
+                                    Ok(app) => opened = Some(app),
+                                    Err(message) => {
+                                        app_events.push(project_dialog(message, self.language))
+                                    }
+                                }
+                            }
+                        }
+                    }
                     // quit button
                     {
                         if ui
@@ -177,6 +273,37 @@ impl App {
                     }
                 },
             );
+            ui.menu_button(
+                LocalizableStr { english: "Edit" }.localize(self.language),
+                |ui| {
+                    if ui
+                        .add_enabled(
+                            self.history.can_undo(),
+                            egui::Button::new(
+                                LocalizableStr { english: "Undo" }.localize(self.language),
+                            )
+                            .shortcut_text("Ctrl+Z"),
+                        )
+                        .clicked()
+                    {
+                        undo_requested = true;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.history.can_redo(),
+                            egui::Button::new(
+                                LocalizableStr { english: "Redo" }.localize(self.language),
+                            )
+                            .shortcut_text("Ctrl+Y"),
+                        )
+                        .clicked()
+                    {
+                        redo_requested = true;
+                        ui.close_menu();
+                    }
+                },
+            );
             ui.menu_button(
                 LocalizableStr { english: "Tabs" }.localize(self.language),
                 |ui| {
@@ -200,6 +327,29 @@ impl App {
                             _tabs::TabKind::Selection,
                         ),
                         (LocalizableStr { english: "Plot" }, _tabs::TabKind::Plot),
+                        (
+                            LocalizableStr { english: "History" },
+                            _tabs::TabKind::History,
+                        ),
+                        (
+                            LocalizableStr {
+                                english: "Formulas",
+                            },
+                            _tabs::TabKind::Formulas,
+                        ),
+                        (
+                            LocalizableStr {
+                                english: "Statistics",
+                            },
+                            _tabs::TabKind::Statistics,
+                        ),
+                        (
+                            LocalizableStr {
+                                english: "Scenarios",
+                            },
+                            _tabs::TabKind::Scenarios,
+                        ),
+                        (LocalizableStr { english: "Pareto" }, _tabs::TabKind::Pareto),
                     ] {
                         if ui.button(label.localize(self.language)).clicked() {
                             self.tabs.push(tab);
@@ -209,6 +359,11 @@ impl App {
                 },
             );
         });
+        if let Some(app) = opened {
+            // the running loaders belong to the previous project, their results are dropped
+            *self = app;
+            self.restore(ui.ctx());
+        }
         let state = &mut AppState {
             language: self.language,
             app_events: &mut app_events,
@@ -216,11 +371,13 @@ impl App {
             files: &mut self.files,
             data_events: &mut self.data_events,
             file_key_generator: &mut self.file_key_generator,
-            total_filterings: &mut self.total_filterings,
-            filtering: &mut self.filterings,
+            center: &mut self.center,
             locked_limits: &mut self.locked_limits,
             selected: &mut self.selected,
             requested_screenshot: &mut self.requested_screenshot,
+            history: &mut self.history,
+            formulas: &mut self.formulas,
+            scenarios: &mut self.scenarios,
         };
         self.tabs.progress(state);
 
@@ -228,19 +385,49 @@ impl App {
             //.scroll_area_in_tabs(false)
             .show_close_buttons(true)
             .show_inside(ui, state);
+        // text fields have their own undo, while they are edited
+        if ui.memory(|m| m.focus().is_none()) {
+            ui.input_mut(|i| {
+                undo_requested |= i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z);
+                redo_requested |= i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y)
+                    | i.consume_key(
+                        egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                        egui::Key::Z,
+                    );
+            });
+        }
+        let steps = self.history.take_request().unwrap_or_default() + isize::from(undo_requested)
+            - isize::from(redo_requested);
+        for _ in 0..steps {
+            self.undo();
+        }
+        for _ in steps..0 {
+            self.redo();
+        }
+        self.process_data_events();
+        app_events
+    }
+
+    /// Handles the events of this frame, including the events caused by them
+    fn process_data_events(&mut self) {
         while !self.data_events.is_empty() {
             let current_events = std::mem::take(&mut self.data_events);
             for event in current_events {
                 let event = &event;
                 self.check_for_file_loading(event);
                 self.check_for_limit_event(event);
+                self.record_limit_event(event);
                 self.check_for_selection_event(event);
+                if let DataEvent::Formulas = event {
+                    self.formulas_changed();
+                }
+                self.scenarios.notify(event);
                 self.data_events.extend(self.limits.notify(event));
                 self.data_events.extend(self.files.notify(event));
                 self.data_events.extend(self.tabs.notify(event));
             }
         }
-        app_events
+        self.history.commit();
     }
 
     #[must_use]
@@ -262,7 +449,7 @@ impl App {
                     name,
                     last_modified: _,
                     bytes: Some(bytes),
-                } if name.is_empty() => files::FileEvent::ParseFromBytes {
+                } => files::FileEvent::ParseFromBytes {
                     key,
                     label: name.to_string(),
                     bytes: bytes.to_vec(),
@@ -286,122 +473,299 @@ impl App {
             match event {
                 files::FileEvent::LoadFromPath { key, path } => {
                     self.files.insert(key.clone(), path.clone());
-                    self.file_loader.load(key.clone(), path.clone());
+                    let progress = self.file_loader.load(
+                        key.clone(),
+                        path.clone(),
+                        self.files.parser_config(key),
+                    );
+                    self.files.set_progress(key, progress);
                 }
                 files::FileEvent::ParseFromBytes { key, label, bytes } => {
                     self.files.make_parsing(key, label);
-                    self.file_loader.parse(key.clone(), bytes.to_vec());
+                    let progress = self.file_loader.parse(
+                        key.clone(),
+                        bytes.to_vec(),
+                        self.files.parser_config(key),
+                    );
+                    self.files.set_progress(key, progress);
+                }
+                files::FileEvent::FromData { key, label, data } => {
+                    self.files.make_parsing(key, label);
+                    self.data_events
+                        .push(DataEvent::File(files::FileEvent::Loaded {
+                            key: key.clone(),
+                            file: data.clone().into(),
+                            parser_config: None,
+                            non_conforming_tooltip: None,
+                        }));
+                }
+                files::FileEvent::Reload(key) => {
+                    // the data center keeps the file until it is replaced, so its limits are kept
+                    if let Some(path) = self.files.make_reloading(key) {
+                        let progress =
+                            self.file_loader
+                                .load(key.clone(), path, self.files.parser_config(key));
+                        self.files.set_progress(key, progress);
+                    }
+                }
+                files::FileEvent::Cancel(key) => {
+                    self.file_loader.cancel(key);
+                    self.progress_center(michael_visualizer_basic::FileEvent::Removed(key.clone()));
+                    self.files.make_loaderror(
+                        key,
+                        crate::LocalizableString {
+                            english: "Loading was cancelled".to_string(),
+                        },
+                    );
+                }
+                files::FileEvent::Remove(key) => {
+                    if let Some((index, file, limits)) = self.remove_file(key) {
+                        self.history.push(history::Change::FileRemoved {
+                            key: key.clone(),
+                            label: file.label().clone(),
+                            index,
+                            file: Some(Box::new(file)),
+                            limits,
+                        });
+                    }
+                }
+                files::FileEvent::MoveUp(key) | files::FileEvent::MoveDown(key) => {
+                    let up = matches!(event, files::FileEvent::MoveUp(_));
+                    if self.move_file(key, up) {
+                        if let Some(file) = self.files.get(key) {
+                            self.history.push(history::Change::FileMoved {
+                                key: key.clone(),
+                                label: file.label().clone(),
+                                up,
+                            });
+                        }
+                    }
                 }
-                files::FileEvent::Remove(key) => self.files.remove(key),
-                files::FileEvent::MoveUp(key) => self.files.move_up(key),
-                files::FileEvent::MoveDown(key) => self.files.move_down(key),
                 files::FileEvent::LoadError { key, msg } => {
+                    self.progress_center(michael_visualizer_basic::FileEvent::Removed(key.clone()));
                     self.files.make_loaderror(key, msg.clone())
                 }
+                files::FileEvent::ParseError { key, error } => {
+                    self.progress_center(michael_visualizer_basic::FileEvent::Removed(key.clone()));
+                    self.files.make_parse_error(key, error.clone())
+                }
                 files::FileEvent::Loaded {
                     key,
                     file: filedata,
+                    parser_config,
                     non_conforming_tooltip,
                 } => {
-                    self.file_loaded(key, filedata, non_conforming_tooltip);
+                    self.file_loaded(key, filedata, parser_config, non_conforming_tooltip);
+                }
+                files::FileEvent::ToShow(key) => {
+                    if let Some(file) = self.files.get(key) {
+                        let hidden_or_shown = if file.is_shown() {
+                            michael_visualizer_basic::HiddenOrShown::Shown
+                        } else {
+                            michael_visualizer_basic::HiddenOrShown::Hidden
+                        };
+                        self.progress_center(michael_visualizer_basic::FileEvent::ShowHide(
+                            michael_visualizer_basic::ShowHideEvent::single(
+                                key.clone(),
+                                hidden_or_shown,
+                            ),
+                        ));
+                    }
+                }
+                files::FileEvent::Label(key) => {
+                    if let Some(file) = self.files.get(key) {
+                        self.progress_center(michael_visualizer_basic::FileEvent::Title(
+                            key.clone(),
+                            file.label().clone(),
+                        ));
+                    }
                 }
-                files::FileEvent::ToShow(_) => {}
-                files::FileEvent::Label(_) => {}
             }
         }
     }
 
+    /// Returns the file with its position and the limits, which no other file has
+    fn remove_file(&mut self, key: &FileKey) -> Option<(usize, files::File, PrunedLimits)> {
+        self.file_loader.cancel(key);
+        let (index, file) = self.files.remove(key)?;
+        self.progress_center(michael_visualizer_basic::FileEvent::Removed(key.clone()));
+        // files which are still loading may have the limits, e.g. when a project is restored
+        let pruned = if self.files.is_busy() {
+            Vec::new()
+        } else {
+            self.center.prune_limits()
+        };
+        Some((index, file, pruned))
+    }
+
+    /// Returns false, if the file is already first (up) or last (down)
+    fn move_file(&mut self, key: &FileKey, up: bool) -> bool {
+        let other = if up {
+            self.files.move_up(key)
+        } else {
+            self.files.move_down(key)
+        };
+        if let Some(other) = other {
+            self.progress_center(michael_visualizer_basic::FileEvent::OrderSwitched(
+                key.clone(),
+                other,
+            ));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// File list changes which only need to be mirrored, they do not change the filtering
+    fn progress_center(
+        &mut self,
+        event: michael_visualizer_basic::FileEvent<FileKey, files::FileData>,
+    ) {
+        let _ = self
+            .center
+            .progress(std::iter::once(michael_visualizer_basic::DataEvent::File(
+                event,
+            )));
+    }
+
     fn file_loaded(
         &mut self,
         key: &FileKey,
         filedata: &files::FileData,
+        parser_config: &Option<files::ParserConfig>,
         non_conforming_tooltip: &Option<crate::LocalizableString>,
     ) {
+        if self.files.get(key).is_some_and(|f| !f.is_shown()) {
+            // hidden in a restored project
+            self.data_events
+                .push(DataEvent::File(files::FileEvent::ToShow(key.clone())));
+        }
+        let (filedata, limit_sorting) = self.add_to_center(key, filedata);
+        let duplicates = self.center.duplicate_labels(key).unwrap_or_default();
+        let tooltip = if duplicates.is_empty() {
+            non_conforming_tooltip.clone()
+        } else {
+            let message = format!(
+                "Several columns have the same label, only the first one is used: {}",
+                duplicates
+                    .iter()
+                    .map(|label| label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            Some(crate::LocalizableString {
+                english: match non_conforming_tooltip {
+                    Some(tooltip) => format!("{}\n{message}", tooltip.english),
+                    None => message,
+                },
+            })
+        };
+        self.files
+            .make_loaded(key, &filedata, parser_config, limit_sorting, &tooltip);
+    }
+
+    /// Hands a file with its derived columns to the data center, a file already there is replaced
+    /// Returns the file as handed over and the column of each limit
+    fn add_to_center(
+        &mut self,
+        key: &FileKey,
+        filedata: &files::FileData,
+    ) -> (files::FileData, std::collections::HashMap<LimitKey, usize>) {
         let Self {
             language: _,
             tabs: _,
             mode: _,
-            limits,
+            limits: _,
             files,
             file_key_generator: _,
-            limit_key_generator,
             file_loader: _,
             data_events,
-            filterings,
-            total_filterings,
+            center,
             locked_limits,
             selected: _,
             requested_screenshot: _,
+            history: _,
+            formulas,
+            scenarios: _,
         } = self;
-        assert!(total_filterings
-            .insert(
-                key.clone(),
-                vec![0; filedata.data_count()].into_boxed_slice(),
-            )
-            .is_none());
-
-        let mut limit_sorting = HashMap::new();
-        for (column, limit) in filedata.limits().enumerate() {
-            let (is_new, limit_key) = limits.insert(limit_key_generator, limit);
-            if is_new {
+        let filedata = formulas.derive(filedata);
+        let event = if center.file(key).is_some() {
+            michael_visualizer_basic::FileEvent::Replaced {
+                key: key.clone(),
+                file: filedata.clone(),
+            }
+        } else {
+            michael_visualizer_basic::FileEvent::Loaded {
+                key: key.clone(),
+                label: files
+                    .get(key)
+                    .map(|f| f.label().clone())
+                    .unwrap_or_else(|| String::new().into()),
+                file: filedata.clone(),
+            }
+        };
+        let known = center
+            .limits()
+            .map(|(limit_key, _)| limit_key.clone())
+            .collect::<std::collections::HashSet<_>>();
+        let _ = center.progress(std::iter::once(michael_visualizer_basic::DataEvent::File(
+            event,
+        )));
+        let limit_sorting = center
+            .limit_indices(key)
+            .expect("File was just added")
+            .clone();
+        let mut sorted = limit_sorting.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|(_, &column)| column);
+        for (limit_key, _) in sorted {
+            if !known.contains(limit_key) {
                 if locked_limits.is_empty() {
                     locked_limits.push(limit_key.clone());
                 }
                 data_events.push(DataEvent::Limit(limits::LimitEvent::New(limit_key.clone())))
             }
-            let limit = limits.get(&limit_key).unwrap();
-            let filtering = filedata.apply_limit(limit, column);
-
-            for f in filtering
-                .iter()
-                .zip(total_filterings.get_mut(key).unwrap().iter_mut())
-                .filter_map(|(&b, f)| b.then_some(f))
-            {
-                *f += 1;
-            }
-            assert!(filterings
-                .insert((limit_key.clone(), key.clone()), filtering)
-                .is_none());
-            assert!(limit_sorting.insert(limit_key, column).is_none());
         }
-        files.make_loaded(key, filedata, limit_sorting, non_conforming_tooltip);
+        (filedata, limit_sorting)
+    }
+
+    /// Computes the derived columns of all loaded files again
+    fn formulas_changed(&mut self) {
+        let loaded = self
+            .files
+            .iter_loaded()
+            .map(|(key, (_, filedata, _))| (key.clone(), filedata.clone()))
+            .collect::<Vec<_>>();
+        for (key, filedata) in loaded {
+            let (filedata, limit_sorting) = self.add_to_center(&key, &filedata);
+            self.files.update_loaded(&key, filedata, limit_sorting);
+        }
+        // limits of removed formulas, files which are still loading get the formulas when loaded
+        if !self.files.is_busy() {
+            let _ = self.center.prune_limits();
+        }
+        self.data_events.push(DataEvent::Filtering);
     }
 
     fn check_for_limit_event(&mut self, event: &DataEvent) {
         match event {
             DataEvent::Limit(event) => match event {
                 limits::LimitEvent::LockableLimit(_) => {}
+                // labels are changed in place, the data center only needs them for plotting
                 limits::LimitEvent::Label(_) => {}
                 limits::LimitEvent::Limit(limit_key) | limits::LimitEvent::New(limit_key) => {
-                    let Self {
-                        language: _,
-                        tabs: _,
-                        mode: _,
-                        limits,
-                        files,
-                        file_key_generator: _,
-                        limit_key_generator: _,
-                        file_loader: _,
-                        data_events,
-                        filterings,
-                        total_filterings,
-                        locked_limits: _,
-                        selected: _,
-                        requested_screenshot: _,
-                    } = self;
-                    let mut changed = false;
-                    if let Some(limit) = limits.get(limit_key) {
-                        changed |=
-                            compute_filters(files, limit_key, total_filterings, filterings, limit);
-                    }
-                    if changed {
-                        data_events.push(DataEvent::Filtering)
+                    let redraw = self.center.progress(std::iter::once(
+                        michael_visualizer_basic::DataEvent::Limit(
+                            michael_visualizer_basic::LimitEvent::Edited(limit_key.clone()),
+                        ),
+                    ));
+                    if redraw.limits {
+                        self.data_events.push(DataEvent::Filtering)
                     }
                 }
             },
             DataEvent::LimitRequest(event) => match event {
                 limits::LimitRequest::RequestLabel(key, label) => {
-                    if let Some(limit) = self.limits.get_mut(key) {
+                    if let Some(limit) = self.center.limit_mut(key) {
                         if limit.change_label(label) {
                             self.data_events
                                 .push(DataEvent::Limit(limits::LimitEvent::Label(key.clone())));
@@ -413,12 +777,12 @@ impl App {
                     y_key,
                     rectangle,
                 } => {
-                    if let Some(limit) = self.limits.get_mut(x_key) {
+                    if let Some(limit) = self.center.limit_mut(x_key) {
                         limit.change(rectangle.left_top.x, rectangle.right_bottom.x - 1);
                         self.data_events
                             .push(DataEvent::Limit(limits::LimitEvent::Limit(x_key.clone())));
                     }
-                    if let Some(limit) = self.limits.get_mut(y_key) {
+                    if let Some(limit) = self.center.limit_mut(y_key) {
                         limit.change(rectangle.left_top.y, rectangle.right_bottom.y - 1);
                         self.data_events
                             .push(DataEvent::Limit(limits::LimitEvent::Limit(y_key.clone())));
@@ -428,6 +792,17 @@ impl App {
             _ => (),
         }
     }
+    fn record_limit_event(&mut self, event: &DataEvent) {
+        if let DataEvent::Limit(event) = event {
+            match event {
+                limits::LimitEvent::LockableLimit(_) => {}
+                limits::LimitEvent::Label(key) | limits::LimitEvent::Limit(key) => {
+                    self.history.limit_changed(key, &self.center)
+                }
+                limits::LimitEvent::New(key) => self.history.limit_added(key, &self.center),
+            }
+        }
+    }
     fn check_for_selection_event(&mut self, event: &DataEvent) {
         if let DataEvent::SelectionRequest(selection) = event {
             match selection {
@@ -457,53 +832,17 @@ impl App {
     }
 }
 
-#[must_use]
-fn compute_filters(
-    files: &mut files::FileContainer,
-    limit_key: &LimitKey,
-    total_filterings: &mut HashMap<FileKey, Box<[u32]>>,
-    filterings: &mut HashMap<(LimitKey, FileKey), Vec<bool>>,
-    limit: &limits::Limit,
-) -> bool {
-    let mut changed = false;
-    for (file_key, (_, data, sorting)) in files.get_files_with_limit(limit_key) {
-        if let Some(total_filtering) = total_filterings.get_mut(file_key) {
-            if let Some(old_filtering) = filterings.get_mut(&(limit_key.clone(), file_key.clone()))
-            {
-                if let Some(column) = sorting.get(limit_key) {
-                    let new_filtering = data.apply_limit(limit, *column);
-                    debug_assert_eq!(old_filtering.len(), new_filtering.len());
-                    debug_assert_eq!(old_filtering.len(), total_filtering.len());
-                    total_filtering
-                        .iter_mut()
-                        .zip(new_filtering.iter())
-                        .zip(old_filtering.iter())
-                        .for_each(|((t, new), old)| match (new, old) {
-                            (true, true) => {}
-                            (true, false) => {
-                                changed = true;
-                                *t += 1;
-                            }
-                            (false, true) => {
-                                changed = true;
-                                *t -= 1;
-                            }
-                            (false, false) => {}
-                        });
-                    assert!(filterings
-                        .insert((limit_key.clone(), file_key.clone()), new_filtering)
-                        .is_some());
-                } else {
-                    panic!();
-                }
-            } else {
-                panic!();
-            }
-        } else {
-            panic!();
-        }
-    }
-    changed
+fn project_dialog(message: String, language: Language) -> AppEvent {
+    AppEvent::Dialog(crate::dialog::Dialog::new(
+        LocalizableStr { english: "Project" }
+            .localize(language)
+            .to_string(),
+        Box::new(move |ui| {
+            ui.label(&message);
+            false
+        }),
+        crate::dialog::DialogKind::ok(),
+    ))
 }
 
 struct AppState<'a> {
@@ -514,10 +853,12 @@ struct AppState<'a> {
     selected: &'a mut Option<selection::Selection>,
     data_events: &'a mut Vec<DataEvent>,
     file_key_generator: &'a mut FileKeyGenerator,
-    filtering: &'a mut HashMap<(LimitKey, FileKey), Filtering>,
-    total_filterings: &'a mut HashMap<FileKey, Box<[u32]>>,
+    center: &'a mut DataCenter,
     locked_limits: &'a mut Vec<LimitKey>,
     requested_screenshot: &'a mut Option<egui::Rect>,
+    history: &'a mut history::History,
+    formulas: &'a mut formulas::Formulas,
+    scenarios: &'a mut scenarios::Scenarios,
 }
 
 enum DataEvent {
@@ -525,6 +866,10 @@ enum DataEvent {
     Limit(limits::LimitEvent),
     File(files::FileEvent),
     Filtering,
+    /// Formulas were added, changed or removed
+    Formulas,
+    /// Scenarios were saved, removed or chosen for comparison
+    Scenarios,
     FileRequest(files::FileRequest),
     SelectionRequest(selection::SelectionRequest),
     SelectionEvent(selection::SelectionEvent),
@@ -540,18 +885,6 @@ impl<'a> AppState<'a> {
         self.file_key_generator.next()
     }
 
-    fn get_files_for_limit<'b>(
-        &'b self,
-        limit_key: &'b LimitKey,
-    ) -> impl Iterator<Item = &'b FileKey> + 'b {
-        self.files.keys().filter(move |k: &&FileKey| {
-            let k: &FileKey = k;
-            self.filtering
-                .keys()
-                .any(|(l, ref f)| l == limit_key && f == k)
-        })
-    }
-
     #[must_use]
     fn ui_selectable_limit(&mut self, ui: &mut egui::Ui, to_show: &mut LockableLimitKey) -> bool {
         let mut needs_recompute = false;
@@ -564,7 +897,7 @@ impl<'a> AppState<'a> {
             let (is_locked, value) = to_show.get(self.locked_limits);
             let mut value = value.cloned();
             let selected_label = if let Some(key) = value.as_ref() {
-                if let Some(limit) = self.limits.get(key) {
+                if let Some(limit) = self.center.limit(key) {
                     format!(
                         "{} {}",
                         limit.get_label().as_str(),
@@ -581,7 +914,7 @@ impl<'a> AppState<'a> {
                 axis_selection_text.to_string()
             };
 
-            if self.limits.is_empty() {
+            if self.center.limits().next().is_none() {
                 ui.label(
                     LocalizableStr {
                         english: "No limits available",
@@ -592,7 +925,7 @@ impl<'a> AppState<'a> {
                 egui::ComboBox::from_id_source(axis_selection_text)
                     .selected_text(selected_label)
                     .show_ui(ui, |ui| {
-                        for (key, limit) in self.limits.iter() {
+                        for (key, limit) in self.center.limits() {
                             let previous = value.clone();
                             ui.selectable_value(
                                 &mut value,
@@ -705,7 +1038,7 @@ impl<'a> AppState<'a> {
                 let (is_locked, value) = to_color.get(self.locked_limits);
                 let value = value.cloned();
                 if let Some(key) = value.as_ref() {
-                    let text = if let Some(limit) = self.limits.get(key) {
+                    let text = if let Some(limit) = self.center.limit(key) {
                         format!(
                             "{} {}",
                             limit.get_label().as_str(),
@@ -725,7 +1058,7 @@ impl<'a> AppState<'a> {
             } else {
                 (coloring_selection_text.to_string(), None)
             };
-            if !self.limits.iter().any(|(_, x)| x.is_int()) {
+            if !self.center.limits().any(|(_, x)| x.is_int()) {
                 ui.label(
                     LocalizableStr {
                         english: "No integer limits for coloring available",
@@ -751,7 +1084,7 @@ impl<'a> AppState<'a> {
                                 *to_color = None;
                             }
                         }
-                        for (key, limit) in self.limits.iter().filter(|(_, x)| x.is_int()) {
+                        for (key, limit) in self.center.limits().filter(|(_, x)| x.is_int()) {
                             let previous: Option<LimitKey> = value.clone();
                             ui.selectable_value(
                                 &mut value,
@@ -769,8 +1102,8 @@ impl<'a> AppState<'a> {
                                         ))
                                     }
                                 } else {
-                                    *to_color=Some(LockableLimitKey::Single(key.clone()));
-                                }                                
+                                    *to_color = Some(LockableLimitKey::Single(key.clone()));
+                                }
                             }
                         }
                     })
@@ -852,12 +1185,43 @@ impl<'a> AppState<'a> {
     }
 
     fn get_color(&self, index: usize) -> egui::Color32 {
-        let colors = egui_heatmap::colors::DISTINGUISHABLE_COLORS;
-        let i = index % colors.len();
-        colors[i]
+        distinguishable_color(index)
+    }
+
+    /// Shown files which are loaded, together with their filtering
+    fn plot_files(&self) -> Vec<PlotFile<'_>> {
+        self.files
+            .iter_loaded()
+            .filter_map(|(key, (label, data, sorting))| {
+                self.center.filters_summed(key).map(|filtering| PlotFile {
+                    key,
+                    label,
+                    data,
+                    sorting,
+                    filtering,
+                })
+            })
+            .collect()
     }
 }
 
+fn distinguishable_color(index: usize) -> egui::Color32 {
+    let colors = egui_heatmap::colors::DISTINGUISHABLE_COLORS;
+    let i = index % colors.len();
+    colors[i]
+}
+
+/// Input of the plots, so they can be computed from the GUI state or headless from a data center
+struct PlotFile<'a> {
+    key: &'a FileKey,
+    label: &'a crate::data_types::FileLabel,
+    data: &'a files::FileData,
+    /// Column of each limit
+    sorting: &'a std::collections::HashMap<LimitKey, usize>,
+    /// Count of limits which filter each row
+    filtering: &'a [u32],
+}
+
 #[derive(Hash, serde::Deserialize, serde::Serialize, PartialEq)]
 struct TabId(usize);
 impl TabId {
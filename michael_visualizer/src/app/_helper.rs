@@ -13,3 +13,12 @@ pub(crate) fn galleys(
         })
         .collect()
 }
+
+/// Cell of a ';'-separated line, quoted if it contains the delimiter, a quote or a line break
+pub(crate) fn csv_cell(text: &str) -> std::borrow::Cow<'_, str> {
+    if text.contains([';', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\"")).into()
+    } else {
+        text.into()
+    }
+}
@@ -12,6 +12,11 @@ pub(super) enum Tab {
     Selection(super::selection::SelectionTab),
     Plot(super::plot::PlotTab),
     Distribution(super::distribution::DistributionTab),
+    History(super::history::HistoryTab),
+    Formulas(super::formulas::FormulaTab),
+    Statistics(super::statistics::StatisticsTab),
+    Scenarios(super::scenarios::ScenarioTab),
+    Pareto(super::pareto::ParetoTab),
 }
 impl super::DataEventNotifyable for Tab {
     fn notify(&mut self, event: &super::DataEvent) -> Vec<super::DataEvent> {
@@ -24,6 +29,11 @@ impl super::DataEventNotifyable for Tab {
             Tab::Heatmap(d) => d.notify(event),
             Tab::Plot(d) => d.notify(event),
             Tab::Distribution(d) => d.notify(event),
+            Tab::History(_) => Default::default(),
+            Tab::Formulas(_) => Default::default(),
+            Tab::Statistics(d) => d.notify(event),
+            Tab::Scenarios(d) => d.notify(event),
+            Tab::Pareto(d) => d.notify(event),
         }
     }
 
@@ -37,6 +47,11 @@ impl super::DataEventNotifyable for Tab {
             Tab::Heatmap(d) => d.progress(state),
             Tab::Plot(d) => d.progress(state),
             Tab::Distribution(d) => d.progress(state),
+            Tab::History(_) => {}
+            Tab::Formulas(_) => {}
+            Tab::Statistics(d) => d.progress(state),
+            Tab::Scenarios(d) => d.progress(state),
+            Tab::Pareto(d) => d.progress(state),
         }
     }
 }
@@ -52,6 +67,11 @@ impl Tab {
             Tab::Selection(_) => TabKind::Selection,
             Tab::Plot(_) => TabKind::Plot,
             Tab::Distribution(_) => TabKind::Distribution,
+            Tab::History(_) => TabKind::History,
+            Tab::Formulas(_) => TabKind::Formulas,
+            Tab::Statistics(_) => TabKind::Statistics,
+            Tab::Scenarios(_) => TabKind::Scenarios,
+            Tab::Pareto(_) => TabKind::Pareto,
         }
     }
 }
@@ -66,6 +86,11 @@ pub(super) enum TabKind {
     Selection,
     Plot,
     Distribution,
+    History,
+    Formulas,
+    Statistics,
+    Scenarios,
+    Pareto,
 }
 
 impl TabKind {
@@ -79,6 +104,11 @@ impl TabKind {
             TabKind::Selection,
             TabKind::Plot,
             TabKind::Distribution,
+            TabKind::History,
+            TabKind::Formulas,
+            TabKind::Statistics,
+            TabKind::Scenarios,
+            TabKind::Pareto,
         ]
     }
     pub(super) fn to_tab(self) -> Tab {
@@ -91,6 +121,11 @@ impl TabKind {
             TabKind::Selection => Tab::Selection(Default::default()),
             TabKind::Plot => Tab::Plot(Default::default()),
             TabKind::Distribution => Tab::Distribution(Default::default()),
+            TabKind::History => Tab::History(Default::default()),
+            TabKind::Formulas => Tab::Formulas(Default::default()),
+            TabKind::Statistics => Tab::Statistics(Default::default()),
+            TabKind::Scenarios => Tab::Scenarios(Default::default()),
+            TabKind::Pareto => Tab::Pareto(Default::default()),
         }
     }
 }
@@ -111,6 +146,11 @@ impl Tab {
             Tab::Selection(d) => d.title(viewer),
             Tab::Plot(d) => d.title(viewer),
             Tab::Distribution(d) => d.title(viewer),
+            Tab::History(d) => d.title(viewer),
+            Tab::Formulas(d) => d.title(viewer),
+            Tab::Statistics(d) => d.title(viewer),
+            Tab::Scenarios(d) => d.title(viewer),
+            Tab::Pareto(d) => d.title(viewer),
         }
     }
     pub(super) fn show(&mut self, viewer: &mut AppState, ui: &mut egui::Ui) {
@@ -123,6 +163,11 @@ impl Tab {
             Tab::Selection(d) => d.show(viewer, ui),
             Tab::Plot(d) => d.show(viewer, ui),
             Tab::Distribution(d) => d.show(viewer, ui),
+            Tab::History(d) => d.show(viewer, ui),
+            Tab::Formulas(d) => d.show(viewer, ui),
+            Tab::Statistics(d) => d.show(viewer, ui),
+            Tab::Scenarios(d) => d.show(viewer, ui),
+            Tab::Pareto(d) => d.show(viewer, ui),
         }
     }
 }
@@ -12,6 +12,9 @@ pub struct DistributionTab {
     #[serde(skip)]
     state: State,
     resolution: usize,
+    /// Scenario, whose filtering is plotted instead of the current one
+    #[serde(default)]
+    scenario: Option<String>,
 }
 impl Default for DistributionTab {
     fn default() -> Self {
@@ -20,6 +23,7 @@ impl Default for DistributionTab {
             to_color: Default::default(),
             state: Default::default(),
             resolution: 31,
+            scenario: None,
         }
     }
 }
@@ -74,9 +78,212 @@ enum LimitLabelChange {
 }
 impl DistributionPlot {
     fn show(&mut self, ui: &mut egui::Ui, state: &mut super::AppState) -> Vec<DataEvent> {
-        ui.label("ToDo");
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size_before_wrap(), egui::Sense::click());
+        let rect = response.rect.shrink(5.);
+        self.paint(&painter, rect);
+        response.context_menu(|ui| {
+            if ui
+                .button(
+                    LocalizableStr {
+                        english: "Clipboard",
+                    }
+                    .localize(state.language),
+                )
+                .clicked()
+            {
+                state.request_screenshot(rect);
+                ui.close_menu();
+            }
+        });
         Default::default()
     }
+
+    /// Histogram of all files, the bars of one bin are placed side by side
+    fn paint(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let background = egui::Color32::WHITE;
+        let axis_color = egui::Color32::BLACK;
+        let grid_color = egui::Color32::LIGHT_GRAY;
+        let fontid = egui::FontId::proportional(16.);
+        let steps_count = 5;
+        let margin = 3.;
+        let legend_box_width = 10.;
+
+        painter.rect_filled(rect, egui::Rounding::none(), background);
+        // one series per file, or per file and coloring value
+        let series = self
+            .entries
+            .iter()
+            .enumerate()
+            .flat_map(|(file_index, e)| {
+                e.entries
+                    .iter()
+                    .map(move |(color, entry)| (file_index, *color, entry))
+            })
+            .collect::<Vec<_>>();
+        let Some(bin_count) = series.first().map(|(_, _, e)| e.bins.len()) else {
+            return;
+        };
+        let max_bin = self
+            .entries
+            .iter()
+            .map(|e| e.max_bin())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let layout = |text: String| painter.layout_no_wrap(text, fontid.clone(), axis_color);
+        let step = |i: usize| i as f32 / (steps_count - 1) as f32;
+        let y_labels = (0..steps_count)
+            .map(|i| layout(format!("{}", (max_bin as f32 * step(i)).round())))
+            .collect::<Vec<_>>();
+        let x_labels = (0..steps_count)
+            .map(|i| {
+                let value = self.min.inner() + (self.max.inner() - self.min.inner()) * step(i);
+                layout(value.to_string())
+            })
+            .collect::<Vec<_>>();
+        let limit_label = layout(self.limit_label.as_str().into());
+        let y_label_width = y_labels.iter().map(|g| g.size().x).fold(0., f32::max);
+        let x_label_height = x_labels.iter().map(|g| g.size().y).fold(0., f32::max);
+        let x_label_overhang = x_labels.last().map(|g| g.size().x / 2.).unwrap_or(0.);
+        let inner = egui::Rect::from_min_max(
+            rect.left_top() + egui::vec2(y_label_width + 2. * margin, x_label_height / 2.),
+            rect.right_bottom()
+                - egui::vec2(
+                    x_label_overhang + margin,
+                    x_label_height + limit_label.size().y + 2. * margin,
+                ),
+        );
+        if inner.width() <= 0. || inner.height() <= 0. {
+            return;
+        }
+        // axes and grid
+        for (i, g) in y_labels.into_iter().enumerate() {
+            let y = inner.bottom() - inner.height() * step(i);
+            painter.line_segment(
+                [egui::pos2(inner.left(), y), egui::pos2(inner.right(), y)],
+                egui::Stroke::new(1., grid_color),
+            );
+            painter.galley(
+                egui::pos2(inner.left() - margin - g.size().x, y - g.size().y / 2.),
+                g,
+            );
+        }
+        for (i, g) in x_labels.into_iter().enumerate() {
+            let x = inner.left() + inner.width() * step(i);
+            painter.line_segment(
+                [
+                    egui::pos2(x, inner.bottom()),
+                    egui::pos2(x, inner.bottom() + margin),
+                ],
+                egui::Stroke::new(1., axis_color),
+            );
+            painter.galley(egui::pos2(x - g.size().x / 2., inner.bottom() + margin), g);
+        }
+        painter.galley(
+            egui::pos2(
+                inner.center().x - limit_label.size().x / 2.,
+                inner.bottom() + x_label_height + 2. * margin,
+            ),
+            limit_label,
+        );
+        // bars
+        let bin_width = inner.width() / bin_count as f32;
+        let bar_width = bin_width * 0.9 / series.len() as f32;
+        for (series_index, (file_index, color, entry)) in series.iter().enumerate() {
+            let color = color
+                .and_then(|color| self.colors.iter().find(|(c, _)| *c == color))
+                .map(|(_, c)| *c)
+                .unwrap_or_else(|| super::distinguishable_color(*file_index));
+            for (bin, &count) in entry.bins.iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                let left = inner.left()
+                    + bin_width * (bin as f32 + 0.05)
+                    + bar_width * series_index as f32;
+                let top = inner.bottom() - inner.height() * count as f32 / max_bin as f32;
+                painter.rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(left, top),
+                        egui::pos2(left + bar_width, inner.bottom()),
+                    ),
+                    egui::Rounding::none(),
+                    color,
+                );
+            }
+        }
+        painter.rect_stroke(
+            inner,
+            egui::Rounding::none(),
+            egui::Stroke::new(1., axis_color),
+        );
+        // legend
+        let legend = if self.colors.is_empty() {
+            self.entries
+                .iter()
+                .enumerate()
+                .map(|(i, e)| {
+                    (
+                        super::distinguishable_color(i),
+                        e.label.as_str().to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        } else {
+            self.colors
+                .iter()
+                .map(|(i, c)| (*c, i.to_string()))
+                .collect()
+        };
+        let legend = legend
+            .into_iter()
+            .map(|(c, text)| (c, layout(text)))
+            .collect::<Vec<_>>();
+        let width = legend.iter().map(|(_, g)| g.size().x).fold(0., f32::max)
+            + legend_box_width
+            + 3. * margin;
+        let height = legend.iter().map(|(_, g)| g.size().y + margin).sum::<f32>() + margin;
+        let left_top = inner.right_top() + egui::vec2(-width - margin, margin);
+        painter.rect(
+            egui::Rect::from_min_size(left_top, egui::vec2(width, height)),
+            egui::Rounding::none(),
+            background,
+            egui::Stroke::new(1., axis_color),
+        );
+        let mut offset_y = margin;
+        for (c, g) in legend {
+            let top_box = left_top + egui::vec2(margin, offset_y);
+            offset_y += g.size().y + margin;
+            painter.rect_filled(
+                egui::Rect::from_min_size(top_box, egui::vec2(legend_box_width, g.size().y)),
+                egui::Rounding::none(),
+                c,
+            );
+            painter.galley(top_box + egui::vec2(legend_box_width + margin, 0.), g);
+        }
+    }
+}
+
+/// Draw the histogram of a limit without a GUI, e.g. for reports
+pub(super) fn paint(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    limit_key: &crate::data_types::LimitKey,
+    limit: &super::limits::Limit,
+    to_color_key: Option<&crate::data_types::LimitKey>,
+    files: &[super::PlotFile],
+    resolution: usize,
+) -> Result<(), LocalizableString> {
+    match State::plot(limit_key, limit, to_color_key, files, resolution) {
+        State::Plot(plot) => {
+            plot.paint(painter, rect);
+            Ok(())
+        }
+        State::Error(msg) => Err(msg),
+        State::NeedsRecompute | State::NoLimitSelected => unreachable!("Limit is given"),
+    }
 }
 
 #[derive(Default)]
@@ -147,15 +354,21 @@ impl State {
                 DataEvent::File(event) => match event {
                     FileEvent::LoadFromPath { .. } => unaffected,
                     FileEvent::ParseFromBytes { .. } => unaffected,
+                    FileEvent::FromData { .. } => unaffected,
                     FileEvent::ToShow(_) => affected,
                     FileEvent::Remove(key) => condition(entries.iter().any(|x| &x.key == key)),
                     FileEvent::MoveUp(_) => affected,
                     FileEvent::MoveDown(_) => affected,
                     FileEvent::Label(key) => condition(entries.iter().any(|x| &x.key == key)),
+                    FileEvent::Reload(key) => condition(entries.iter().any(|x| &x.key == key)),
                     FileEvent::LoadError { .. } => unaffected,
+                    FileEvent::ParseError { .. } => unaffected,
+                    FileEvent::Cancel(_) => unaffected,
                     FileEvent::Loaded { .. } => affected,
                 },
                 DataEvent::Filtering => affected,
+                DataEvent::Formulas => unaffected,
+                DataEvent::Scenarios => affected,
                 DataEvent::LimitRequest(_) => unaffected,
                 DataEvent::FileRequest(_) => unaffected,
                 DataEvent::SelectionRequest(_) => unaffected,
@@ -167,118 +380,30 @@ impl State {
 }
 
 impl DistributionTab {
-    fn recompute(&mut self, state: &super::AppState) -> State {
+    fn recompute(&mut self, state: &mut super::AppState) -> State {
         if let Some(limit_key) = self.to_show.get(state.locked_limits).1 {
-            if let Some(limit) = state.limits.get(limit_key) {
-                let super::limits::LimitData {
-                    label: limit_label,
-                    lower,
-                    upper,
-                    info: _,
-                    data_kind: _,
-                } = limit.data();
-                let min = lower.unwrap_or(FiniteF32::new(f32::MIN));
-                let max = upper.unwrap_or(FiniteF32::new(f32::MAX));
-                let mut entries = Vec::new();
+            // rows and range as with the limits of the scenario
+            let filterings = self
+                .scenario
+                .as_deref()
+                .map(|name| state.scenarios.summed(name, state.center));
+            let limit = match self.scenario.as_deref() {
+                Some(name) => state.scenarios.limit(name, limit_key, state.center),
+                None => state.center.limit(limit_key),
+            };
+            if let Some(limit) = limit {
                 let to_color_key = self.to_color.as_ref().and_then(|k| {
                     k.get(state.locked_limits)
                         .1
                         .filter(|&to_color_key| to_color_key != limit_key)
                 });
-                for file_key in state.get_files_for_limit(limit_key) {
-                    let filtering = state.total_filterings.get(file_key);
-                    let file = state.files.get(file_key).and_then(|x| x.get_loaded());
-                    if let (Some((label, file, sorting)), Some(filtering)) = (file, filtering) {
-                        if let Some(column) = sorting.get(limit_key) {
-                            let data = file.get_column(*column);
-                            assert_eq!(data.len(), filtering.len());
-                            let data = data.filter(filtering, min, max);
-                            let to_color = to_color_key
-                                .and_then(|k| sorting.get(k))
-                                .and_then(|column| file.get_column(*column).as_int());
-                            if data.is_empty() {
-                                continue;
-                            }
-                            let data = if let Some(to_color) = to_color {
-                                let min = if let Some(min) = to_color.iter().min() {
-                                    min
-                                } else {
-                                    continue;
-                                };
-                                let max = if let Some(max) = to_color.iter().max() {
-                                    max
-                                } else {
-                                    continue;
-                                };
-                                let mut colors = Vec::new();
-                                for i in *min..(*max + 1) {
-                                    let d = data
-                                        .iter()
-                                        .zip(to_color.iter())
-                                        .filter(|(_, &c)| c == i)
-                                        .map(|(d, _)| *d)
-                                        .collect();
-                                    colors.push((Some(i), d));
-                                }
-                                colors
-                            } else {
-                                vec![(None, data)]
-                            };
-                            entries.push((file_key.clone(), label.clone(), data));
-                        }
+                let files = match &filterings {
+                    Some(filterings) => {
+                        super::scenarios::with_filterings(state.plot_files(), filterings)
                     }
-                }
-                if entries.is_empty() {
-                    return State::Error(LocalizableString {
-                        english: "No data after filtering".into(),
-                    });
-                }
-                let min: FiniteF32 = lower.unwrap_or_else(|| {
-                    entries
-                        .iter()
-                        .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().min()).min())
-                        .min()
-                        .cloned()
-                        .unwrap_or(min)
-                });
-                let max: FiniteF32 = upper.unwrap_or_else(|| {
-                    entries
-                        .iter()
-                        .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().max()).max())
-                        .max()
-                        .cloned()
-                        .unwrap_or(max)
-                });
-                let entries = entries
-                    .into_iter()
-                    .filter_map(|(key, label, data)| {
-                        ColoredDistributionEntry::new(key, label, data, self.resolution, min, max)
-                    })
-                    .collect::<Vec<_>>();
-                let mut colors = entries
-                    .iter()
-                    .flat_map(|e| e.entries.iter().flat_map(|x| x.0))
-                    .collect::<Vec<_>>();
-                colors.sort();
-                let colors = colors
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, c)| (c, state.get_color(i)))
-                    .collect();
-                let (s, r) = std::sync::mpsc::channel();
-                State::Plot(DistributionPlot {
-                    limit_key: limit_key.clone(),
-                    limit_label,
-                    min,
-                    max,
-                    entries,
-                    context_pos: Default::default(),
-                    limit_label_change_sender: s,
-                    limit_label_change_receiver: r,
-                    limit_label_change_value: Default::default(),
-                    colors,
-                    legend_left_top: Default::default(),
-                })
+                    None => state.plot_files(),
+                };
+                State::plot(limit_key, limit, to_color_key, &files, self.resolution)
             } else {
                 State::Error(LocalizableString {
                     english: "No limits available".into(),
@@ -290,6 +415,125 @@ impl DistributionTab {
     }
 }
 
+impl State {
+    fn plot(
+        limit_key: &crate::data_types::LimitKey,
+        limit: &super::limits::Limit,
+        to_color_key: Option<&crate::data_types::LimitKey>,
+        files: &[super::PlotFile],
+        resolution: usize,
+    ) -> Self {
+        let super::limits::LimitData {
+            label: limit_label,
+            lower,
+            upper,
+            info: _,
+            unit: _,
+            data_kind: _,
+        } = limit.data();
+        let min = lower.unwrap_or(FiniteF32::new(f32::MIN));
+        let max = upper.unwrap_or(FiniteF32::new(f32::MAX));
+        let mut entries = Vec::new();
+        for super::PlotFile {
+            key: file_key,
+            label,
+            data: file,
+            sorting,
+            filtering,
+        } in files
+        {
+            if let Some(column) = sorting.get(limit_key) {
+                let data = file.get_column(*column);
+                assert_eq!(data.len(), filtering.len());
+                let data = data.filter(filtering, min, max);
+                let to_color = to_color_key
+                    .and_then(|k| sorting.get(k))
+                    .and_then(|column| file.get_column(*column).as_int());
+                if data.is_empty() {
+                    continue;
+                }
+                let data = if let Some(to_color) = to_color {
+                    let min = if let Some(min) = to_color.iter().min() {
+                        min
+                    } else {
+                        continue;
+                    };
+                    let max = if let Some(max) = to_color.iter().max() {
+                        max
+                    } else {
+                        continue;
+                    };
+                    let mut colors = Vec::new();
+                    for i in *min..(*max + 1) {
+                        let d = data
+                            .iter()
+                            .zip(to_color.iter())
+                            .filter(|(_, &c)| c == i)
+                            .map(|(d, _)| *d)
+                            .collect();
+                        colors.push((Some(i), d));
+                    }
+                    colors
+                } else {
+                    vec![(None, data)]
+                };
+                entries.push(((*file_key).clone(), (*label).clone(), data));
+            }
+        }
+        if entries.is_empty() {
+            return Self::Error(LocalizableString {
+                english: "No data after filtering".into(),
+            });
+        }
+        let min: FiniteF32 = lower.unwrap_or_else(|| {
+            entries
+                .iter()
+                .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().min()).min())
+                .min()
+                .cloned()
+                .unwrap_or(min)
+        });
+        let max: FiniteF32 = upper.unwrap_or_else(|| {
+            entries
+                .iter()
+                .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().max()).max())
+                .max()
+                .cloned()
+                .unwrap_or(max)
+        });
+        let entries = entries
+            .into_iter()
+            .filter_map(|(key, label, data)| {
+                ColoredDistributionEntry::new(key, label, data, resolution, min, max)
+            })
+            .collect::<Vec<_>>();
+        let mut colors = entries
+            .iter()
+            .flat_map(|e| e.entries.iter().flat_map(|x| x.0))
+            .collect::<Vec<_>>();
+        colors.sort();
+        let colors = colors
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| (c, super::distinguishable_color(i)))
+            .collect();
+        let (s, r) = std::sync::mpsc::channel();
+        Self::Plot(DistributionPlot {
+            limit_key: limit_key.clone(),
+            limit_label,
+            min,
+            max,
+            entries,
+            context_pos: Default::default(),
+            limit_label_change_sender: s,
+            limit_label_change_receiver: r,
+            limit_label_change_value: Default::default(),
+            colors,
+            legend_left_top: Default::default(),
+        })
+    }
+}
+
 struct ColoredDistributionEntry {
     key: crate::data_types::FileKey,
     label: crate::data_types::FileLabel,
@@ -317,6 +561,21 @@ impl super::TabTrait for DistributionTab {
             if state.ui_coloring_limit(ui, &mut self.to_color) {
                 self.state = State::NeedsRecompute;
             }
+            if state.scenarios.ui_select(
+                ui,
+                "DistributionScenario",
+                LocalizableStr {
+                    english: "Current limits",
+                }
+                .localize(state.language),
+                LocalizableStr {
+                    english: "Limits whose filtering is plotted",
+                }
+                .localize(state.language),
+                &mut self.scenario,
+            ) {
+                self.state = State::NeedsRecompute;
+            }
         });
 
         if let &State::NeedsRecompute = &self.state {
@@ -390,7 +649,6 @@ impl ColoredDistributionEntry {
 
     fn to_shapes(
         &self,
-        state: &super::AppState,
         to_inner_screen: egui::emath::RectTransform,
         coloring_index: usize,
         entries_count: usize,
@@ -402,7 +660,7 @@ impl ColoredDistributionEntry {
             return Default::default();
         }
         if colors.is_empty() {
-            let color = state.get_color(coloring_index);
+            let color = super::distinguishable_color(coloring_index);
             entries.first().unwrap().1.to_shapes(
                 color,
                 to_inner_screen,
@@ -19,6 +19,16 @@ impl super::TabTrait for DummyTab {
                     Some(std::time::Duration::from_secs(5)),
                 )));
         }
+        if ui.button("Example data").clicked() {
+            let key = state.next_file_key();
+            state
+                .data_events
+                .push(super::DataEvent::File(super::files::FileEvent::FromData {
+                    key,
+                    label: "Example".into(),
+                    data: data_format::DataFormat::example_rectangle_simple(100),
+                }));
+        }
         for i in 1..4 {
             if ui.button("Dialog Buttons").clicked() {
                 state
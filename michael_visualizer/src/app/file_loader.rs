@@ -1,19 +1,192 @@
-use crate::{data_types::FileKey, LocalizableString};
+use crate::{
+    data_types::{FileKey, LimitLabel},
+    LocalizableString,
+};
 
-#[derive(Debug)]
+/// Reasons why an mv01 file could not be parsed
+/// Rows and columns are zero-based, rows count all lines of the file (header and limit block included)
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) enum FileParseError {
-    DummyError(String),
+    InvalidUtf8 {
+        row: usize,
+        column: usize,
+    },
+    MissingHeader,
+    ShortLimitBlock {
+        rows: usize,
+        expected: usize,
+    },
+    ColumnCountMismatch {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    NonNumericCell {
+        row: usize,
+        column: usize,
+        label: LimitLabel,
+        content: String,
+    },
+    BadLimitValue {
+        row: usize,
+        column: usize,
+        label: LimitLabel,
+        bound: LimitBound,
+        content: String,
+    },
+    NoData,
+    /// Columnar files which are broken or cannot be mapped
+    Format {
+        message: String,
+    },
+    Io {
+        row: usize,
+        message: String,
+    },
+    Cancelled,
 }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LimitBound {
+    Lower,
+    Upper,
+}
+impl FileParseError {
+    /// Short description of the broken cell, if the error can be located
+    pub(crate) fn location(&self) -> Option<LocalizableString> {
+        match self {
+            FileParseError::InvalidUtf8 { row, column } => Some(LocalizableString {
+                english: format!(
+                    "line {row}, column {column}",
+                    row = row + 1,
+                    column = column + 1
+                ),
+            }),
+            FileParseError::MissingHeader
+            | FileParseError::ShortLimitBlock { .. }
+            | FileParseError::NoData
+            | FileParseError::Format { .. }
+            | FileParseError::Cancelled => None,
+            FileParseError::ColumnCountMismatch { row, .. } | FileParseError::Io { row, .. } => {
+                Some(LocalizableString {
+                    english: format!("line {row}", row = row + 1),
+                })
+            }
+            FileParseError::NonNumericCell {
+                row, column, label, ..
+            }
+            | FileParseError::BadLimitValue {
+                row, column, label, ..
+            } => Some(LocalizableString {
+                english: format!(
+                    "line {row}, column {column} ({label})",
+                    row = row + 1,
+                    column = column + 1,
+                    label = label.as_str()
+                ),
+            }),
+        }
+    }
+    pub(crate) fn message(&self) -> LocalizableString {
+        let location = self
+            .location()
+            .map(|l| format!("{}: ", l.english))
+            .unwrap_or_default();
+        let english = match self {
+            FileParseError::InvalidUtf8 { .. } => "File is not valid UTF-8".to_string(),
+            FileParseError::MissingHeader => "File is empty, header line is missing".to_string(),
+            FileParseError::ShortLimitBlock { rows, expected } => {
+                format!("Limit block is incomplete, expected {expected} rows but found {rows}")
+            }
+            FileParseError::ColumnCountMismatch {
+                row: _,
+                expected,
+                found,
+            } => format!("Expected {expected} columns, but found {found}"),
+            FileParseError::NonNumericCell { content, .. } => {
+                format!("Value is not a number: '{content}'")
+            }
+            FileParseError::BadLimitValue { bound, content, .. } => format!(
+                "{bound} limit is not a finite number: '{content}'",
+                bound = match bound {
+                    LimitBound::Lower => "Lower",
+                    LimitBound::Upper => "Upper",
+                }
+            ),
+            FileParseError::NoData => {
+                "File contains no data, at most test header description".to_string()
+            }
+            FileParseError::Format { message } => message.clone(),
+            FileParseError::Io { message, .. } => format!("Failed to read: {message}"),
+            FileParseError::Cancelled => "Parsing was cancelled".to_string(),
+        };
+        LocalizableString {
+            english: format!("Failed to parse: {location}{english}"),
+        }
+    }
+}
+
+/// Shared between a worker thread and the UI
+/// The worker publishes how far it got and stops if it was cancelled
+#[derive(Clone, Default)]
+pub(crate) struct Progress {
+    /// Zero if the size is not known (yet)
+    total_bytes: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    bytes_read: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    rows_parsed: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    cancelled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+impl Progress {
+    fn set_total(&self, total_bytes: usize) {
+        self.total_bytes
+            .store(total_bytes, std::sync::atomic::Ordering::Relaxed);
+    }
+    pub(crate) fn total_bytes(&self) -> usize {
+        self.total_bytes.load(std::sync::atomic::Ordering::Relaxed)
+    }
+    pub(crate) fn update(&self, bytes_read: usize, rows_parsed: usize) {
+        use std::sync::atomic::Ordering;
+        self.bytes_read.store(bytes_read, Ordering::Relaxed);
+        self.rows_parsed.store(rows_parsed, Ordering::Relaxed);
+    }
+    pub(crate) fn cancel(&self) {
+        self.cancelled
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(std::sync::atomic::Ordering::Relaxed)
+    }
+    /// Share of the file which was read already, if its size is known
+    pub(crate) fn fraction(&self) -> Option<f32> {
+        use std::sync::atomic::Ordering;
+        let total = self.total_bytes.load(Ordering::Relaxed);
+        (total > 0).then(|| (self.bytes_read.load(Ordering::Relaxed) as f32 / total as f32).min(1.))
+    }
+    pub(crate) fn text(&self) -> LocalizableString {
+        use std::sync::atomic::Ordering;
+        LocalizableString {
+            english: format!(
+                "{mb:.1} MB, {rows} rows",
+                mb = self.bytes_read.load(Ordering::Relaxed) as f64 / 1e6,
+                rows = self.rows_parsed.load(Ordering::Relaxed)
+            ),
+        }
+    }
+}
+
+/// Columnar formats have no parser configuration
+pub(super) type Parsed =
+    Result<(super::files::FileData, Option<super::files::ParserConfig>), FileParseError>;
 
 struct LoadThread {
     key: FileKey,
-    path: std::path::PathBuf,
-    thread: std::thread::JoinHandle<std::io::Result<Vec<u8>>>,
+    progress: Progress,
+    thread: std::thread::JoinHandle<std::io::Result<Parsed>>,
 }
 
 struct Type {
     key: FileKey,
-    thread: std::thread::JoinHandle<Result<super::files::FileData, FileParseError>>,
+    progress: Progress,
+    thread: std::thread::JoinHandle<Parsed>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
@@ -24,33 +197,86 @@ pub(super) struct FileLoader {
     parse_thread: Vec<Type>,
 }
 impl FileLoader {
-    pub(super) fn load(&mut self, key: FileKey, path: std::path::PathBuf) {
+    /// Read and parse in a separate thread, see [`read_path`]
+    pub(super) fn load(
+        &mut self,
+        key: FileKey,
+        path: std::path::PathBuf,
+        parser_config: Option<super::files::ParserConfig>,
+    ) -> Progress {
+        self.cancel(&key);
+        let progress = Progress::default();
+        let worker_progress = progress.clone();
         self.load_thread.push(LoadThread {
             key,
-            path: path.clone(),
-            thread: std::thread::spawn(move || std::fs::read(path)),
-        })
+            progress: progress.clone(),
+            thread: std::thread::spawn(move || read_path(&path, parser_config, &worker_progress)),
+        });
+        progress
     }
-    pub(super) fn parse(&mut self, key: FileKey, bytes: Vec<u8>) {
+    /// Parse in a separate thread, if no parser configuration is given, it is detected from the content
+    pub(super) fn parse(
+        &mut self,
+        key: FileKey,
+        bytes: Vec<u8>,
+        parser_config: Option<super::files::ParserConfig>,
+    ) -> Progress {
+        self.cancel(&key);
+        let progress = Progress::default();
+        progress.set_total(bytes.len());
+        let worker_progress = progress.clone();
         self.parse_thread.push(Type {
             key,
-            thread: std::thread::spawn(move || super::files::FileData::parse(bytes)),
-        })
+            progress: progress.clone(),
+            thread: std::thread::spawn(move || {
+                let progress = worker_progress;
+                if let Some(format) = super::files::ColumnarFormat::from_magic(&bytes) {
+                    return format.read_bytes(bytes, &progress).map(|file| (file, None));
+                }
+                if super::files::has_stdf_magic(&bytes) {
+                    return super::files::read_stdf(bytes.as_slice(), &progress)
+                        .map(|file| (file, None));
+                }
+                let parser_config =
+                    parser_config.unwrap_or_else(|| super::files::ParserConfig::detect(&bytes));
+                super::files::FileData::parse_reader(bytes.as_slice(), &parser_config, &progress)
+                    .map(|file| (file, Some(parser_config)))
+            }),
+        });
+        progress
+    }
+    /// Stop all workers of this file, their results are discarded
+    pub(super) fn cancel(&mut self, key: &FileKey) {
+        for progress in self
+            .load_thread
+            .iter()
+            .filter(|t| &t.key == key)
+            .map(|t| &t.progress)
+            .chain(
+                self.parse_thread
+                    .iter()
+                    .filter(|t| &t.key == key)
+                    .map(|t| &t.progress),
+            )
+        {
+            progress.cancel();
+        }
     }
     #[must_use]
     pub(super) fn check_progress(&mut self) -> Vec<super::DataEvent> {
         let mut events = Vec::new();
         loop {
             if let Some(index) = self.load_thread.iter().position(|t| t.thread.is_finished()) {
-                let LoadThread { key, path, thread } = self.load_thread.remove(index);
-                let label = path
-                    .as_path()
-                    .file_name()
-                    .unwrap_or(path.as_os_str())
-                    .to_string_lossy()
-                    .to_string();
+                let LoadThread {
+                    key,
+                    progress,
+                    thread,
+                } = self.load_thread.remove(index);
+                if progress.is_cancelled() {
+                    continue;
+                }
                 let event = match thread.join() {
-                    Ok(Ok(bytes)) => super::files::FileEvent::ParseFromBytes { key, label, bytes },
+                    Ok(Ok(parsed)) => parsed_event(key, parsed),
                     Ok(Err(err)) => super::files::FileEvent::LoadError {
                         key,
                         msg: LocalizableString {
@@ -70,19 +296,16 @@ impl FileLoader {
                 .iter()
                 .position(|t| t.thread.is_finished())
             {
-                let Type { key, thread } = self.parse_thread.remove(index);
+                let Type {
+                    key,
+                    progress,
+                    thread,
+                } = self.parse_thread.remove(index);
+                if progress.is_cancelled() {
+                    continue;
+                }
                 let event = match thread.join() {
-                    Ok(Ok(file)) => super::files::FileEvent::Loaded {
-                        key,
-                        file,
-                        non_conforming_tooltip: None,
-                    },
-                    Ok(Err(err)) => super::files::FileEvent::LoadError {
-                        key,
-                        msg: LocalizableString {
-                            english: format!("Failed to parse: {err:?}"),
-                        },
-                    },
+                    Ok(parsed) => parsed_event(key, parsed),
                     Err(err) => super::files::FileEvent::LoadError {
                         key,
                         msg: LocalizableString {
@@ -97,3 +320,60 @@ impl FileLoader {
         }
     }
 }
+
+/// Read and parse a file, it is streamed and never held in memory as a whole
+/// If no parser configuration is given, it is detected from the start of the file
+/// Unchanged files are taken from the binary cache instead
+pub(super) fn read_path(
+    path: &std::path::Path,
+    parser_config: Option<super::files::ParserConfig>,
+    progress: &Progress,
+) -> std::io::Result<Parsed> {
+    use std::io::Read;
+    let file = std::fs::File::open(path)?;
+    let metadata = file.metadata()?;
+    progress.set_total(metadata.len() as usize);
+    if let Some(format) = super::files::ColumnarFormat::from_path(path) {
+        return Ok(format.read_path(path, progress).map(|file| (file, None)));
+    }
+    if super::files::is_stdf_path(path) {
+        let reader = std::io::BufReader::new(file);
+        return Ok(super::files::read_stdf(reader, progress).map(|file| (file, None)));
+    }
+    let cache_key = super::files::CacheKey::new(path, &metadata, &parser_config);
+    if let Some((file, parser_config)) = cache_key.as_ref().and_then(|k| k.load()) {
+        progress.update(metadata.len() as usize, file.data_count());
+        return Ok(Ok((file, Some(parser_config))));
+    }
+    let mut reader = std::io::BufReader::new(file);
+    let (parser_config, sample) = match parser_config {
+        Some(parser_config) => (parser_config, Vec::new()),
+        None => super::files::ParserConfig::detect_from_reader(&mut reader)?,
+    };
+    let reader = std::io::Cursor::new(sample).chain(reader);
+    let parsed = super::files::FileData::parse_reader(reader, &parser_config, progress);
+    if let (Ok(file), Some(cache_key)) = (&parsed, cache_key) {
+        cache_key.store(file, &parser_config);
+    }
+    Ok(parsed.map(|file| (file, Some(parser_config))))
+}
+
+fn parsed_event(key: FileKey, parsed: Parsed) -> super::files::FileEvent {
+    match parsed {
+        Ok((file, parser_config)) => super::files::FileEvent::Loaded {
+            key,
+            file,
+            non_conforming_tooltip: parser_config
+                .as_ref()
+                .filter(|parser_config| !parser_config.is_mv01())
+                .map(|parser_config| LocalizableString {
+                    english: format!(
+                        "File is not in mv01 format, it was loaded with\n{layout}",
+                        layout = parser_config.description().english
+                    ),
+                }),
+            parser_config,
+        },
+        Err(error) => super::files::FileEvent::ParseError { key, error },
+    }
+}
@@ -1,9 +1,17 @@
+mod columnar;
+mod file_cache;
 mod file_data;
+mod parser_config;
+mod stdf;
 use crate::{
     data_types::{FileKey, FileLabel},
     Language, LocalizableStr, LocalizableString,
 };
+pub(super) use columnar::ColumnarFormat;
+pub(super) use file_cache::CacheKey;
 pub(super) use file_data::{DataColumn, FileData};
+pub(super) use parser_config::ParserConfig;
+pub(super) use stdf::{has_magic as has_stdf_magic, is_stdf_path, read as read_stdf};
 
 pub(super) enum FileEvent {
     LoadFromPath {
@@ -15,18 +23,31 @@ pub(super) enum FileEvent {
         label: String,
         bytes: Vec<u8>,
     },
+    /// Dataset built in code, it is added as a file without path
+    FromData {
+        key: FileKey,
+        label: String,
+        data: data_format::DataFormat,
+    },
     ToShow(FileKey),
     Remove(FileKey),
     MoveUp(FileKey),
     MoveDown(FileKey),
     Label(FileKey),
+    Reload(FileKey),
+    Cancel(FileKey),
     LoadError {
         key: FileKey,
         msg: LocalizableString,
     },
+    ParseError {
+        key: FileKey,
+        error: super::file_loader::FileParseError,
+    },
     Loaded {
         key: FileKey,
         file: file_data::FileData,
+        parser_config: Option<ParserConfig>,
         non_conforming_tooltip: Option<LocalizableString>,
     },
 }
@@ -42,7 +63,9 @@ pub(super) struct FileContainer {
 }
 impl FileContainer {
     pub(super) fn insert(&mut self, key: FileKey, path: std::path::PathBuf) {
-        let _ = self.files.insert(key, File::from_path(path));
+        if !self.files.contains_key(&key) {
+            let _ = self.files.insert(key, File::from_path(path));
+        }
     }
 
     pub(super) fn make_parsing(&mut self, key: &FileKey, label: &str) {
@@ -55,33 +78,85 @@ impl FileContainer {
         }
     }
 
-    fn move_up_or_down(&mut self, key: &FileKey, steps: isize) {
-        if let Some(from) = self.files.get_index_of(key) {
-            let to = from as isize + steps;
-            if to >= 0 && to < self.files.len() as isize {
-                self.files.move_index(from, to as usize);
-            }
+    /// Returns the file which was passed
+    fn move_up_or_down(&mut self, key: &FileKey, steps: isize) -> Option<FileKey> {
+        let from = self.files.get_index_of(key)?;
+        let to = from as isize + steps;
+        if to >= 0 && to < self.files.len() as isize {
+            self.files.move_index(from, to as usize);
+            self.files.get_index(from).map(|(k, _)| k.clone())
+        } else {
+            None
         }
     }
-    pub(super) fn move_down(&mut self, key: &FileKey) {
-        self.move_up_or_down(key, 1);
+    pub(super) fn move_down(&mut self, key: &FileKey) -> Option<FileKey> {
+        self.move_up_or_down(key, 1)
+    }
+
+    pub(super) fn move_up(&mut self, key: &FileKey) -> Option<FileKey> {
+        self.move_up_or_down(key, -1)
     }
 
-    pub(super) fn move_up(&mut self, key: &FileKey) {
-        self.move_up_or_down(key, -1);
+    /// Returns the position and the file, so it can be inserted again
+    pub(super) fn remove(&mut self, key: &FileKey) -> Option<(usize, File)> {
+        self.files
+            .shift_remove_full(key)
+            .map(|(index, _, file)| (index, file))
     }
 
-    pub(super) fn remove(&mut self, key: &FileKey) {
-        self.files.shift_remove(key);
+    /// Inserts a removed file at its former position, returns the event to load it again
+    pub(super) fn reinsert(
+        &mut self,
+        index: usize,
+        key: FileKey,
+        mut file: File,
+    ) -> Option<FileEvent> {
+        let event = match std::mem::take(&mut file.state) {
+            FileState::Loaded {
+                file: data,
+                limit_sorting: _,
+                non_conforming_tooltip,
+            } => Some(FileEvent::Loaded {
+                key: key.clone(),
+                file: data,
+                parser_config: file.used_parser_config.clone(),
+                non_conforming_tooltip,
+            }),
+            state => match file.original_path.clone() {
+                Some(path) => Some(FileEvent::LoadFromPath {
+                    key: key.clone(),
+                    path,
+                }),
+                // e.g. a file dropped as bytes, its loading was cancelled on removal
+                None => {
+                    file.state = match state {
+                        FileState::Loading | FileState::Parsing => {
+                            FileState::Error(LocalizableString {
+                                english: "Loading was cancelled".to_string(),
+                            })
+                        }
+                        state => state,
+                    };
+                    None
+                }
+            },
+        };
+        file.progress = None;
+        self.files.insert(key, file);
+        self.files
+            .move_index(self.files.len() - 1, index.min(self.files.len() - 1));
+        event
     }
     pub(super) fn make_loaded(
         &mut self,
         key: &FileKey,
         filedata: &FileData,
+        parser_config: &Option<ParserConfig>,
         limit_sorting: std::collections::HashMap<crate::data_types::LimitKey, usize>,
         non_conforming_tooltip: &Option<LocalizableString>,
     ) {
         if let Some(file) = self.files.get_mut(key) {
+            file.used_parser_config = parser_config.clone();
             file.state = FileState::Loaded {
                 file: filedata.clone(),
                 limit_sorting,
@@ -90,16 +165,84 @@ impl FileContainer {
         }
     }
 
+    /// Content of a loaded file changed, e.g. its derived columns
+    pub(super) fn update_loaded(
+        &mut self,
+        key: &FileKey,
+        filedata: FileData,
+        limit_sorting: std::collections::HashMap<crate::data_types::LimitKey, usize>,
+    ) {
+        if let Some(File {
+            state:
+                FileState::Loaded {
+                    file,
+                    limit_sorting: sorting,
+                    non_conforming_tooltip: _,
+                },
+            ..
+        }) = self.files.get_mut(key)
+        {
+            *file = filedata;
+            *sorting = limit_sorting;
+        }
+    }
+
     pub(super) fn make_loaderror(&mut self, key: &FileKey, msg: LocalizableString) {
         if let Some(file) = self.files.get_mut(key) {
             file.state = FileState::Error(msg);
         }
     }
 
+    /// Switch file back to loading, returns the path to load from
+    /// Files without a path (e.g. dropped as bytes) cannot be reloaded
+    pub(super) fn make_reloading(&mut self, key: &FileKey) -> Option<std::path::PathBuf> {
+        let file = self.files.get_mut(key)?;
+        let path = file.original_path.clone()?;
+        file.state = FileState::Loading;
+        Some(path)
+    }
+
+    /// True while any file is loaded or parsed
+    pub(super) fn is_busy(&self) -> bool {
+        self.files
+            .values()
+            .any(|file| matches!(file.state, FileState::Loading | FileState::Parsing))
+    }
+
+    pub(super) fn set_progress(&mut self, key: &FileKey, progress: super::file_loader::Progress) {
+        if let Some(file) = self.files.get_mut(key) {
+            file.progress = Some(progress);
+        }
+    }
+
+    pub(super) fn parser_config(&self, key: &FileKey) -> Option<ParserConfig> {
+        self.files.get(key).and_then(|f| f.parser_config.clone())
+    }
+
+    pub(super) fn make_parse_error(
+        &mut self,
+        key: &FileKey,
+        error: super::file_loader::FileParseError,
+    ) {
+        if let Some(file) = self.files.get_mut(key) {
+            file.state = FileState::ParseError {
+                msg: error.message(),
+                error,
+            };
+        }
+    }
+
     pub(super) fn init(&mut self) -> Vec<super::DataEvent> {
         let mut events = Vec::new();
         for (key, file) in std::mem::take(&mut self.files).into_iter() {
             if let Some(path) = file.original_path {
+                // keep the layout, label and visibility chosen by the user
+                let mut restored = File::from_path(path.clone());
+                restored.parser_config = file.parser_config;
+                restored.label = file.label;
+                restored.label_before = file.label_before;
+                restored.to_show = file.to_show;
+                self.files.insert(key.clone(), restored);
                 events.push(super::DataEvent::File(
                     super::files::FileEvent::LoadFromPath { key, path },
                 ))
@@ -108,24 +251,31 @@ impl FileContainer {
         events
     }
 
-    pub(super) fn get_files_with_limit<'a>(
-        &'a self,
-        limit_key: &'a crate::data_types::LimitKey,
-    ) -> impl Iterator<
-        Item = (
-            &'a FileKey,
-            (
-                &'a FileLabel,
-                &'a FileData,
-                &'a std::collections::HashMap<crate::data_types::LimitKey, usize>,
-            ),
-        ),
-    > + 'a {
-        self.files
-            .iter()
-            .flat_map(|(key, f)| f.get_loaded().map(|f| (key, f)))
-            .filter(move |(_, (_, _, sorting))| sorting.contains_key(limit_key))
+    /// Changes the path of each file which was loaded from disk, e.g. to resolve it relative to a project
+    pub(super) fn map_paths(&mut self, f: impl Fn(&std::path::Path) -> std::path::PathBuf) {
+        for file in self.files.values_mut() {
+            if let Some(path) = file.original_path.as_mut() {
+                *path = f(path);
+            }
+        }
+    }
+
+    /// Copy of the persisted state with the paths changed, e.g. to store them relative to a project
+    pub(super) fn with_paths(&self, f: impl Fn(&std::path::Path) -> std::path::PathBuf) -> Self {
+        Self {
+            files: self
+                .files
+                .iter()
+                .map(|(key, file)| {
+                    (
+                        key.clone(),
+                        file.persisted(file.original_path.as_deref().map(&f)),
+                    )
+                })
+                .collect(),
+        }
     }
+
     pub(crate) fn get(&self, key: &FileKey) -> Option<&File> {
         self.files.get(key)
     }
@@ -159,6 +309,8 @@ impl super::DataEventNotifyable for FileContainer {
             super::DataEvent::Limit(_) => {}
             super::DataEvent::File(_) => {}
             super::DataEvent::Filtering => {}
+            super::DataEvent::Formulas => {}
+            super::DataEvent::Scenarios => {}
             super::DataEvent::FileRequest(event) => match event {
                 FileRequest::Hide(key) => {
                     if let Some(file) = self.files.get_mut(key) {
@@ -192,6 +344,10 @@ pub(super) enum FileState {
     Loading,
     Parsing,
     Error(LocalizableString),
+    ParseError {
+        error: super::file_loader::FileParseError,
+        msg: LocalizableString,
+    },
     Loaded {
         file: file_data::FileData,
         limit_sorting: std::collections::HashMap<crate::data_types::LimitKey, usize>,
@@ -208,6 +364,7 @@ impl FileState {
             FileState::Loading => LocalizableStr { english: "Loading" },
             FileState::Parsing => LocalizableStr { english: "Parsing" },
             FileState::Error(msg) => msg.as_str(),
+            FileState::ParseError { error: _, msg } => msg.as_str(),
             FileState::Loaded {
                 file,
                 limit_sorting: _,
@@ -229,6 +386,7 @@ impl FileState {
             FileState::Loading => None,
             FileState::Parsing => None,
             FileState::Error(_) => None,
+            FileState::ParseError { .. } => None,
             FileState::Loaded {
                 file: a,
                 limit_sorting: b,
@@ -245,6 +403,18 @@ pub(super) struct File {
     label: FileLabel,
     label_before: FileLabel,
     to_show: bool,
+    /// Layout chosen by the user, if none is given, it is detected
+    #[serde(default)]
+    parser_config: Option<ParserConfig>,
+    #[serde(skip)]
+    used_parser_config: Option<ParserConfig>,
+    #[serde(skip)]
+    show_parser_config: bool,
+    #[serde(skip)]
+    export_requested: Option<ColumnarFormat>,
+    /// Progress of the worker thread, while loading or parsing
+    #[serde(skip)]
+    progress: Option<super::file_loader::Progress>,
     #[serde(skip)]
     state: FileState,
 }
@@ -262,6 +432,11 @@ impl File {
             label,
             label_before,
             original_label,
+            parser_config: _,
+            used_parser_config: _,
+            show_parser_config,
+            export_requested,
+            progress,
             state,
         } = self;
         let mut events = Vec::new();
@@ -280,7 +455,9 @@ impl File {
                         .map(|_| (Color32::WHITE, Color32::KHAKI)),
                     FileState::Loading => Some((Color32::BLACK, Color32::LIGHT_BLUE)),
                     FileState::Parsing => Some((Color32::WHITE, Color32::DARK_BLUE)),
-                    FileState::Error(_) => Some((Color32::WHITE, Color32::RED)),
+                    FileState::Error(_) | FileState::ParseError { .. } => {
+                        Some((Color32::WHITE, Color32::RED))
+                    }
                 } {
                     visuals.extreme_bg_color = bg;
                     visuals.faint_bg_color = bg;
@@ -310,6 +487,34 @@ impl File {
                         if ui.button(super::RESET.localize(language)).clicked() {
                             reset_requested = true;
                         }
+                        if ui
+                            .button(
+                                LocalizableStr {
+                                    english: "Parser settings",
+                                }
+                                .localize(language),
+                            )
+                            .clicked()
+                        {
+                            *show_parser_config = !*show_parser_config;
+                            ui.close_menu();
+                        }
+                        if state.get_loaded().is_some() {
+                            ui.menu_button(
+                                LocalizableStr {
+                                    english: "Export filtered data",
+                                }
+                                .localize(language),
+                                |ui| {
+                                    for format in ColumnarFormat::all() {
+                                        if ui.button(format.label().localize(language)).clicked() {
+                                            *export_requested = Some(format);
+                                            ui.close_menu();
+                                        }
+                                    }
+                                },
+                            );
+                        }
                     });
                 if reset_requested {
                     *label = original_label.clone();
@@ -321,10 +526,49 @@ impl File {
                     *label_before = label.clone();
                 }
             }
+            // broken cell
+            if let FileState::ParseError { error, msg: _ } = &*state {
+                if let Some(location) = error.location() {
+                    ui.label(location.localize(language)).on_hover_text(tooltip);
+                }
+            }
+            // progress
+            if let (FileState::Loading | FileState::Parsing, Some(progress)) = (&*state, progress) {
+                let bar = egui::ProgressBar::new(progress.fraction().unwrap_or_default())
+                    .desired_width(150.)
+                    .text(progress.text().localize(language));
+                ui.add(bar);
+                if ui
+                    .button("\u{274C}")
+                    .on_hover_text(LocalizableStr { english: "Cancel" }.localize(language))
+                    .clicked()
+                {
+                    events.push(super::DataEvent::File(FileEvent::Cancel(key.clone())));
+                }
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_millis(100));
+            }
         });
         events
     }
 
+    /// Settings which are serialized, the file has to be loaded again
+    fn persisted(&self, original_path: Option<std::path::PathBuf>) -> File {
+        File {
+            original_path,
+            original_label: self.original_label.clone(),
+            label: self.label.clone(),
+            label_before: self.label_before.clone(),
+            to_show: self.to_show,
+            parser_config: self.parser_config.clone(),
+            used_parser_config: None,
+            show_parser_config: false,
+            export_requested: None,
+            progress: None,
+            state: FileState::Loading,
+        }
+    }
+
     fn from_path(path: std::path::PathBuf) -> File {
         let label: FileLabel = path
             .as_path()
@@ -339,6 +583,11 @@ impl File {
             label: label.clone(),
             label_before: label,
             to_show: true,
+            parser_config: None,
+            used_parser_config: None,
+            show_parser_config: false,
+            export_requested: None,
+            progress: None,
             state: FileState::Loading,
         }
     }
@@ -351,6 +600,11 @@ impl File {
             label: label.clone(),
             label_before: label,
             to_show: true,
+            parser_config: None,
+            used_parser_config: None,
+            show_parser_config: false,
+            export_requested: None,
+            progress: None,
             state: FileState::Loading,
         }
     }
@@ -367,6 +621,11 @@ impl File {
             label,
             label_before: _,
             to_show,
+            parser_config: _,
+            used_parser_config: _,
+            show_parser_config: _,
+            export_requested: _,
+            progress: _,
             state,
         } = self;
         if !*to_show {
@@ -377,6 +636,130 @@ impl File {
     }
 }
 
+impl File {
+    pub(super) fn label(&self) -> &FileLabel {
+        &self.label
+    }
+
+    pub(super) fn is_shown(&self) -> bool {
+        self.to_show
+    }
+
+    #[cfg(test)]
+    pub(super) fn tooltip(&self) -> LocalizableStr<'_> {
+        self.state.tooltip()
+    }
+
+    /// Asks for the target path, returns a dialog if the export failed
+    #[must_use]
+    fn export(
+        &self,
+        format: ColumnarFormat,
+        filtering: Option<&[u32]>,
+        language: Language,
+    ) -> Option<crate::dialog::Dialog> {
+        let (file, _) = self.state.get_loaded()?;
+        let path = rfd::FileDialog::new()
+            .add_filter(format.label().localize(language), &[format.extension()])
+            .set_file_name(&format!(
+                "{label}.{extension}",
+                label = self.label.as_str(),
+                extension = format.extension()
+            ))
+            .save_file()?;
+        let message = format.write(file, filtering, &path).err()?;
+        Some(crate::dialog::Dialog::new(
+            LocalizableStr {
+                english: "Export failed",
+            }
+            .localize(language)
+            .to_string(),
+            Box::new(move |ui| {
+                ui.label(&message);
+                false
+            }),
+            crate::dialog::DialogKind::ok(),
+        ))
+    }
+
+    #[must_use]
+    fn show_parser_config(
+        &mut self,
+        key: &FileKey,
+        ui: &mut egui::Ui,
+        language: Language,
+    ) -> Vec<super::DataEvent> {
+        let mut events = Vec::new();
+        let Self {
+            original_path,
+            parser_config,
+            used_parser_config,
+            ..
+        } = self;
+        ui.group(|ui| {
+            if let Some(format) = original_path.as_deref().and_then(ColumnarFormat::from_path) {
+                ui.label(format!(
+                    "{format}: {text}",
+                    format = format.label().localize(language),
+                    text = LocalizableStr {
+                        english: "layout is given by the file format"
+                    }
+                    .localize(language)
+                ));
+                return;
+            }
+            if original_path.as_deref().map(is_stdf_path).unwrap_or(false) {
+                ui.label(format!(
+                    "STDF: {text}",
+                    text = LocalizableStr {
+                        english: "layout is given by the file format"
+                    }
+                    .localize(language)
+                ));
+                return;
+            }
+            let mut auto_detect = parser_config.is_none();
+            if ui
+                .checkbox(
+                    &mut auto_detect,
+                    LocalizableStr {
+                        english: "Detect layout automatically",
+                    }
+                    .localize(language),
+                )
+                .changed()
+            {
+                *parser_config = if auto_detect {
+                    None
+                } else {
+                    Some(used_parser_config.clone().unwrap_or_default())
+                };
+            }
+            if let Some(parser_config) = parser_config {
+                parser_config.show(ui, language);
+            } else if let Some(used_parser_config) = used_parser_config {
+                ui.label(used_parser_config.description().localize(language));
+            }
+            if ui
+                .add_enabled(
+                    original_path.is_some(),
+                    egui::Button::new(LocalizableStr { english: "Reload" }.localize(language)),
+                )
+                .on_disabled_hover_text(
+                    LocalizableStr {
+                        english: "File was not loaded from a path",
+                    }
+                    .localize(language),
+                )
+                .clicked()
+            {
+                events.push(super::DataEvent::File(FileEvent::Reload(key.clone())));
+            }
+        });
+        events
+    }
+}
+
 fn context_menu_entries(
     ui: &mut egui::Ui,
     language: Language,
@@ -445,12 +828,24 @@ impl super::TabTrait for FileTab {
             language,
             files: FileContainer { files },
             data_events,
+            center,
+            app_events,
             ..
         } = state;
         for (key, file) in files.iter_mut() {
             ui.horizontal(|ui| {
                 data_events.extend(file.show(key, ui, *language));
             });
+            if let Some(format) = file.export_requested.take() {
+                if let Some(dialog) = file.export(format, center.filters_summed(key), *language) {
+                    app_events.push(super::AppEvent::Dialog(dialog));
+                }
+            }
+            if file.show_parser_config {
+                ui.push_id(key, |ui| {
+                    data_events.extend(file.show_parser_config(key, ui, *language));
+                });
+            }
         }
     }
 }
@@ -0,0 +1,414 @@
+use super::file_data::{DataColumn, FileData};
+use crate::app::file_loader::{FileParseError, Progress};
+use crate::app::limits::{LimitData, LimitDataKind};
+use crate::data_types::finite_f32::FiniteF32;
+use crate::{LocalizableStr, LocalizableString};
+use arrow_array::{cast::AsArray, Array, ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+/// Keys of the field metadata which hold the limits of a column
+const LOWER: &str = "lower";
+const UPPER: &str = "upper";
+const INFO: &str = "info";
+const UNIT: &str = "unit";
+/// Key of the schema metadata which holds the free-text header
+const HEADER: &str = "header";
+
+/// Binary formats of column-oriented data
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum ColumnarFormat {
+    Parquet,
+    ArrowIpc,
+}
+
+impl ColumnarFormat {
+    pub(crate) fn all() -> [ColumnarFormat; 2] {
+        [ColumnarFormat::Parquet, ColumnarFormat::ArrowIpc]
+    }
+
+    pub(crate) fn from_path(path: &std::path::Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "parquet" | "pq" => Some(ColumnarFormat::Parquet),
+            "arrow" | "arrows" | "ipc" | "feather" => Some(ColumnarFormat::ArrowIpc),
+            _ => None,
+        }
+    }
+
+    /// Files dropped as bytes have no extension, so they are recognized by their start
+    pub(crate) fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"PAR1") {
+            Some(ColumnarFormat::Parquet)
+        } else if bytes.starts_with(b"ARROW1") || bytes.starts_with(&[0xff; 4]) {
+            Some(ColumnarFormat::ArrowIpc)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn label(&self) -> LocalizableStr<'static> {
+        match self {
+            ColumnarFormat::Parquet => LocalizableStr { english: "Parquet" },
+            ColumnarFormat::ArrowIpc => LocalizableStr {
+                english: "Arrow IPC",
+            },
+        }
+    }
+
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            ColumnarFormat::Parquet => "parquet",
+            ColumnarFormat::ArrowIpc => "arrow",
+        }
+    }
+
+    /// Limits are taken from a sidecar file 'name.limits.csv' if present, otherwise from the field metadata
+    pub(crate) fn read_path(
+        &self,
+        path: &std::path::Path,
+        progress: &Progress,
+    ) -> Result<FileData, FileParseError> {
+        let sidecar = std::fs::read_to_string(path.with_extension("limits.csv"))
+            .map(|text| parse_sidecar(&text))
+            .unwrap_or_default();
+        let mut file = std::fs::File::open(path).map_err(io_error)?;
+        match self {
+            ColumnarFormat::Parquet => read_parquet(file, &sidecar, progress),
+            ColumnarFormat::ArrowIpc => {
+                use std::io::{Read, Seek};
+                let mut magic = [0; 6];
+                let is_file = file.read_exact(&mut magic).is_ok() && &magic == b"ARROW1";
+                file.rewind().map_err(io_error)?;
+                read_arrow_ipc(std::io::BufReader::new(file), is_file, &sidecar, progress)
+            }
+        }
+    }
+
+    pub(crate) fn read_bytes(
+        &self,
+        bytes: Vec<u8>,
+        progress: &Progress,
+    ) -> Result<FileData, FileParseError> {
+        let sidecar = Default::default();
+        match self {
+            ColumnarFormat::Parquet => read_parquet(bytes::Bytes::from(bytes), &sidecar, progress),
+            ColumnarFormat::ArrowIpc => {
+                let is_file = bytes.starts_with(b"ARROW1");
+                read_arrow_ipc(std::io::Cursor::new(bytes), is_file, &sidecar, progress)
+            }
+        }
+    }
+
+    /// Write all rows which pass the filtering, limits are stored as field metadata
+    pub(crate) fn write(
+        &self,
+        file: &FileData,
+        filtering: Option<&[u32]>,
+        path: &std::path::Path,
+    ) -> Result<(), String> {
+        let batch = to_record_batch(file, filtering).map_err(|e| e.to_string())?;
+        let writer = std::fs::File::create(path).map_err(|e| e.to_string())?;
+        match self {
+            ColumnarFormat::Parquet => {
+                let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)
+                    .map_err(|e| e.to_string())?;
+                writer.write(&batch).map_err(|e| e.to_string())?;
+                writer.close().map_err(|e| e.to_string())?;
+            }
+            ColumnarFormat::ArrowIpc => {
+                let mut writer = arrow_ipc::writer::FileWriter::try_new(
+                    std::io::BufWriter::new(writer),
+                    &batch.schema(),
+                )
+                .map_err(|e| e.to_string())?;
+                writer.write(&batch).map_err(|e| e.to_string())?;
+                writer.finish().map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+type Sidecar = std::collections::HashMap<String, std::collections::HashMap<&'static str, String>>;
+
+/// One line per column: label;lower;upper;info;unit, a first line starting with 'label' is skipped
+fn parse_sidecar(text: &str) -> Sidecar {
+    text.lines()
+        .map(|line| line.split(';').map(|c| c.trim()).collect::<Vec<_>>())
+        .filter(|cells| !cells[0].is_empty() && !cells[0].eq_ignore_ascii_case("label"))
+        .map(|cells| {
+            let entries = [LOWER, UPPER, INFO, UNIT]
+                .into_iter()
+                .zip(cells[1..].iter())
+                .filter(|(_, c)| !c.is_empty())
+                .map(|(key, c)| (key, c.to_string()))
+                .collect();
+            (cells[0].to_string(), entries)
+        })
+        .collect()
+}
+
+fn io_error(e: std::io::Error) -> FileParseError {
+    FileParseError::Io {
+        row: 0,
+        message: e.to_string(),
+    }
+}
+
+fn format_error(e: impl std::fmt::Display) -> FileParseError {
+    FileParseError::Format {
+        message: e.to_string(),
+    }
+}
+
+fn read_parquet(
+    reader: impl parquet::file::reader::ChunkReader + 'static,
+    sidecar: &Sidecar,
+    progress: &Progress,
+) -> Result<FileData, FileParseError> {
+    let builder = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(reader)
+        .map_err(format_error)?;
+    let total_rows = builder.metadata().file_metadata().num_rows() as usize;
+    let schema = builder.schema().clone();
+    let batches = builder.build().map_err(format_error)?;
+    read_batches(schema, batches, Some(total_rows), sidecar, progress)
+}
+
+fn read_arrow_ipc(
+    reader: impl std::io::Read + std::io::Seek,
+    is_file: bool,
+    sidecar: &Sidecar,
+    progress: &Progress,
+) -> Result<FileData, FileParseError> {
+    if is_file {
+        let reader = arrow_ipc::reader::FileReader::try_new(reader, None).map_err(format_error)?;
+        let schema = reader.schema();
+        read_batches(schema, reader, None, sidecar, progress)
+    } else {
+        let reader =
+            arrow_ipc::reader::StreamReader::try_new(reader, None).map_err(format_error)?;
+        let schema = reader.schema();
+        read_batches(schema, reader, None, sidecar, progress)
+    }
+}
+
+/// Numeric and boolean columns are imported, all others (e.g. strings) are skipped
+fn read_batches(
+    schema: SchemaRef,
+    batches: impl Iterator<Item = Result<RecordBatch, ArrowError>>,
+    total_rows: Option<usize>,
+    sidecar: &Sidecar,
+    progress: &Progress,
+) -> Result<FileData, FileParseError> {
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| f.data_type().is_numeric() || f.data_type() == &DataType::Boolean)
+        .collect::<Vec<_>>();
+    let mut arrays: Vec<Vec<ArrayRef>> = vec![Vec::new(); columns.len()];
+    let mut rows = 0;
+    for batch in batches {
+        let batch = batch.map_err(format_error)?;
+        for ((index, _), arrays) in columns.iter().zip(arrays.iter_mut()) {
+            arrays.push(batch.column(*index).clone());
+        }
+        rows += batch.num_rows();
+        // bytes are not tracked by the readers, so they are estimated from the rows
+        let bytes_read = total_rows
+            .filter(|&total| total > 0)
+            .map(|total| progress.total_bytes() * rows.min(total) / total)
+            .unwrap_or_default();
+        progress.update(bytes_read, rows);
+        if progress.is_cancelled() {
+            return Err(FileParseError::Cancelled);
+        }
+    }
+    if columns.is_empty() || rows == 0 {
+        return Err(FileParseError::NoData);
+    }
+    let content = columns
+        .into_iter()
+        .zip(arrays)
+        .map(|((_, field), arrays)| {
+            let data = to_column(field.data_type(), &arrays).map_err(format_error)?;
+            let label = field.name();
+            let get = |key: &str| {
+                sidecar
+                    .get(label)
+                    .and_then(|entries| entries.get(key))
+                    .or_else(|| field.metadata().get(key))
+                    .map(|s| s.as_str())
+            };
+            let bound = |key: &str| match get(key) {
+                None | Some("") | Some("-") => Ok(None),
+                Some(s) => s
+                    .parse()
+                    .ok()
+                    .and_then(FiniteF32::new_checked)
+                    .map(Some)
+                    .ok_or_else(|| FileParseError::Format {
+                        message: format!(
+                            "{key} limit of column '{label}' is not a finite number: '{s}'"
+                        ),
+                    }),
+            };
+            let limit = LimitData {
+                label: label.to_string().into(),
+                lower: bound(LOWER)?,
+                upper: bound(UPPER)?,
+                info: LocalizableString {
+                    english: get(INFO).unwrap_or_default().to_string(),
+                },
+                unit: get(UNIT).map(|u| u.to_string()),
+                data_kind: LimitDataKind::new(&data),
+            };
+            Ok((limit, data))
+        })
+        .collect::<Result<Vec<_>, FileParseError>>()?;
+    Ok(FileData::new(
+        LocalizableString {
+            english: schema.metadata().get(HEADER).cloned().unwrap_or_default(),
+        },
+        content,
+    ))
+}
+
+/// Integer columns stay integer, as long as they have no missing values and fit into 32 bits
+fn to_column(data_type: &DataType, arrays: &[ArrayRef]) -> Result<DataColumn, ArrowError> {
+    use arrow_array::types::{Float32Type, Int32Type};
+    if (data_type.is_integer() || data_type == &DataType::Boolean)
+        && arrays.iter().all(|a| a.null_count() == 0)
+    {
+        let options = arrow_cast::CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+        if let Ok(ints) = arrays
+            .iter()
+            .map(|a| arrow_cast::cast_with_options(a, &DataType::Int32, &options))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            return Ok(DataColumn::Int(
+                ints.iter()
+                    .flat_map(|a| a.as_primitive::<Int32Type>().values().iter().copied())
+                    .collect(),
+            ));
+        }
+    }
+    let mut floats = Vec::with_capacity(arrays.iter().map(|a| a.len()).sum());
+    for array in arrays {
+        let array = arrow_cast::cast(array, &DataType::Float32)?;
+        floats.extend(
+            array
+                .as_primitive::<Float32Type>()
+                .iter()
+                .map(|f| f.unwrap_or(f32::NAN)),
+        );
+    }
+    Ok(DataColumn::Float(floats.into_boxed_slice()))
+}
+
+fn to_record_batch(file: &FileData, filtering: Option<&[u32]>) -> Result<RecordBatch, ArrowError> {
+    let keep = |index: usize| filtering.map(|f| f[index] == 0).unwrap_or(true);
+    let mut fields = Vec::new();
+    let mut arrays: Vec<ArrayRef> = Vec::new();
+    for (limit, column) in file.content() {
+        let LimitData {
+            label,
+            lower,
+            upper,
+            info,
+            unit,
+            data_kind: _,
+        } = limit;
+        let metadata = [
+            (LOWER, lower.map(|f| f.inner().to_string())),
+            (UPPER, upper.map(|f| f.inner().to_string())),
+            (INFO, Some(info.english.clone()).filter(|i| !i.is_empty())),
+            (UNIT, unit.clone()),
+        ]
+        .into_iter()
+        .flat_map(|(key, value)| value.map(|v| (key.to_string(), v)))
+        .collect();
+        let (data_type, array): (_, ArrayRef) = match column {
+            DataColumn::Float(data) => (
+                DataType::Float32,
+                std::sync::Arc::new(arrow_array::Float32Array::from_iter_values(
+                    data.iter()
+                        .enumerate()
+                        .filter(|(i, _)| keep(*i))
+                        .map(|(_, f)| *f),
+                )),
+            ),
+            DataColumn::Int(data) => (
+                DataType::Int32,
+                std::sync::Arc::new(arrow_array::Int32Array::from_iter_values(
+                    data.iter()
+                        .enumerate()
+                        .filter(|(i, _)| keep(*i))
+                        .map(|(_, x)| *x),
+                )),
+            ),
+        };
+        fields.push(Field::new(label.as_str(), data_type, false).with_metadata(metadata));
+        arrays.push(array);
+    }
+    let schema = Schema::new(fields).with_metadata(
+        [(HEADER.to_string(), file.tooltip().english.to_string())]
+            .into_iter()
+            .collect(),
+    );
+    RecordBatch::try_new(std::sync::Arc::new(schema), arrays)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ColumnarFormat, FileData};
+    use crate::app::file_loader::Progress;
+    use crate::app::files::ParserConfig;
+
+    #[test]
+    fn columnar_roundtrip() {
+        let csv = "lot 17\nX;Y;Vth\n-;-;0.1\n-;-;0.9\nx;y;threshold\n1;2;0.5\n2;2;0.7\n3;2;1.5";
+        let file = FileData::parse(csv.as_bytes().to_vec(), &ParserConfig::default()).unwrap();
+        // unique per process, so concurrent runs do not collide
+        let directory = std::env::temp_dir().join(format!(
+            "michael_visualizer_columnar_roundtrip_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        for format in ColumnarFormat::all() {
+            let path = directory.join(format!("lot17.{}", format.extension()));
+            assert_eq!(ColumnarFormat::from_path(&path), Some(format));
+            format.write(&file, None, &path).unwrap();
+            let read = format.read_path(&path, &Progress::default()).unwrap();
+            assert_eq!(read.to_csv(), file.to_csv());
+            assert!(read.get_column(0).as_int().is_some());
+            assert!(read.get_column(2).as_int().is_none());
+            // dropped as bytes
+            let bytes = std::fs::read(&path).unwrap();
+            assert_eq!(ColumnarFormat::from_magic(&bytes), Some(format));
+            let read = format.read_bytes(bytes, &Progress::default()).unwrap();
+            assert_eq!(read.to_csv(), file.to_csv());
+            // filtered rows only
+            format.write(&file, Some(&[0, 1, 0]), &path).unwrap();
+            let read = format.read_path(&path, &Progress::default()).unwrap();
+            assert_eq!(read.data_count(), 2);
+            assert_eq!(read.get_column(2).get_as_float(1), 1.5);
+            // sidecar limits take precedence over metadata
+            std::fs::write(
+                path.with_extension("limits.csv"),
+                "label;lower;upper;info;unit\nVth;0.2;-;;V",
+            )
+            .unwrap();
+            let read = format.read_path(&path, &Progress::default()).unwrap();
+            let (limit, _) = &read.content()[2];
+            assert_eq!(limit.lower.map(|x| x.inner()), Some(0.2));
+            assert_eq!(limit.upper, None);
+            assert_eq!(limit.info.english, "threshold");
+            assert_eq!(limit.unit.as_deref(), Some("V"));
+            std::fs::remove_file(path.with_extension("limits.csv")).unwrap();
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,371 @@
+use super::file_data::{DataColumn, FileData};
+use super::parser_config::{MetadataRow, ParserConfig};
+use crate::app::limits::{LimitData, LimitDataKind};
+use crate::data_types::finite_f32::FiniteF32;
+use crate::LocalizableString;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 8] = b"MV01BIN\0";
+/// Increase whenever the layout below changes, older caches are ignored then
+const VERSION: u32 = 1;
+
+/// Identifies a parsed file on disk, a cached result is only used if all of it matches
+#[derive(PartialEq, Debug)]
+pub(crate) struct CacheKey {
+    path: String,
+    size: u64,
+    modified: std::time::Duration,
+    /// Layout requested by the user, None if it was detected
+    parser_config: Option<ParserConfig>,
+}
+
+impl CacheKey {
+    /// None, if the file system does not report a modification time
+    pub(crate) fn new(
+        path: &std::path::Path,
+        metadata: &std::fs::Metadata,
+        parser_config: &Option<ParserConfig>,
+    ) -> Option<Self> {
+        Some(Self {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?,
+            parser_config: parser_config.clone(),
+        })
+    }
+
+    /// One cache file per path, it is overwritten if the file changes
+    fn cache_path(&self) -> Option<std::path::PathBuf> {
+        // same name as the eframe app, so the cache sits next to the persisted state
+        let dirs = directories_next::ProjectDirs::from("", "", "Michael Visualizer")?;
+        Some(dirs.cache_dir().join(format!(
+            "{hash:016x}.mv01bin",
+            hash = fnv1a(self.path.as_bytes())
+        )))
+    }
+
+    pub(crate) fn load(&self) -> Option<(FileData, ParserConfig)> {
+        let bytes = std::fs::read(self.cache_path()?).ok()?;
+        self.read(&mut bytes.as_slice()).ok()?
+    }
+
+    /// Failures are ignored, the file is simply parsed again next time
+    pub(crate) fn store(&self, file: &FileData, parser_config: &ParserConfig) {
+        let Some(path) = self.cache_path() else {
+            return;
+        };
+        let temporary = path.with_extension("tmp");
+        let written = (|| {
+            std::fs::create_dir_all(path.parent().expect("Cache file is inside a directory"))?;
+            let mut writer = std::io::BufWriter::new(std::fs::File::create(&temporary)?);
+            self.write(&mut writer, file, parser_config)?;
+            writer.flush()?;
+            drop(writer);
+            std::fs::rename(&temporary, &path)
+        })();
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temporary);
+        }
+    }
+
+    fn write(
+        &self,
+        w: &mut impl Write,
+        file: &FileData,
+        parser_config: &ParserConfig,
+    ) -> std::io::Result<()> {
+        w.write_all(MAGIC)?;
+        write_u32(w, VERSION)?;
+        self.write_key(w)?;
+        write_parser_config(w, parser_config)?;
+        write_str(w, file.tooltip().english)?;
+        write_u64(w, file.content().len() as u64)?;
+        for (limit, column) in file.content() {
+            let LimitData {
+                label,
+                lower,
+                upper,
+                info,
+                unit,
+                data_kind: _,
+            } = limit;
+            write_str(w, label.as_str())?;
+            write_option_f32(w, lower.map(|f| f.inner()))?;
+            write_option_f32(w, upper.map(|f| f.inner()))?;
+            write_str(w, &info.english)?;
+            write_option_str(w, unit.as_deref())?;
+            match column {
+                DataColumn::Float(data) => {
+                    w.write_all(&[0])?;
+                    write_u64(w, data.len() as u64)?;
+                    for f in data.iter() {
+                        w.write_all(&f.to_le_bytes())?;
+                    }
+                }
+                DataColumn::Int(data) => {
+                    w.write_all(&[1])?;
+                    write_u64(w, data.len() as u64)?;
+                    for i in data.iter() {
+                        w.write_all(&i.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Outer error for broken caches, inner None if the cache belongs to some other state of the file
+    fn read(&self, r: &mut &[u8]) -> std::io::Result<Option<(FileData, ParserConfig)>> {
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u32(r)? != VERSION || &Self::read_key(r)? != self {
+            return Ok(None);
+        }
+        let parser_config = read_parser_config(r)?;
+        let header = read_string(r)?;
+        let columns = read_u64(r)?;
+        let mut content = Vec::new();
+        for _ in 0..columns {
+            let label = read_string(r)?;
+            let lower = read_option_f32(r)?.and_then(FiniteF32::new_checked);
+            let upper = read_option_f32(r)?.and_then(FiniteF32::new_checked);
+            let info = read_string(r)?;
+            let unit = read_option_string(r)?;
+            let kind = read_u8(r)?;
+            let mut bytes = vec![0; read_len(r, 4)?];
+            r.read_exact(&mut bytes)?;
+            let chunks = bytes.chunks_exact(4).map(|b| [b[0], b[1], b[2], b[3]]);
+            let data = match kind {
+                0 => DataColumn::Float(chunks.map(f32::from_le_bytes).collect()),
+                1 => DataColumn::Int(chunks.map(i32::from_le_bytes).collect()),
+                _ => return Err(invalid_data()),
+            };
+            let data_kind = LimitDataKind::new(&data);
+            content.push((
+                LimitData {
+                    label: label.into(),
+                    lower,
+                    upper,
+                    info: LocalizableString { english: info },
+                    unit,
+                    data_kind,
+                },
+                data,
+            ));
+        }
+        Ok(Some((
+            FileData::new(LocalizableString { english: header }, content),
+            parser_config,
+        )))
+    }
+
+    fn write_key(&self, w: &mut impl Write) -> std::io::Result<()> {
+        let Self {
+            path,
+            size,
+            modified,
+            parser_config,
+        } = self;
+        write_str(w, path)?;
+        write_u64(w, *size)?;
+        write_u64(w, modified.as_secs())?;
+        write_u32(w, modified.subsec_nanos())?;
+        match parser_config {
+            Some(parser_config) => {
+                w.write_all(&[1])?;
+                write_parser_config(w, parser_config)
+            }
+            None => w.write_all(&[0]),
+        }
+    }
+
+    fn read_key(r: &mut &[u8]) -> std::io::Result<Self> {
+        Ok(Self {
+            path: read_string(r)?,
+            size: read_u64(r)?,
+            modified: std::time::Duration::new(read_u64(r)?, read_u32(r)?),
+            parser_config: match read_u8(r)? {
+                0 => None,
+                1 => Some(read_parser_config(r)?),
+                _ => return Err(invalid_data()),
+            },
+        })
+    }
+}
+
+fn invalid_data() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, "Broken cache file")
+}
+
+/// 64 bit FNV-1a, stable across Rust releases unlike the hasher of the standard library
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn write_u32(w: &mut impl Write, value: u32) -> std::io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+fn write_u64(w: &mut impl Write, value: u64) -> std::io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+fn write_str(w: &mut impl Write, s: &str) -> std::io::Result<()> {
+    write_u64(w, s.len() as u64)?;
+    w.write_all(s.as_bytes())
+}
+fn write_option_str(w: &mut impl Write, s: Option<&str>) -> std::io::Result<()> {
+    match s {
+        Some(s) => {
+            w.write_all(&[1])?;
+            write_str(w, s)
+        }
+        None => w.write_all(&[0]),
+    }
+}
+fn write_option_f32(w: &mut impl Write, f: Option<f32>) -> std::io::Result<()> {
+    match f {
+        Some(f) => {
+            w.write_all(&[1])?;
+            w.write_all(&f.to_le_bytes())
+        }
+        None => w.write_all(&[0]),
+    }
+}
+fn write_parser_config(w: &mut impl Write, parser_config: &ParserConfig) -> std::io::Result<()> {
+    let ParserConfig {
+        delimiter,
+        decimal_separator,
+        header_lines,
+        metadata_rows,
+    } = parser_config;
+    write_u32(w, *delimiter as u32)?;
+    write_u32(w, *decimal_separator as u32)?;
+    write_u64(w, *header_lines as u64)?;
+    write_u64(w, metadata_rows.len() as u64)?;
+    for row in metadata_rows {
+        let index = MetadataRow::all()
+            .iter()
+            .position(|r| r == row)
+            .expect("All rows are listed");
+        w.write_all(&[index as u8])?;
+    }
+    Ok(())
+}
+
+fn read_u8(r: &mut impl Read) -> std::io::Result<u8> {
+    let mut bytes = [0; 1];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+fn read_u32(r: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+fn read_u64(r: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+/// Byte length of a sequence of `size` bytes per element, it has to fit into the rest of the cache
+fn read_len(r: &mut &[u8], size: usize) -> std::io::Result<usize> {
+    usize::try_from(read_u64(r)?)
+        .ok()
+        .and_then(|len| len.checked_mul(size))
+        .filter(|&len| len <= r.len())
+        .ok_or_else(invalid_data)
+}
+fn read_string(r: &mut &[u8]) -> std::io::Result<String> {
+    let mut bytes = vec![0; read_len(r, 1)?];
+    r.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid_data())
+}
+fn read_option_string(r: &mut &[u8]) -> std::io::Result<Option<String>> {
+    match read_u8(r)? {
+        0 => Ok(None),
+        1 => Ok(Some(read_string(r)?)),
+        _ => Err(invalid_data()),
+    }
+}
+fn read_option_f32(r: &mut impl Read) -> std::io::Result<Option<f32>> {
+    match read_u8(r)? {
+        0 => Ok(None),
+        1 => Ok(Some(f32::from_bits(read_u32(r)?))),
+        _ => Err(invalid_data()),
+    }
+}
+fn read_parser_config(r: &mut &[u8]) -> std::io::Result<ParserConfig> {
+    let to_char = |c: u32| char::from_u32(c).ok_or_else(invalid_data);
+    let delimiter = to_char(read_u32(r)?)?;
+    let decimal_separator = to_char(read_u32(r)?)?;
+    let header_lines = read_u64(r)? as usize;
+    let metadata_rows = (0..read_len(r, 1)?)
+        .map(|_| {
+            MetadataRow::all()
+                .get(read_u8(r)? as usize)
+                .cloned()
+                .ok_or_else(invalid_data)
+        })
+        .collect::<std::io::Result<_>>()?;
+    Ok(ParserConfig {
+        delimiter,
+        decimal_separator,
+        header_lines,
+        metadata_rows,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CacheKey, FileData, ParserConfig};
+
+    #[test]
+    fn cache_roundtrip() {
+        let csv = "header\nX;Y;Vth\n-;-;0.1\n-;-;0.9\nx;y;threshold\n1;2;0.5\n2;2;0.7";
+        let parser_config = ParserConfig::default();
+        let file = FileData::parse(csv.as_bytes().to_vec(), &parser_config).unwrap();
+        let key = CacheKey {
+            path: "lot17.mv01".into(),
+            size: csv.len() as u64,
+            modified: std::time::Duration::new(1_700_000_000, 17),
+            parser_config: None,
+        };
+        let mut bytes = Vec::new();
+        key.write(&mut bytes, &file, &parser_config).unwrap();
+        let (cached, cached_config) = key.read(&mut bytes.as_slice()).unwrap().unwrap();
+        assert_eq!(cached_config, parser_config);
+        assert_eq!(cached.to_csv(), file.to_csv());
+        assert!(cached.get_column(0).as_int().is_some());
+        assert!(cached.get_column(2).as_int().is_none());
+        // changed file
+        let changed = CacheKey {
+            path: key.path.clone(),
+            size: key.size + 1,
+            modified: key.modified,
+            parser_config: None,
+        };
+        assert!(changed.read(&mut bytes.as_slice()).unwrap().is_none());
+        // truncated cache
+        assert!(key.read(&mut &bytes[..bytes.len() - 1]).is_err());
+        // corrupt length of the last column, instead of allocating it the cache is rejected
+        let mut corrupt = bytes.clone();
+        let len = corrupt.len() - 2 * 4 - 8;
+        corrupt[len..len + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        match key.read(&mut corrupt.as_slice()) {
+            Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+            Ok(_) => panic!("Corrupt cache was read"),
+        }
+    }
+
+    #[test]
+    fn cache_path_hash() {
+        // fixed, so caches stay valid with newer compilers
+        assert_eq!(super::fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(super::fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -1,7 +1,12 @@
-use super::super::file_loader::FileParseError;
-use super::super::limits::{Limit, LimitData};
+use super::super::file_loader::{FileParseError, LimitBound, Progress};
+use super::super::limits::{Limit, LimitData, LimitDataKind};
+use super::parser_config::{MetadataRow, ParserConfig};
 use crate::data_types::finite_f32::FiniteF32;
 use crate::{LocalizableStr, LocalizableString};
+use michael_visualizer_basic::Bitset;
+
+/// Rows parsed between two progress updates
+const PROGRESS_INTERVAL_ROWS: usize = 1 << 12;
 
 #[derive(Clone, serde::Deserialize, serde::Serialize, PartialEq)]
 pub(crate) enum DataKind {
@@ -51,13 +56,19 @@ impl DataColumn {
         }
     }
 
-    fn apply_limit(&self, limit: &Limit) -> Vec<bool> {
+    fn apply_limit(&self, limit: &Limit) -> Bitset {
         if limit.data_kind().is_int() && self.kind() == DataKind::Float {
             unreachable!("This case should never happen")
         }
         match self {
-            DataColumn::Float(d) => d.iter().map(|x| limit.is_outside(*x)).collect(),
-            DataColumn::Int(d) => d.iter().map(|x| limit.is_outside(*x as f32)).collect(),
+            DataColumn::Float(d) => {
+                let bounds = limit.sigma_bounds(d.iter().copied());
+                Bitset::from_fn(d.len(), |i| limit.is_outside(d[i], bounds))
+            }
+            DataColumn::Int(d) => {
+                let bounds = limit.sigma_bounds(d.iter().map(|&x| x as f32));
+                Bitset::from_fn(d.len(), |i| limit.is_outside(d[i] as f32, bounds))
+            }
         }
     }
 
@@ -141,22 +152,108 @@ impl From<Vec<f32>> for DataColumn {
     }
 }
 
+/// Collects the values of a column while parsing, it stays integer as long as all values are
+enum ColumnBuilder {
+    Int(Vec<i32>),
+    Float(Vec<f32>),
+}
+impl ColumnBuilder {
+    fn is_empty(&self) -> bool {
+        match self {
+            ColumnBuilder::Int(d) => d.is_empty(),
+            ColumnBuilder::Float(d) => d.is_empty(),
+        }
+    }
+    fn push(&mut self, f: f32) {
+        let is_int = f.round() == f && f >= i32::MIN as f32 && f <= i32::MAX as f32;
+        match self {
+            ColumnBuilder::Int(data) if is_int => data.push(f as i32),
+            ColumnBuilder::Int(data) => {
+                let mut data: Vec<f32> =
+                    std::mem::take(data).into_iter().map(|i| i as f32).collect();
+                data.push(f);
+                *self = ColumnBuilder::Float(data);
+            }
+            ColumnBuilder::Float(data) => data.push(f),
+        }
+    }
+}
+impl From<ColumnBuilder> for DataColumn {
+    fn from(data: ColumnBuilder) -> Self {
+        match data {
+            ColumnBuilder::Int(d) => Self::Int(d.into_boxed_slice()),
+            ColumnBuilder::Float(d) => Self::Float(d.into_boxed_slice()),
+        }
+    }
+}
+
+/// Cheap to clone, the columns are shared between the file list and the data center
 #[derive(Clone)]
 pub(crate) struct FileData {
     header: LocalizableString,
-    content: Vec<(LimitData, DataColumn)>,
+    content: std::sync::Arc<[(LimitData, DataColumn)]>,
+    /// Count of columns at the end, which are computed by formulas
+    derived: usize,
 }
 
 impl FileData {
+    pub(super) fn new(header: LocalizableString, content: Vec<(LimitData, DataColumn)>) -> Self {
+        Self {
+            header,
+            content: content.into(),
+            derived: 0,
+        }
+    }
+
+    /// Column as read from the file, i.e. not derived, with this label
+    pub(crate) fn base_column(&self, label: &str) -> Option<&DataColumn> {
+        self.content[..self.content.len() - self.derived]
+            .iter()
+            .find(|(limit, _)| limit.label.as_str() == label)
+            .map(|(_, column)| column)
+    }
+
+    /// Same file with other derived columns, the previous ones are dropped
+    /// Each column is given with its label and its info
+    pub(crate) fn with_derived(&self, derived: Vec<(String, String, DataColumn)>) -> Self {
+        let count = derived.len();
+        let derived = derived.into_iter().map(|(label, info, column)| {
+            (
+                LimitData {
+                    label: label.into(),
+                    lower: None,
+                    upper: None,
+                    info: LocalizableString { english: info },
+                    unit: None,
+                    data_kind: LimitDataKind::new(&column),
+                },
+                column,
+            )
+        });
+        Self {
+            header: self.header.clone(),
+            content: self.content[..self.content.len() - self.derived]
+                .iter()
+                .cloned()
+                .chain(derived)
+                .collect(),
+            derived: count,
+        }
+    }
+
     pub(super) fn tooltip(&self) -> LocalizableStr {
         self.header.as_str()
     }
 
+    pub(super) fn content(&self) -> &[(LimitData, DataColumn)] {
+        &self.content
+    }
+
     pub(crate) fn limits(&self) -> impl Iterator<Item = Limit> + '_ {
         self.content.iter().map(|(d, _)| Limit::new(d.clone()))
     }
 
-    pub(crate) fn apply_limit(&self, limit: &Limit, column: usize) -> super::super::Filtering {
+    pub(crate) fn apply_limit(&self, limit: &Limit, column: usize) -> Bitset {
         let (_, column) = self
             .content
             .get(column)
@@ -164,97 +261,164 @@ impl FileData {
         column.apply_limit(limit)
     }
 
-    pub(crate) fn parse(bytes: Vec<u8>) -> Result<Self, FileParseError> {
-        let s = std::string::String::from_utf8(bytes)
-            .map_err(|e| FileParseError::DummyError(format!("{e:?}")))?;
-        let mut limits = Vec::new();
-        let mut rows = Vec::new();
-        let mut lines = s.lines();
-        let header = lines.next().unwrap_or_default();
-        for (row_index, line) in lines.enumerate() {
-            let c = line.split(';').map(|x| x.trim());
-            if row_index < 4 {
-                let h = c.collect::<Vec<_>>();
-                if let Some(cc) = limits.first().map(|x: &Vec<&str>| x.len()) {
-                    if h.len() != cc {
-                        return Err(FileParseError::DummyError(format!(
-                            "Not enough columns in row #{row_index}"
-                        )));
-                    }
-                }
-                limits.push(h);
+    /// Parse a file which is already in memory
+    #[cfg(test)]
+    pub(crate) fn parse(bytes: Vec<u8>, config: &ParserConfig) -> Result<Self, FileParseError> {
+        Self::parse_reader(bytes.as_slice(), config, &Progress::default())
+    }
+
+    /// Parse line by line, data values are written directly into their columns
+    /// Progress is published every few thousand rows, cancellation is checked at the same time
+    pub(crate) fn parse_reader<R: std::io::BufRead>(
+        mut reader: R,
+        config: &ParserConfig,
+        progress: &Progress,
+    ) -> Result<Self, FileParseError> {
+        let mut line = Vec::new();
+        let mut row_index = 0;
+        let mut bytes_read = 0;
+        let mut header = Vec::with_capacity(config.header_lines);
+        let mut metadata: Vec<Vec<String>> = Vec::with_capacity(config.metadata_rows.len());
+        let mut columns: Vec<ColumnBuilder> = Vec::new();
+        let mut column_count = None;
+        loop {
+            line.clear();
+            let n = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|e| FileParseError::Io {
+                    row: row_index,
+                    message: e.to_string(),
+                })?;
+            if n == 0 {
+                break;
+            }
+            bytes_read += n;
+            let text = decode_line(&line, row_index, config.delimiter)?;
+            if header.len() < config.header_lines {
+                header.push(text.to_string());
             } else {
-                let mut row = Vec::new();
-                for (column_index, c) in c.enumerate() {
-                    match c.parse::<f32>() {
-                        Ok(f) => row.push(f),
-                        Err(e) => {
-                            return Err(FileParseError::DummyError(format!(
-                                "Failed to parse row #{row_index}, column #{column_index} due to error: {e}"
-                            )))
+                let found = text.split(config.delimiter).count();
+                let expected = *column_count.get_or_insert(found);
+                if found != expected {
+                    return Err(FileParseError::ColumnCountMismatch {
+                        row: row_index,
+                        expected,
+                        found,
+                    });
+                }
+                let cells = text.split(config.delimiter).map(|x| x.trim());
+                if metadata.len() < config.metadata_rows.len() {
+                    metadata.push(cells.map(|c| c.to_string()).collect());
+                } else {
+                    if columns.is_empty() {
+                        columns = (0..expected)
+                            .map(|_| ColumnBuilder::Int(Vec::new()))
+                            .collect();
+                    }
+                    for (column_index, (c, column)) in cells.zip(columns.iter_mut()).enumerate() {
+                        match config.parse_number(c) {
+                            Some(f) => column.push(f),
+                            None => {
+                                return Err(FileParseError::NonNumericCell {
+                                    row: row_index,
+                                    column: column_index,
+                                    label: column_label(config, &metadata, column_index).into(),
+                                    content: c.to_string(),
+                                })
+                            }
                         }
                     }
                 }
-                if limits.iter().map(|x| x.len()).next().unwrap() != row.len() {
-                    return Err(FileParseError::DummyError(format!(
-                        "Not enough columns in row #{row_index}"
-                    )));
+            }
+            row_index += 1;
+            if row_index % PROGRESS_INTERVAL_ROWS == 0 {
+                progress.update(bytes_read, row_index);
+                if progress.is_cancelled() {
+                    return Err(FileParseError::Cancelled);
                 }
-                rows.push(row);
             }
         }
-        if rows.is_empty() {
-            return Err(FileParseError::DummyError(
-                "File contains no data, at most test header description".into(),
-            ));
+        progress.update(bytes_read, row_index);
+        if header.len() < config.header_lines {
+            return Err(FileParseError::MissingHeader);
         }
-        let mut columns = Vec::new();
-        while !limits.first().unwrap().is_empty() {
-            let label = limits.get_mut(0).unwrap().pop().unwrap();
-            let lower = limits.get_mut(1).unwrap().pop().unwrap();
-            let upper = limits.get_mut(2).unwrap().pop().unwrap();
-            let info = limits.get_mut(3).unwrap().pop().unwrap();
-            fn parse(s: &str) -> Result<Option<f32>, FileParseError> {
-                if s.is_empty() || s == "-" {
-                    Ok(None)
-                } else {
-                    match s.parse::<f32>() {
-                        Ok(f) => Ok(Some(f)),
-                        Err(e) => Err(FileParseError::DummyError(format!("Failed to parse: {e}"))),
-                    }
-                }
-            }
-            let lower = parse(lower)?;
-            let upper = parse(upper)?;
-            let data: DataColumn = rows
-                .iter_mut()
-                .map(|r| r.pop().unwrap())
-                .collect::<Vec<_>>()
-                .into();
-            let data_kind = crate::app::limits::LimitDataKind::new(&data);
-            let limit = LimitData {
-                label: label.to_string().into(),
-                lower: lower.and_then(FiniteF32::new_checked),
-                upper: upper.and_then(FiniteF32::new_checked),
-                info: LocalizableString {
-                    english: info.to_string(),
-                },
-                data_kind,
-            };
-            columns.push((limit, data));
+        if metadata.len() < config.metadata_rows.len() {
+            return Err(FileParseError::ShortLimitBlock {
+                rows: metadata.len(),
+                expected: config.metadata_rows.len(),
+            });
+        }
+        if columns.first().map(|c| c.is_empty()).unwrap_or(true) {
+            return Err(FileParseError::NoData);
         }
-        columns.reverse();
+        let cell = |meaning: MetadataRow, column: usize| {
+            config
+                .metadata_rows
+                .iter()
+                .position(|&r| r == meaning)
+                .map(|row| metadata[row][column].as_str())
+        };
+        let content = columns
+            .into_iter()
+            .enumerate()
+            .map(|(column, data)| {
+                let label = column_label(config, &metadata, column);
+                let parse = |bound: LimitBound| -> Result<Option<FiniteF32>, FileParseError> {
+                    let meaning = match bound {
+                        LimitBound::Lower => MetadataRow::Lower,
+                        LimitBound::Upper => MetadataRow::Upper,
+                    };
+                    match cell(meaning, column) {
+                        None | Some("") | Some("-") => Ok(None),
+                        Some(s) => match config.parse_number(s).and_then(FiniteF32::new_checked) {
+                            Some(f) => Ok(Some(f)),
+                            None => Err(FileParseError::BadLimitValue {
+                                row: config.row_of(meaning).unwrap_or_default(),
+                                column,
+                                label: label.clone().into(),
+                                bound,
+                                content: s.to_string(),
+                            }),
+                        },
+                    }
+                };
+                let lower = parse(LimitBound::Lower)?;
+                let upper = parse(LimitBound::Upper)?;
+                let data: DataColumn = data.into();
+                let data_kind = crate::app::limits::LimitDataKind::new(&data);
+                let limit = LimitData {
+                    label: label.into(),
+                    lower,
+                    upper,
+                    info: LocalizableString {
+                        english: cell(MetadataRow::Info, column)
+                            .unwrap_or_default()
+                            .to_string(),
+                    },
+                    unit: cell(MetadataRow::Unit, column)
+                        .filter(|u| !u.is_empty())
+                        .map(|u| u.to_string()),
+                    data_kind,
+                };
+                Ok((limit, data))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(FileData {
             header: LocalizableString {
-                english: header.into(),
+                english: header.join("\n"),
             },
-            content: columns,
+            content: content.into(),
+            derived: 0,
         })
     }
 
     #[cfg(test)]
     pub fn to_csv(&self) -> Vec<String> {
-        let Self { header, content } = self;
+        let Self {
+            header,
+            content,
+            derived: _,
+        } = self;
         let rows = content.first().unwrap().1.len();
         let mut csv = Vec::with_capacity(rows + 5);
         csv.push(header.as_str().english.to_string());
@@ -309,6 +473,142 @@ impl FileData {
     }
 }
 
+impl michael_visualizer_basic::FileTrait for FileData {
+    type Limit = Limit;
+
+    fn limits(&self) -> Vec<Limit> {
+        FileData::limits(self).collect()
+    }
+
+    fn row_count(&self) -> usize {
+        self.data_count()
+    }
+
+    fn apply_limit(&self, limit_index: usize, limit: &Limit) -> Bitset {
+        FileData::apply_limit(self, limit, limit_index)
+    }
+}
+
+/// Die coordinates become the integer columns X and Y in front of the data columns
+impl From<data_format::DataFormat> for FileData {
+    fn from(data: data_format::DataFormat) -> Self {
+        let coordinate = |label: &str, values: &[i32]| {
+            let data = DataColumn::Int(values.into());
+            (
+                LimitData {
+                    label: label.to_string().into(),
+                    lower: None,
+                    upper: None,
+                    info: LocalizableString {
+                        english: String::new(),
+                    },
+                    unit: None,
+                    data_kind: LimitDataKind::new(&data),
+                },
+                data,
+            )
+        };
+        let mut content = vec![
+            coordinate(data_format::COLUMN_LABEL, data.column()),
+            coordinate(data_format::ROW_LABEL, data.row()),
+        ];
+        for label in data.header() {
+            let info = data.info(label).expect("Every column has an info");
+            let mut column = ColumnBuilder::Int(Vec::new());
+            for &f in data.data(label).expect("Every label has data") {
+                column.push(f);
+            }
+            let column = DataColumn::from(column);
+            content.push((
+                LimitData {
+                    label: label.clone().into(),
+                    lower: info.lower.and_then(FiniteF32::new_checked),
+                    upper: info.upper.and_then(FiniteF32::new_checked),
+                    info: LocalizableString {
+                        english: info.info.clone(),
+                    },
+                    unit: info.unit.clone(),
+                    data_kind: LimitDataKind::new(&column),
+                },
+                column,
+            ));
+        }
+        Self::new(
+            LocalizableString {
+                english: data.title().to_string(),
+            },
+            content,
+        )
+    }
+}
+
+/// The integer columns labeled X and Y are taken as die coordinates, all others as data
+impl TryFrom<&FileData> for data_format::DataFormat {
+    type Error = String;
+
+    fn try_from(file: &FileData) -> Result<Self, Self::Error> {
+        let coordinate = |label: &str| {
+            file.content
+                .iter()
+                .position(|(l, d)| {
+                    l.label.as_str().eq_ignore_ascii_case(label) && d.as_int().is_some()
+                })
+                .ok_or_else(|| format!("File has no integer column '{label}'"))
+        };
+        let (x, y) = (
+            coordinate(data_format::COLUMN_LABEL)?,
+            coordinate(data_format::ROW_LABEL)?,
+        );
+        let as_int = |column: usize| {
+            file.get_column(column)
+                .as_int()
+                .expect("Checked above")
+                .to_vec()
+        };
+        let mut data = Self::new(file.header.as_str().english, as_int(x), as_int(y))
+            .map_err(|e| e.to_string())?;
+        for (index, (limit, column)) in file.content.iter().enumerate() {
+            if index == x || index == y {
+                continue;
+            }
+            let info = data_format::ColumnInfo {
+                lower: limit.lower.map(|f| f.inner()),
+                upper: limit.upper.map(|f| f.inner()),
+                info: limit.info.english.clone(),
+                unit: limit.unit.clone(),
+            };
+            data.add_data(limit.label.as_str(), column.iter_float().collect(), info)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(data)
+    }
+}
+
+/// Strip the line break and decode, a decoding error is located by counting delimiters
+fn decode_line(line: &[u8], row: usize, delimiter: char) -> Result<&str, FileParseError> {
+    let line = match line.strip_suffix(b"\n") {
+        Some(line) => line.strip_suffix(b"\r").unwrap_or(line),
+        None => line,
+    };
+    std::str::from_utf8(line).map_err(|e| FileParseError::InvalidUtf8 {
+        row,
+        column: std::str::from_utf8(&line[..e.valid_up_to()])
+            .expect("Prefix is valid by construction")
+            .matches(delimiter)
+            .count(),
+    })
+}
+
+/// Label of a column, a generic one is used if the layout has no label row
+fn column_label(config: &ParserConfig, metadata: &[Vec<String>], column: usize) -> String {
+    config
+        .metadata_rows
+        .iter()
+        .position(|&r| r == MetadataRow::Label)
+        .map(|row| metadata[row][column].clone())
+        .unwrap_or_else(|| format!("Column {column}", column = column + 1))
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -320,7 +620,7 @@ mod test {
         LocalizableString,
     };
 
-    use super::FileData;
+    use super::{FileData, MetadataRow, ParserConfig};
 
     fn get_example(n: usize) -> FileData {
         fn data(
@@ -340,6 +640,7 @@ mod test {
                     info: LocalizableString {
                         english: info.into(),
                     },
+                    unit: None,
                     data_kind,
                 },
                 data,
@@ -350,17 +651,17 @@ mod test {
             1 => vec![0., 0.5, 1., 1.5, 2., 2.5, 3., 3.5, 4.],
             _ => todo!(),
         };
-        FileData {
-            header: LocalizableString {
+        FileData::new(
+            LocalizableString {
                 english: format!("Example #{n}"),
             },
-            content: vec![
+            vec![
                 data("X", None, None, d.clone(), "no boundaries"),
                 data("Y", Some(1.), None, d.clone(), "only lower boundary"),
                 data("Test03", None, Some(2.), d.clone(), "only upper boundary"),
                 data("Test04", Some(1.), Some(2.), d, "both boundaries"),
             ],
-        }
+        )
     }
     #[test]
     fn generate_example_a() {
@@ -372,7 +673,7 @@ mod test {
     fn parse_example_a() {
         let data = get_example(0);
         let csv = data.to_csv().join("\n");
-        FileData::parse(csv.into_bytes()).unwrap();
+        FileData::parse(csv.into_bytes(), &ParserConfig::default()).unwrap();
     }
     #[test]
     fn generate_example_b() {
@@ -384,7 +685,7 @@ mod test {
     fn parse_example_b() {
         let data = get_example(1);
         let csv = data.to_csv().join("\n");
-        FileData::parse(csv.into_bytes()).unwrap();
+        FileData::parse(csv.into_bytes(), &ParserConfig::default()).unwrap();
     }
 
     #[test]
@@ -397,7 +698,7 @@ mod test {
     fn parse_example_middle() {
         let data = get_big_example(10, 20);
         let csv = data.to_csv().join("\n");
-        FileData::parse(csv.into_bytes()).unwrap();
+        FileData::parse(csv.into_bytes(), &ParserConfig::default()).unwrap();
     }
     #[test]
     fn generate_example_big() {
@@ -409,7 +710,165 @@ mod test {
     fn parse_example_big() {
         let data = get_big_example(10, 1_000_000);
         let csv = data.to_csv().join("\n");
-        FileData::parse(csv.into_bytes()).unwrap();
+        FileData::parse(csv.into_bytes(), &ParserConfig::default()).unwrap();
+    }
+
+    #[test]
+    fn parse_errors() {
+        use crate::app::file_loader::{FileParseError, LimitBound};
+        let parse =
+            |s: &str| FileData::parse(s.as_bytes().to_vec(), &ParserConfig::default()).err();
+        assert_eq!(parse(""), Some(FileParseError::MissingHeader));
+        assert_eq!(
+            parse("header\nX;Y\n-;-"),
+            Some(FileParseError::ShortLimitBlock {
+                rows: 2,
+                expected: 4
+            })
+        );
+        assert_eq!(
+            parse("header\nX;Y\n-;-\n-;-\nx;y"),
+            Some(FileParseError::NoData)
+        );
+        assert_eq!(
+            parse("header\nX;Y\n-;-\n-;-\nx;y\n1;2\n3"),
+            Some(FileParseError::ColumnCountMismatch {
+                row: 6,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            parse("header\nX;Y\n-;-\n-;-\nx;y\n1;2\n3;abc"),
+            Some(FileParseError::NonNumericCell {
+                row: 6,
+                column: 1,
+                label: "Y".to_string().into(),
+                content: "abc".into()
+            })
+        );
+        assert_eq!(
+            parse("header\nX;Y\n-;-\n-;inf\nx;y\n1;2"),
+            Some(FileParseError::BadLimitValue {
+                row: 3,
+                column: 1,
+                label: "Y".to_string().into(),
+                bound: LimitBound::Upper,
+                content: "inf".into()
+            })
+        );
+        assert_eq!(
+            FileData::parse(b"header\nX;Y\n-;\xff".to_vec(), &ParserConfig::default()).err(),
+            Some(FileParseError::InvalidUtf8 { row: 2, column: 1 })
+        );
+    }
+
+    #[test]
+    fn parse_detected_layouts() {
+        let detect_and_parse = |s: &str| {
+            let config = ParserConfig::detect(s.as_bytes());
+            let file = FileData::parse(s.as_bytes().to_vec(), &config).unwrap();
+            (config, file)
+        };
+        // mv01
+        let data = get_example(0);
+        let (config, _) = detect_and_parse(&data.to_csv().join("\n"));
+        assert!(config.is_mv01());
+        // plain csv
+        let (config, file) = detect_and_parse("X,Y,Vth\n1,2,0.5\n2,2,0.7\n3,2,0.6");
+        assert_eq!(config.delimiter, ',');
+        assert_eq!(config.header_lines, 0);
+        assert_eq!(config.metadata_rows, vec![MetadataRow::Label]);
+        assert_eq!(file.content[2].0.label.as_str(), "Vth");
+        assert!(file.content[0].1.as_int().is_some());
+        // tab separated, decimal comma, unit row
+        let (config, file) = detect_and_parse(
+            "Lot 17\nX\tVth\n-\t0,1\n-\t0,9\ndie x\tthreshold\n\tV\n1\t0,5\n2\t0,75\n3\t0,25",
+        );
+        assert_eq!(config.delimiter, '\t');
+        assert_eq!(config.decimal_separator, ',');
+        assert_eq!(config.header_lines, 1);
+        assert_eq!(
+            config.metadata_rows,
+            vec![
+                MetadataRow::Label,
+                MetadataRow::Lower,
+                MetadataRow::Upper,
+                MetadataRow::Info,
+                MetadataRow::Unit
+            ]
+        );
+        let (limit, data) = &file.content[1];
+        assert_eq!(limit.upper.map(|x| x.inner()), Some(0.9));
+        assert_eq!(limit.unit.as_deref(), Some("V"));
+        assert_eq!(data.get_as_float(1), 0.75);
+    }
+
+    #[test]
+    fn data_format_roundtrip() {
+        let mut data =
+            data_format::DataFormat::new("Lot 17", vec![1, 2, 3], vec![7, 7, 8]).unwrap();
+        data.add_data(
+            "Vth",
+            vec![0.5, 0.7, f32::NAN],
+            data_format::ColumnInfo::new(Some(0.1), None).with_unit("V"),
+        )
+        .unwrap();
+        data.add_data("Bin", vec![1., 1., 7.], Default::default())
+            .unwrap();
+        let file = FileData::from(data.clone());
+        assert_eq!(file.get_column(1).as_int(), Some(&[7, 7, 8][..]));
+        assert!(file.get_column(2).as_int().is_none());
+        assert!(file.get_column(3).as_int().is_some());
+        let back = data_format::DataFormat::try_from(&file).unwrap();
+        assert_eq!(back.header(), data.header());
+        assert_eq!(back.info("Vth"), data.info("Vth"));
+        assert_eq!(back.data("Bin"), data.data("Bin"));
+        assert!(back.data("Vth").unwrap()[2].is_nan());
+
+        // mv01 written from the dataset is parsed to the same file
+        let mut bytes = Vec::new();
+        data.write_mv01(&mut bytes).unwrap();
+        let parser_config = ParserConfig::detect(&bytes);
+        let parsed = FileData::parse(bytes, &parser_config).unwrap();
+        assert_eq!(parsed.content()[2].0.unit.as_deref(), Some("V"));
+        assert_eq!(parsed.get_column(3).as_int(), Some(&[1, 1, 7][..]));
+    }
+
+    #[test]
+    fn parse_streaming() {
+        use crate::app::file_loader::{FileParseError, Progress};
+        use std::io::Read;
+        let csv = get_big_example(3, 10_000).to_csv().join("\n");
+        // tiny buffer, so lines are split across reads
+        let reader = std::io::BufReader::with_capacity(7, csv.as_bytes());
+        let file =
+            FileData::parse_reader(reader, &ParserConfig::default(), &Progress::default()).unwrap();
+        assert_eq!(file.to_csv().join("\n"), csv);
+        // windows line breaks and layout detected from the start of the stream
+        let crlf = csv.replace('\n', "\r\n");
+        let mut reader = std::io::BufReader::new(crlf.as_bytes());
+        let (config, sample) = ParserConfig::detect_from_reader(&mut reader).unwrap();
+        assert!(config.is_mv01());
+        let reader = std::io::Cursor::new(sample).chain(reader);
+        let file = FileData::parse_reader(reader, &config, &Progress::default()).unwrap();
+        assert_eq!(file.to_csv().join("\n"), csv);
+        // a column becomes float as soon as one value is not integer
+        let file = FileData::parse(
+            b"header\nX;Y\n-;-\n-;-\nx;y\n1;2\n3;4.5".to_vec(),
+            &ParserConfig::default(),
+        )
+        .unwrap();
+        assert!(file.get_column(0).as_int().is_some());
+        assert!(file.get_column(1).as_int().is_none());
+        assert_eq!(file.get_column(1).get_as_float(0), 2.);
+        // cancelled
+        let progress = Progress::default();
+        progress.cancel();
+        assert_eq!(
+            FileData::parse_reader(csv.as_bytes(), &ParserConfig::default(), &progress).err(),
+            Some(FileParseError::Cancelled)
+        );
     }
 
     fn get_big_example(columns: usize, rows: usize) -> FileData {
@@ -425,6 +884,7 @@ mod test {
                 info: LocalizableString {
                     english: "x-axis".to_string(),
                 },
+                unit: None,
                 data_kind: data.limit_data_kind(),
             };
             content.push((limit, data));
@@ -439,6 +899,7 @@ mod test {
                 info: LocalizableString {
                     english: "y-axis".to_string(),
                 },
+                unit: None,
                 data_kind: data.limit_data_kind(),
             };
             content.push((limit, data));
@@ -461,15 +922,16 @@ mod test {
                 info: LocalizableString {
                     english: format!("Tooltip for Test{col:03}"),
                 },
+                unit: None,
                 data_kind: data.limit_data_kind(),
             };
             content.push((limit, data));
         }
-        FileData {
-            header: LocalizableString {
+        FileData::new(
+            LocalizableString {
                 english: format!("Big {columns} {rows}"),
             },
             content,
-        }
+        )
     }
 }
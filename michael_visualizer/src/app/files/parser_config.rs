@@ -0,0 +1,302 @@
+use crate::{Language, LocalizableStr, LocalizableString};
+
+/// Only the start of a file is inspected to detect its layout
+const DETECTION_SAMPLE_BYTES: usize = 1 << 16;
+const DETECTION_SAMPLE_LINES: usize = 64;
+/// Candidates for the delimiter, in ascending priority if several fit equally well
+const DELIMITER_CANDIDATES: [char; 4] = ['|', '\t', ',', ';'];
+
+/// Layout of a delimited text file
+/// The default is the mv01 layout: one free-text header line, followed by the rows label, lower limit, upper limit and info
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub(crate) struct ParserConfig {
+    pub(crate) delimiter: char,
+    pub(crate) decimal_separator: char,
+    pub(crate) header_lines: usize,
+    pub(crate) metadata_rows: Vec<MetadataRow>,
+}
+
+/// Meaning of a row between the free-text header and the data
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MetadataRow {
+    Label,
+    Lower,
+    Upper,
+    Info,
+    Unit,
+    Ignored,
+}
+impl MetadataRow {
+    pub(super) fn all() -> [MetadataRow; 6] {
+        [
+            MetadataRow::Label,
+            MetadataRow::Lower,
+            MetadataRow::Upper,
+            MetadataRow::Info,
+            MetadataRow::Unit,
+            MetadataRow::Ignored,
+        ]
+    }
+    fn as_str(&self) -> LocalizableStr<'static> {
+        match self {
+            MetadataRow::Label => LocalizableStr { english: "Label" },
+            MetadataRow::Lower => LocalizableStr { english: "Lower" },
+            MetadataRow::Upper => LocalizableStr { english: "Upper" },
+            MetadataRow::Info => LocalizableStr { english: "Info" },
+            MetadataRow::Unit => LocalizableStr { english: "Unit" },
+            MetadataRow::Ignored => LocalizableStr { english: "Ignored" },
+        }
+    }
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: ';',
+            decimal_separator: '.',
+            header_lines: 1,
+            metadata_rows: vec![
+                MetadataRow::Label,
+                MetadataRow::Lower,
+                MetadataRow::Upper,
+                MetadataRow::Info,
+            ],
+        }
+    }
+}
+
+impl ParserConfig {
+    pub(crate) fn parse_number(&self, s: &str) -> Option<f32> {
+        if self.decimal_separator == '.' {
+            s.parse().ok()
+        } else {
+            s.replace(self.decimal_separator, ".").parse().ok()
+        }
+    }
+
+    /// Row index within the file of the first metadata row with the given meaning
+    pub(crate) fn row_of(&self, meaning: MetadataRow) -> Option<usize> {
+        self.metadata_rows
+            .iter()
+            .position(|&r| r == meaning)
+            .map(|i| i + self.header_lines)
+    }
+
+    /// Guess the layout from the start of a file
+    /// Falls back to the mv01 layout (with the detected delimiter) if no data rows are found
+    pub(crate) fn detect(bytes: &[u8]) -> Self {
+        let sample = &bytes[..bytes.len().min(DETECTION_SAMPLE_BYTES)];
+        Self::detect_from_sample(sample, sample.len() < bytes.len())
+    }
+
+    /// Guess the layout from the start of a stream
+    /// The inspected bytes are consumed from the reader, they are returned to be parsed afterwards
+    pub(crate) fn detect_from_reader(
+        reader: &mut impl std::io::Read,
+    ) -> std::io::Result<(Self, Vec<u8>)> {
+        use std::io::Read;
+        let mut sample = Vec::with_capacity(DETECTION_SAMPLE_BYTES);
+        reader
+            .by_ref()
+            .take(DETECTION_SAMPLE_BYTES as u64)
+            .read_to_end(&mut sample)?;
+        let truncated = sample.len() == DETECTION_SAMPLE_BYTES;
+        Ok((Self::detect_from_sample(&sample, truncated), sample))
+    }
+
+    fn detect_from_sample(sample: &[u8], truncated: bool) -> Self {
+        let text = String::from_utf8_lossy(sample);
+        let mut lines = text
+            .lines()
+            .take(DETECTION_SAMPLE_LINES)
+            .collect::<Vec<_>>();
+        if truncated && lines.len() > 1 {
+            // last line might be truncated
+            lines.pop();
+        }
+
+        // the delimiter which splits most lines into the same count of columns
+        let delimiter = DELIMITER_CANDIDATES
+            .into_iter()
+            .max_by_key(|&d| {
+                let mut counts = std::collections::HashMap::new();
+                for line in &lines {
+                    let n = line.split(d).count();
+                    if n > 1 {
+                        *counts.entry(n).or_insert(0usize) += 1;
+                    }
+                }
+                counts.into_values().max().unwrap_or(0)
+            })
+            .expect("Candidates are not empty");
+
+        // decimal comma, if numbers only parse with it
+        let cells = || {
+            lines
+                .iter()
+                .flat_map(|l| l.split(delimiter))
+                .map(|c| c.trim())
+        };
+        let with_point = cells()
+            .filter(|c| c.contains('.') && c.parse::<f32>().is_ok())
+            .count();
+        let with_comma = cells()
+            .filter(|c| c.contains(',') && c.replace(',', ".").parse::<f32>().is_ok())
+            .count();
+        let decimal_separator = if delimiter != ',' && with_comma > with_point {
+            ','
+        } else {
+            '.'
+        };
+        let mut config = Self {
+            delimiter,
+            decimal_separator,
+            ..Default::default()
+        };
+
+        // data starts where a few consecutive rows are numeric only
+        let is_numeric = |line: &str| {
+            line.split(delimiter)
+                .all(|c| config.parse_number(c.trim()).is_some())
+        };
+        let data_start = if let Some(data_start) =
+            (0..lines.len()).find(|&i| lines[i..].iter().take(3).all(|l| is_numeric(l)))
+        {
+            data_start
+        } else {
+            return config;
+        };
+        let columns = lines[data_start].split(delimiter).count();
+        let header_lines = lines[..data_start]
+            .iter()
+            .take_while(|l| l.split(delimiter).count() != columns)
+            .count();
+
+        // first row are labels, then limits (numeric) and descriptions (text)
+        let mut metadata_rows: Vec<MetadataRow> = Vec::new();
+        for (index, line) in lines[header_lines..data_start].iter().enumerate() {
+            let is_limit = line
+                .split(delimiter)
+                .map(|c| c.trim())
+                .all(|c| c.is_empty() || c == "-" || config.parse_number(c).is_some());
+            let candidates: &[MetadataRow] = if index == 0 {
+                &[MetadataRow::Label]
+            } else if is_limit {
+                &[MetadataRow::Lower, MetadataRow::Upper]
+            } else {
+                &[MetadataRow::Info, MetadataRow::Unit]
+            };
+            metadata_rows.push(
+                candidates
+                    .iter()
+                    .find(|&c| !metadata_rows.contains(c))
+                    .cloned()
+                    .unwrap_or(MetadataRow::Ignored),
+            );
+        }
+        config.header_lines = header_lines;
+        config.metadata_rows = metadata_rows;
+        config
+    }
+
+    pub(crate) fn is_mv01(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub(crate) fn description(&self) -> LocalizableString {
+        LocalizableString {
+            english: format!(
+                "Delimiter: {delimiter}, decimal separator: '{decimal}', header lines: {header}, rows: {rows}",
+                delimiter = delimiter_label(self.delimiter),
+                decimal = self.decimal_separator,
+                header = self.header_lines,
+                rows = self
+                    .metadata_rows
+                    .iter()
+                    .map(|r| r.as_str().english)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Show editor, changes are applied on the next reload
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, language: Language) {
+        let Self {
+            delimiter,
+            decimal_separator,
+            header_lines,
+            metadata_rows,
+        } = self;
+        ui.horizontal(|ui| {
+            ui.label(
+                LocalizableStr {
+                    english: "Delimiter",
+                }
+                .localize(language),
+            );
+            egui::ComboBox::from_id_source("ParserConfigDelimiter")
+                .selected_text(delimiter_label(*delimiter))
+                .show_ui(ui, |ui| {
+                    for d in DELIMITER_CANDIDATES.into_iter().rev() {
+                        ui.selectable_value(delimiter, d, delimiter_label(d));
+                    }
+                });
+            ui.label(
+                LocalizableStr {
+                    english: "Decimal separator",
+                }
+                .localize(language),
+            );
+            egui::ComboBox::from_id_source("ParserConfigDecimalSeparator")
+                .selected_text(decimal_separator.to_string())
+                .show_ui(ui, |ui| {
+                    for d in ['.', ','] {
+                        ui.selectable_value(decimal_separator, d, d.to_string());
+                    }
+                });
+            ui.label(
+                LocalizableStr {
+                    english: "Header lines",
+                }
+                .localize(language),
+            );
+            ui.add(egui::DragValue::new(header_lines).clamp_range(0..=100));
+        });
+        let mut to_remove = None;
+        for (index, row) in metadata_rows.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{row_label} #{index}",
+                    row_label = LocalizableStr { english: "Row" }.localize(language)
+                ));
+                egui::ComboBox::from_id_source(("ParserConfigRow", index))
+                    .selected_text(row.as_str().localize(language))
+                    .show_ui(ui, |ui| {
+                        for meaning in MetadataRow::all() {
+                            ui.selectable_value(row, meaning, meaning.as_str().localize(language));
+                        }
+                    });
+                if ui.button("\u{1F5D1}").clicked() {
+                    to_remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = to_remove {
+            metadata_rows.remove(index);
+        }
+        if ui
+            .button(LocalizableStr { english: "Add row" }.localize(language))
+            .clicked()
+        {
+            metadata_rows.push(MetadataRow::Ignored);
+        }
+    }
+}
+
+fn delimiter_label(delimiter: char) -> String {
+    match delimiter {
+        '\t' => "Tab".to_string(),
+        d => format!("'{d}'"),
+    }
+}
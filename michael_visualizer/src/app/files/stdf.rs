@@ -0,0 +1,398 @@
+use super::file_data::{DataColumn, FileData};
+use crate::app::file_loader::{FileParseError, Progress};
+use crate::app::limits::{LimitData, LimitDataKind};
+use crate::data_types::finite_f32::FiniteF32;
+use crate::LocalizableString;
+
+/// Record types (REC_TYP, REC_SUB) which are used, all others are skipped
+const FAR: (u8, u8) = (0, 10);
+const MIR: (u8, u8) = (1, 10);
+const PIR: (u8, u8) = (5, 10);
+const PRR: (u8, u8) = (5, 20);
+const PTR: (u8, u8) = (15, 10);
+/// Coordinate of a part without a position on the wafer
+const INVALID_COORDINATE: i16 = i16::MIN;
+/// Records read between two progress updates
+const PROGRESS_INTERVAL_RECORDS: usize = 1 << 12;
+
+pub(crate) fn is_stdf_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| matches!(e.to_lowercase().as_str(), "stdf" | "std"))
+        .unwrap_or(false)
+}
+
+/// Every STDF file starts with a FAR record, which has a length of 2 in either byte order
+pub(crate) fn has_magic(bytes: &[u8]) -> bool {
+    matches!(bytes, [2, 0, 0, 10, ..] | [0, 2, 0, 10, ..])
+}
+
+/// Fields of a single record, trailing fields may be missing and are returned as None then
+struct Fields<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+impl<'a> Fields<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = (self.data.len() >= N).then(|| self.data.split_at(N))?;
+        self.data = rest;
+        let mut bytes: [u8; N] = bytes.try_into().expect("Length is checked");
+        if self.big_endian {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
+    fn u1(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+    fn u2(&mut self) -> Option<u16> {
+        self.take().map(u16::from_le_bytes)
+    }
+    fn u4(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+    fn i2(&mut self) -> Option<i16> {
+        self.take().map(i16::from_le_bytes)
+    }
+    fn r4(&mut self) -> Option<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+    /// String with a leading length byte
+    fn cn(&mut self) -> Option<String> {
+        let len = self.u1()? as usize;
+        let (text, rest) = (self.data.len() >= len).then(|| self.data.split_at(len))?;
+        self.data = rest;
+        Some(String::from_utf8_lossy(text).trim().to_string())
+    }
+}
+
+/// One parametric test, identified by its test number
+struct TestColumn {
+    label: String,
+    lower: Option<f32>,
+    upper: Option<f32>,
+    unit: Option<String>,
+    values: Vec<f32>,
+}
+
+#[derive(Default)]
+struct Part {
+    results: std::collections::HashMap<u32, f32>,
+}
+
+/// Each part (PIR ... PRR) becomes a row, each parametric test (PTR) a column
+/// Die coordinates and bins are added as integer columns
+pub(crate) fn read(
+    mut reader: impl std::io::BufRead,
+    progress: &Progress,
+) -> Result<FileData, FileParseError> {
+    let io_error = |records: usize| {
+        move |e: std::io::Error| FileParseError::Io {
+            row: records,
+            message: e.to_string(),
+        }
+    };
+    // byte order is given by the first record
+    let mut byte_order = None;
+    let mut header = Vec::new();
+    let mut record = Vec::new();
+    let mut records = 0;
+    let mut bytes_read = 0;
+    let mut open_parts: std::collections::HashMap<(u8, u8), Part> = Default::default();
+    let mut tests: indexmap::IndexMap<u32, TestColumn> = Default::default();
+    let (mut x, mut y, mut hard_bin, mut soft_bin) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    loop {
+        if reader.fill_buf().map_err(io_error(records))?.is_empty() {
+            break;
+        }
+        let mut record_header = [0; 4];
+        reader
+            .read_exact(&mut record_header)
+            .map_err(io_error(records))?;
+        let big_endian = match (byte_order, record_header) {
+            (Some(big_endian), _) => big_endian,
+            (None, [2, 0, 0, 10]) => *byte_order.insert(false),
+            (None, [0, 2, 0, 10]) => *byte_order.insert(true),
+            (None, _) => {
+                return Err(FileParseError::Format {
+                    message: "Not an STDF file, it does not start with a FAR record".into(),
+                })
+            }
+        };
+        let length = if big_endian {
+            u16::from_be_bytes([record_header[0], record_header[1]])
+        } else {
+            u16::from_le_bytes([record_header[0], record_header[1]])
+        };
+        record.resize(length as usize, 0);
+        reader.read_exact(&mut record).map_err(io_error(records))?;
+        let mut fields = Fields {
+            data: &record,
+            big_endian,
+        };
+        match (record_header[2], record_header[3]) {
+            FAR => {
+                let _cpu_type = fields.u1();
+                let version = fields.u1().unwrap_or_default();
+                if version != 4 {
+                    return Err(FileParseError::Format {
+                        message: format!("Only STDF V4 is supported, but found V{version}"),
+                    });
+                }
+            }
+            MIR => {
+                // SETUP_T, START_T, STAT_NUM, MODE_COD, RTST_COD, PROT_COD, BURN_TIM, CMOD_COD
+                fields.data = fields.data.get(15..).unwrap_or_default();
+                for name in ["Lot", "Part type", "Node", "Tester", "Job"] {
+                    if let Some(value) = fields.cn().filter(|v| !v.is_empty()) {
+                        header.push(format!("{name}: {value}"));
+                    }
+                }
+            }
+            PIR => {
+                let site = (
+                    fields.u1().unwrap_or_default(),
+                    fields.u1().unwrap_or_default(),
+                );
+                open_parts.insert(site, Part::default());
+            }
+            PTR => read_ptr(&mut fields, &mut open_parts, &mut tests, x.len()),
+            PRR => {
+                let site = (
+                    fields.u1().unwrap_or_default(),
+                    fields.u1().unwrap_or_default(),
+                );
+                let part = open_parts.remove(&site).unwrap_or_default();
+                let _part_flag = fields.u1();
+                let _test_count = fields.u2();
+                hard_bin.push(fields.u2().unwrap_or_default() as i32);
+                soft_bin.push(fields.u2().map(|b| b as i32).unwrap_or(-1));
+                x.push(fields.i2().unwrap_or(INVALID_COORDINATE) as i32);
+                y.push(fields.i2().unwrap_or(INVALID_COORDINATE) as i32);
+                for (test_number, column) in tests.iter_mut() {
+                    column
+                        .values
+                        .push(part.results.get(test_number).cloned().unwrap_or(f32::NAN));
+                }
+            }
+            _ => {}
+        }
+        records += 1;
+        bytes_read += 4 + length as usize;
+        if records % PROGRESS_INTERVAL_RECORDS == 0 {
+            progress.update(bytes_read, x.len());
+            if progress.is_cancelled() {
+                return Err(FileParseError::Cancelled);
+            }
+        }
+    }
+    progress.update(bytes_read, x.len());
+    if x.is_empty() {
+        return Err(FileParseError::NoData);
+    }
+    let mut content = Vec::new();
+    let has_coordinates = x
+        .iter()
+        .zip(y.iter())
+        .any(|(&x, &y)| x != INVALID_COORDINATE as i32 && y != INVALID_COORDINATE as i32);
+    let integer_columns = [
+        ("X", "Die x coordinate", x),
+        ("Y", "Die y coordinate", y),
+        ("Hard bin", "Hardware bin", hard_bin),
+        ("Soft bin", "Software bin", soft_bin),
+    ];
+    for (index, (label, info, data)) in integer_columns.into_iter().enumerate() {
+        if index < 2 && !has_coordinates {
+            continue;
+        }
+        content.push(column(
+            label.into(),
+            None,
+            None,
+            info.into(),
+            None,
+            DataColumn::Int(data.into()),
+        ));
+    }
+    for (test_number, test) in tests {
+        let TestColumn {
+            label,
+            lower,
+            upper,
+            unit,
+            values,
+        } = test;
+        content.push(column(
+            label,
+            lower,
+            upper,
+            format!("Test number {test_number}"),
+            unit,
+            DataColumn::Float(values.into()),
+        ));
+    }
+    Ok(FileData::new(
+        LocalizableString {
+            english: header.join("\n"),
+        },
+        content,
+    ))
+}
+
+fn read_ptr(
+    fields: &mut Fields,
+    open_parts: &mut std::collections::HashMap<(u8, u8), Part>,
+    tests: &mut indexmap::IndexMap<u32, TestColumn>,
+    finished_parts: usize,
+) {
+    let (Some(test_number), Some(head), Some(site), Some(test_flag), Some(_parm_flag)) = (
+        fields.u4(),
+        fields.u1(),
+        fields.u1(),
+        fields.u1(),
+        fields.u1(),
+    ) else {
+        return;
+    };
+    let result = fields.r4();
+    let text = fields.cn();
+    let _alarm = fields.cn();
+    let optional_flag = fields.u1();
+    // RES_SCAL, LLM_SCAL, HLM_SCAL: values are stored unscaled, so they are ignored
+    fields.data = fields.data.get(3..).unwrap_or_default();
+    let lower = fields.r4();
+    let upper = fields.r4();
+    let unit = fields.cn();
+    // the first record of a test defines its label, limits and unit, later ones may omit them
+    let test = tests.entry(test_number).or_insert_with(|| TestColumn {
+        label: match text.as_deref() {
+            Some(text) if !text.is_empty() => format!("{test_number} {text}"),
+            _ => format!("Test {test_number}"),
+        },
+        lower: None,
+        upper: None,
+        unit: None,
+        values: vec![f32::NAN; finished_parts],
+    });
+    if let Some(optional_flag) = optional_flag {
+        // bit 4/5: lower/upper limit is not valid in this record, bit 6/7: no lower/upper limit
+        if optional_flag & 0x50 == 0 {
+            test.lower = lower.or(test.lower);
+        }
+        if optional_flag & 0xa0 == 0 {
+            test.upper = upper.or(test.upper);
+        }
+    }
+    if test.unit.is_none() {
+        test.unit = unit.filter(|u| !u.is_empty());
+    }
+    // bit 1: result is not valid, bit 4: test was not executed
+    if let Some(result) = result.filter(|_| test_flag & 0x12 == 0) {
+        open_parts
+            .entry((head, site))
+            .or_default()
+            .results
+            .insert(test_number, result);
+    }
+}
+
+fn column(
+    label: String,
+    lower: Option<f32>,
+    upper: Option<f32>,
+    info: String,
+    unit: Option<String>,
+    data: DataColumn,
+) -> (LimitData, DataColumn) {
+    (
+        LimitData {
+            label: label.into(),
+            lower: lower.and_then(FiniteF32::new_checked),
+            upper: upper.and_then(FiniteF32::new_checked),
+            info: LocalizableString { english: info },
+            unit,
+            data_kind: LimitDataKind::new(&data),
+        },
+        data,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{has_magic, read};
+    use crate::app::file_loader::Progress;
+
+    /// Little endian record, the length is filled in
+    fn record(kind: (u8, u8), body: &[u8]) -> Vec<u8> {
+        let mut bytes = (body.len() as u16).to_le_bytes().to_vec();
+        bytes.extend([kind.0, kind.1]);
+        bytes.extend(body);
+        bytes
+    }
+    fn cn(text: &str) -> Vec<u8> {
+        let mut bytes = vec![text.len() as u8];
+        bytes.extend(text.as_bytes());
+        bytes
+    }
+    fn ptr(test_number: u32, site: u8, result: f32, first: bool) -> Vec<u8> {
+        let mut body = test_number.to_le_bytes().to_vec();
+        body.extend([1, site, 0, 0]);
+        body.extend(result.to_le_bytes());
+        if first {
+            body.extend(cn("Vth"));
+            body.extend(cn(""));
+            // OPT_FLAG: upper limit not valid
+            body.extend([0x20, 0, 0, 0]);
+            body.extend(0.1f32.to_le_bytes());
+            body.extend(0f32.to_le_bytes());
+            body.extend(cn("V"));
+        }
+        record(super::PTR, &body)
+    }
+    fn prr(site: u8, hard_bin: u16, x: i16, y: i16) -> Vec<u8> {
+        let mut body = vec![1, site, 0, 1, 0];
+        body.extend(hard_bin.to_le_bytes());
+        body.extend(hard_bin.to_le_bytes());
+        body.extend(x.to_le_bytes());
+        body.extend(y.to_le_bytes());
+        record(super::PRR, &body)
+    }
+
+    #[test]
+    fn read_stdf() {
+        let mut bytes = record(super::FAR, &[2, 4]);
+        let mut mir = vec![0; 15];
+        mir.extend(cn("LOT17"));
+        bytes.extend(record(super::MIR, &mir));
+        // two sites tested in parallel
+        bytes.extend(record(super::PIR, &[1, 0]));
+        bytes.extend(record(super::PIR, &[1, 1]));
+        bytes.extend(ptr(100, 0, 0.5, true));
+        bytes.extend(ptr(100, 1, 0.05, false));
+        bytes.extend(prr(0, 1, 3, 4));
+        bytes.extend(prr(1, 7, 4, 4));
+        // a part without results
+        bytes.extend(record(super::PIR, &[1, 0]));
+        bytes.extend(prr(0, 9, 5, 4));
+        assert!(has_magic(&bytes));
+
+        let file = read(bytes.as_slice(), &Progress::default()).unwrap();
+        assert_eq!(file.tooltip().english, "Lot: LOT17");
+        let labels = file
+            .content()
+            .iter()
+            .map(|(l, _)| l.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["X", "Y", "Hard bin", "Soft bin", "100 Vth"]);
+        assert_eq!(file.get_column(0).as_int(), Some(&[3, 4, 5][..]));
+        assert_eq!(file.get_column(2).as_int(), Some(&[1, 7, 9][..]));
+        let (limit, data) = &file.content()[4];
+        assert_eq!(limit.lower.map(|x| x.inner()), Some(0.1));
+        assert_eq!(limit.upper, None);
+        assert_eq!(limit.unit.as_deref(), Some("V"));
+        assert_eq!(data.get_as_float(0), 0.5);
+        assert_eq!(data.get_as_float(1), 0.05);
+        assert!(data.get_as_float(2).is_nan());
+    }
+}
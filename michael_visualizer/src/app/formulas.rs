@@ -0,0 +1,269 @@
+mod expression;
+use super::files::{DataColumn, FileData};
+use super::DataEvent;
+use crate::LocalizableStr;
+use expression::Expression;
+
+/// Virtual column, computed from other columns of the same file
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub(super) struct Formula {
+    label: String,
+    expression: String,
+}
+
+/// Formulas in the order they are computed, a formula can use the columns of earlier ones
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(super) struct Formulas {
+    formulas: Vec<Formula>,
+}
+impl Formulas {
+    /// Adds the derived columns to a file, replacing the previous ones
+    /// A formula is skipped, if the file lacks one of its columns
+    pub(super) fn derive(&self, data: &FileData) -> FileData {
+        let rows = data.data_count();
+        let mut derived: Vec<(String, String, DataColumn)> = Vec::new();
+        for Formula { label, expression } in &self.formulas {
+            let Ok(parsed) = Expression::parse(expression) else {
+                continue;
+            };
+            let values = {
+                // in the order of the columns of the expression
+                let columns = parsed
+                    .columns()
+                    .into_iter()
+                    .map(|column| {
+                        data.base_column(column).or_else(|| {
+                            derived
+                                .iter()
+                                .find(|(label, _, _)| label == column)
+                                .map(|(_, _, data)| data)
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>();
+                let Some(columns) = columns else {
+                    continue;
+                };
+                let mut row_values = vec![0.; columns.len()];
+                (0..rows)
+                    .map(|row| {
+                        for (value, column) in row_values.iter_mut().zip(&columns) {
+                            *value = column.get_as_float(row) as f64;
+                        }
+                        parsed.evaluate(&row_values) as f32
+                    })
+                    .collect::<Box<[f32]>>()
+            };
+            // float even for integral results, so all files agree on the kind
+            derived.push((
+                label.clone(),
+                format!("{label} = {expression}"),
+                DataColumn::Float(values),
+            ));
+        }
+        data.with_derived(derived)
+    }
+
+    fn position(&self, label: &str) -> Option<usize> {
+        self.formulas.iter().position(|f| f.label == label)
+    }
+
+    /// Adds a formula, or replaces the expression of the formula with this label
+    /// `is_column` tells whether a label is used by a column of a loaded file
+    fn set(
+        &mut self,
+        label: &str,
+        expression: &str,
+        is_column: impl Fn(&str) -> bool,
+    ) -> Result<(), String> {
+        let label = label.trim();
+        if label.is_empty() {
+            return Err("Label is empty".into());
+        }
+        let position = self.position(label);
+        if position.is_none() && is_column(label) {
+            return Err(format!("Label '{label}' is already used by a column"));
+        }
+        let parsed = Expression::parse(expression)?;
+        if parsed.columns().contains(&label) {
+            return Err(format!("Formula '{label}' uses its own column"));
+        }
+        let formula = Formula {
+            label: label.to_string(),
+            expression: expression.trim().to_string(),
+        };
+        match position {
+            Some(position) => self.formulas[position] = formula,
+            None => self.formulas.push(formula),
+        }
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(super) struct FormulaTab {
+    label: String,
+    expression: String,
+    #[serde(skip)]
+    error: Option<String>,
+}
+impl super::TabTrait for FormulaTab {
+    fn title(&self, state: &super::AppState) -> &str {
+        LocalizableStr {
+            english: "Formulas",
+        }
+        .localize(state.language)
+    }
+
+    fn show(&mut self, state: &mut super::AppState, ui: &mut egui::Ui) {
+        let super::AppState {
+            language,
+            formulas,
+            files,
+            data_events,
+            ..
+        } = state;
+        let language = *language;
+        ui.label(
+            LocalizableStr {
+                english: "Derived columns are computed from the columns of each file, e.g. radius = sqrt(X^2 + Y^2)\nOperators: + - * / ^, functions: abs, sqrt, exp, ln, log10, sin, cos, tan, round, floor, ceil, min, max\nLabels with spaces or other special characters are quoted, e.g. \"Vth after\" - \"Vth before\"",
+            }
+            .localize(language),
+        );
+        ui.separator();
+        let is_new = formulas.position(self.label.trim()).is_none();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.label)
+                    .hint_text(LocalizableStr { english: "Label" }.localize(language))
+                    .desired_width(120.),
+            );
+            ui.label("=");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.expression).hint_text(
+                    LocalizableStr {
+                        english: "Expression",
+                    }
+                    .localize(language),
+                ),
+            );
+            let text = if is_new {
+                LocalizableStr { english: "Add" }
+            } else {
+                LocalizableStr { english: "Replace" }
+            };
+            if ui.button(text.localize(language)).clicked() {
+                let is_column = |label: &str| {
+                    files
+                        .iter_loaded()
+                        .any(|(_, (_, data, _))| data.base_column(label).is_some())
+                };
+                match formulas.set(&self.label, &self.expression, is_column) {
+                    Ok(()) => {
+                        self.label.clear();
+                        self.expression.clear();
+                        self.error = None;
+                        data_events.push(DataEvent::Formulas);
+                    }
+                    Err(error) => self.error = Some(error),
+                }
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.separator();
+        let mut to_remove = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("Formulas").striped(true).show(ui, |ui| {
+                for (index, Formula { label, expression }) in formulas.formulas.iter().enumerate() {
+                    ui.label(label);
+                    ui.label(format!("= {expression}"));
+                    if ui
+                        .button(LocalizableStr { english: "Edit" }.localize(language))
+                        .clicked()
+                    {
+                        self.label = label.clone();
+                        self.expression = expression.clone();
+                        self.error = None;
+                    }
+                    if ui.button("\u{1F5D1}").clicked() {
+                        to_remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(index) = to_remove {
+            formulas.formulas.remove(index);
+            data_events.push(DataEvent::Formulas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::{app_with_example, DataEvent};
+
+    #[test]
+    fn derived_columns() {
+        let (mut app, key) = app_with_example("Example");
+        let limit_count = app.center.limits().count();
+        let no_column = |_: &str| false;
+        assert!(app
+            .formulas
+            .set("radius", "sqrt(X^2 + Y^2", no_column)
+            .is_err());
+        assert!(app.formulas.set("radius", "2 * radius", no_column).is_err());
+        assert!(app.formulas.set("X", "Y", |label| label == "X").is_err());
+        app.formulas
+            .set("radius", "sqrt(X^2 + Y^2)", no_column)
+            .unwrap();
+        app.formulas.set("twice", "2 * radius", no_column).unwrap();
+        app.formulas
+            .set("missing", "2 * nothing", no_column)
+            .unwrap();
+        app.data_events.push(DataEvent::Formulas);
+        app.process_data_events();
+
+        // the file keeps its position, the formulas are new limits
+        assert_eq!(app.center.files().count(), 1);
+        assert_eq!(app.center.limits().count(), limit_count + 2);
+        let data = app.center.file(&key).unwrap();
+        let indices = app.center.limit_indices(&key).unwrap();
+        let column = |label: &str| {
+            let (limit_key, _) = app
+                .center
+                .limits()
+                .find(|(_, limit)| limit.get_label().as_str() == label)
+                .unwrap();
+            data.get_column(indices[limit_key])
+        };
+        for row in 0..data.data_count() {
+            let (x, y) = (column("X").get_as_float(row), column("Y").get_as_float(row));
+            let radius = column("radius").get_as_float(row);
+            assert_eq!(radius, (x * x + y * y).sqrt());
+            assert_eq!(column("twice").get_as_float(row), 2. * radius);
+        }
+
+        // replacing keeps the column count
+        app.formulas.set("twice", "3 * radius", no_column).unwrap();
+        app.data_events.push(DataEvent::Formulas);
+        app.process_data_events();
+        assert_eq!(
+            app.center.limit_indices(&key).unwrap().len(),
+            limit_count + 2
+        );
+
+        // removing a formula removes its limit
+        app.formulas
+            .formulas
+            .retain(|formula| formula.label != "twice");
+        app.data_events.push(DataEvent::Formulas);
+        app.process_data_events();
+        assert_eq!(app.center.limits().count(), limit_count + 1);
+        assert!(app
+            .center
+            .limits()
+            .all(|(_, limit)| limit.get_label().as_str() != "twice"));
+    }
+}
@@ -0,0 +1,406 @@
+/// Deeper nesting of parentheses, signs and powers is rejected, so evaluating cannot overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// Parsed formula, e.g. `sqrt(X^2 + Y^2)`
+/// Columns are given by their label, labels with other characters than letters, digits and '_'
+/// are quoted, e.g. `"Vth after" - "Vth before"`
+#[derive(Debug, PartialEq)]
+pub(super) struct Expression {
+    root: Node,
+    /// Labels of the columns used, each once, nodes refer to them by index
+    columns: Vec<String>,
+}
+#[derive(Debug, PartialEq)]
+enum Node {
+    Number(f64),
+    Column(usize),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Function(Function, Vec<Node>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum Function {
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Sin,
+    Cos,
+    Tan,
+    Round,
+    Floor,
+    Ceil,
+    Min,
+    Max,
+}
+impl Function {
+    fn all() -> [Function; 13] {
+        use Function::*;
+        [
+            Abs, Sqrt, Exp, Ln, Log10, Sin, Cos, Tan, Round, Floor, Ceil, Min, Max,
+        ]
+    }
+
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Function::Abs => "abs",
+            Function::Sqrt => "sqrt",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+            Function::Log10 => "log10",
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Round => "round",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Min => "min",
+            Function::Max => "max",
+        }
+    }
+
+    fn arguments(self) -> usize {
+        match self {
+            Function::Min | Function::Max => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, arguments: &[f64]) -> f64 {
+        let x = arguments[0];
+        match self {
+            Function::Abs => x.abs(),
+            Function::Sqrt => x.sqrt(),
+            Function::Exp => x.exp(),
+            Function::Ln => x.ln(),
+            Function::Log10 => x.log10(),
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Round => x.round(),
+            Function::Floor => x.floor(),
+            Function::Ceil => x.ceil(),
+            Function::Min => x.min(arguments[1]),
+            Function::Max => x.max(arguments[1]),
+        }
+    }
+}
+
+impl Expression {
+    pub(super) fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            position: 0,
+            depth: 0,
+            columns: Vec::new(),
+        };
+        let root = parser.sum()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(Self {
+                root,
+                columns: parser.columns,
+            }),
+            Some(c) => Err(parser.unexpected(c)),
+        }
+    }
+
+    /// Labels of the columns used, each once
+    pub(super) fn columns(&self) -> Vec<&str> {
+        self.columns.iter().map(String::as_str).collect()
+    }
+
+    /// Value for one row, `values` are the values of the [`Self::columns`] in this row
+    pub(super) fn evaluate(&self, values: &[f64]) -> f64 {
+        self.root.evaluate(values)
+    }
+}
+impl Node {
+    fn evaluate(&self, values: &[f64]) -> f64 {
+        match self {
+            Node::Number(x) => *x,
+            Node::Column(index) => values[*index],
+            Node::Negate(x) => -x.evaluate(values),
+            Node::Binary(operator, a, b) => {
+                let (a, b) = (a.evaluate(values), b.evaluate(values));
+                match operator {
+                    Operator::Add => a + b,
+                    Operator::Subtract => a - b,
+                    Operator::Multiply => a * b,
+                    Operator::Divide => a / b,
+                    Operator::Power => a.powf(b),
+                }
+            }
+            Node::Function(function, arguments) => function.apply(
+                &arguments
+                    .iter()
+                    .map(|x| x.evaluate(values))
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+}
+
+/// Recursive descent, the precedence is: sum < product < sign < power
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    /// Current nesting, see [`MAX_DEPTH`]
+    depth: usize,
+    columns: Vec<String>,
+}
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips whitespace, then consumes `c` if it is next
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self, c: char) -> String {
+        format!("Unexpected '{c}' at position {}", self.position + 1)
+    }
+
+    /// Index of the column, it is added when used first
+    fn column(&mut self, label: String) -> Node {
+        let index = match self.columns.iter().position(|c| *c == label) {
+            Some(index) => index,
+            None => {
+                self.columns.push(label);
+                self.columns.len() - 1
+            }
+        };
+        Node::Column(index)
+    }
+
+    fn sum(&mut self) -> Result<Node, String> {
+        let mut expression = self.product()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(expression);
+            };
+            expression = Node::Binary(operator, expression.into(), self.product()?.into());
+        }
+    }
+
+    fn product(&mut self) -> Result<Node, String> {
+        let mut expression = self.sign()?;
+        loop {
+            let operator = if self.eat('*') {
+                Operator::Multiply
+            } else if self.eat('/') {
+                Operator::Divide
+            } else {
+                return Ok(expression);
+            };
+            expression = Node::Binary(operator, expression.into(), self.sign()?.into());
+        }
+    }
+
+    /// `-x^2` is `-(x^2)`
+    /// Each nesting passes here, so the depth is checked here
+    fn sign(&mut self) -> Result<Node, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!(
+                "Formula is nested too deeply at position {}",
+                self.position + 1
+            ));
+        }
+        self.depth += 1;
+        let node = if self.eat('-') {
+            self.sign().map(|x| Node::Negate(x.into()))
+        } else if self.eat('+') {
+            self.sign()
+        } else {
+            self.power()
+        };
+        self.depth -= 1;
+        node
+    }
+
+    /// Right associative, i.e. `2^3^2` is `2^(3^2)`
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            Ok(Node::Binary(
+                Operator::Power,
+                base.into(),
+                self.sign()?.into(),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("Unexpected end of formula".into()),
+            Some('(') => {
+                self.position += 1;
+                let expression = self.sum()?;
+                if self.eat(')') {
+                    Ok(expression)
+                } else {
+                    Err(format!("Missing ')' at position {}", self.position + 1))
+                }
+            }
+            Some(quote @ ('"' | '`')) => {
+                self.position += 1;
+                let start = self.position;
+                while self.peek().is_some_and(|c| c != quote) {
+                    self.position += 1;
+                }
+                if self.peek().is_none() {
+                    return Err(format!("Missing closing {quote} of column label"));
+                }
+                let label = self.chars[start..self.position].iter().collect();
+                self.position += 1;
+                Ok(self.column(label))
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let start = self.position;
+                while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.position += 1;
+                }
+                let name = self.chars[start..self.position].iter().collect::<String>();
+                if self.eat('(') {
+                    self.call(&name)
+                } else {
+                    Ok(self.column(name))
+                }
+            }
+            Some(c) => Err(self.unexpected(c)),
+        }
+    }
+
+    fn number(&mut self) -> Result<Node, String> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+        // exponent, e.g. 1e-3
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.position += 1;
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position = mantissa_end;
+            }
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position += 1;
+            }
+        }
+        let text = self.chars[start..self.position].iter().collect::<String>();
+        text.parse()
+            .map(Node::Number)
+            .map_err(|_| format!("Invalid number '{text}' at position {}", start + 1))
+    }
+
+    /// The opening parenthesis is already consumed
+    fn call(&mut self, name: &str) -> Result<Node, String> {
+        let function = Function::all()
+            .into_iter()
+            .find(|f| f.name() == name)
+            .ok_or_else(|| format!("Unknown function '{name}'"))?;
+        let mut arguments = vec![self.sum()?];
+        while self.eat(',') {
+            arguments.push(self.sum()?);
+        }
+        if !self.eat(')') {
+            return Err(format!("Missing ')' at position {}", self.position + 1));
+        }
+        if arguments.len() != function.arguments() {
+            return Err(format!(
+                "'{name}' takes {} argument(s), {} given",
+                function.arguments(),
+                arguments.len()
+            ));
+        }
+        Ok(Node::Function(function, arguments))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Expression;
+
+    #[test]
+    fn parse_and_evaluate() {
+        let column = |label: &str| match label {
+            "X" => 3.,
+            "Y" => -4.,
+            "Vth after" => 0.75,
+            _ => f64::NAN,
+        };
+        for (text, expected) in [
+            ("sqrt(X^2+Y^2)", 5.),
+            ("abs(Y)*1000", 4000.),
+            ("\"Vth after\" - 0.25", 0.5),
+            ("-X^2 + 2^3^2", 503.),
+            ("1 - 2 - 3", -4.),
+            ("max(X, Y) / 2e1", 0.15),
+            ("(X + Y) * 2", -2.),
+        ] {
+            let expression = Expression::parse(text).unwrap();
+            let values = expression
+                .columns()
+                .into_iter()
+                .map(column)
+                .collect::<Vec<_>>();
+            assert!(
+                (expression.evaluate(&values) - expected).abs() < 1e-9,
+                "{text}"
+            );
+        }
+        assert_eq!(
+            Expression::parse("X * (Y + X) - `Vth after`")
+                .unwrap()
+                .columns(),
+            ["X", "Y", "Vth after"]
+        );
+        for text in ["", "X +", "(X", "foo(X)", "min(X)", "X Y", "\"X", "3 $"] {
+            assert!(Expression::parse(text).is_err(), "{text}");
+        }
+        assert!(Expression::parse(&format!("{}1{}", "(".repeat(50), ")".repeat(50))).is_ok());
+        for text in [
+            format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+            format!("{}1", "-".repeat(100_000)),
+            format!("{}1", "2^".repeat(100_000)),
+        ] {
+            assert!(Expression::parse(&text).is_err());
+        }
+    }
+}
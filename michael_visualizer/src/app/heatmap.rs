@@ -1,8 +1,21 @@
+mod bins;
+mod colormap;
+mod stack;
+mod wafer;
 use egui_heatmap::CoordinatePoint;
 
 use crate::{data_types::LimitKey, LocalizableStr, LocalizableString};
 
-use super::{limits::LimitDataKind, selection::Selection, DataEvent};
+use super::{
+    limits::{LimitDataKind, UniqueInt},
+    selection::Selection,
+    DataEvent,
+};
+use bins::{BinSettings, Legend};
+pub(super) use colormap::ColorMap;
+use colormap::ColorScale;
+use stack::Stacking;
+use wafer::{DieSite, WaferGeometry};
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 pub struct HeatmapTab {
@@ -14,12 +27,42 @@ pub struct HeatmapTab {
     restrict_limit_by_shown_area: bool,
     heatmap_state: Option<egui_heatmap::ShowState<crate::data_types::FileKey>>,
     to_select: Option<Selection>,
+    /// Dies are placed on the wafer, instead of a rectangular bitmap
+    #[serde(default)]
+    wafer_map: bool,
+    #[serde(default)]
+    wafer: WaferGeometry,
+    /// Integer columns with few values are colored per bin, instead of by the gradient
+    #[serde(default)]
+    categorical: bool,
+    #[serde(default)]
+    bins: BinSettings,
+    #[serde(skip)]
+    legend: Option<Legend>,
+    #[serde(default)]
+    color_map: ColorMap,
+    /// Scale drawn below the heatmap widget, if the widget can not show it
+    #[serde(skip)]
+    colorbar: Option<ColorScale>,
+    /// One map combining all files, instead of one per file
+    #[serde(default)]
+    stacking: Option<Stacking>,
+    /// Dies under the cursor, one per file for the stacked map
+    #[serde(skip)]
+    inspected: Vec<super::selection::DieInspection>,
+    /// Position of the inspected dies, they are only inspected anew if it changes
+    #[serde(skip)]
+    inspected_at: Option<(crate::data_types::FileKey, CoordinatePoint)>,
+    /// Dies selected on the wafer map, the heatmap widget keeps its own selection
+    #[serde(skip)]
+    wafer_selected: std::collections::HashSet<CoordinatePoint>,
 }
 #[derive(Default)]
 enum HeatmapState {
     #[default]
     Recompute,
     Heatmap(Box<egui_heatmap::MultiBitmapWidget<crate::data_types::FileKey>>),
+    Wafer(Box<HeatmapImages>),
     Error(LocalizableString),
 }
 impl HeatmapState {
@@ -44,15 +87,21 @@ impl super::DataEventNotifyable for HeatmapTab {
             DataEvent::File(event) => match event {
                 super::files::FileEvent::LoadFromPath { .. } => {}
                 super::files::FileEvent::ParseFromBytes { .. } => {}
+                super::files::FileEvent::FromData { .. } => {}
                 super::files::FileEvent::ToShow(_) => self.needs_recompute(),
                 super::files::FileEvent::Remove(_) => self.needs_recompute(),
                 super::files::FileEvent::MoveUp(_) => self.needs_recompute(),
                 super::files::FileEvent::MoveDown(_) => self.needs_recompute(),
                 super::files::FileEvent::Label(_) => self.needs_recompute(),
+                super::files::FileEvent::Reload(_) => self.needs_recompute(),
+                super::files::FileEvent::Cancel(_) => {}
                 super::files::FileEvent::LoadError { .. } => {}
+                super::files::FileEvent::ParseError { .. } => {}
                 super::files::FileEvent::Loaded { .. } => self.needs_recompute(),
             },
             DataEvent::Filtering => self.needs_recompute(),
+            DataEvent::Formulas => {}
+            DataEvent::Scenarios => {}
             DataEvent::LimitRequest(_) => {}
             DataEvent::FileRequest(_) => {}
             DataEvent::SelectionRequest(_) => {}
@@ -61,6 +110,7 @@ impl super::DataEventNotifyable for HeatmapTab {
                     if let Some(heatmap_state) = self.heatmap_state.as_mut() {
                         heatmap_state.clear_selected();
                     }
+                    self.wafer_selected.clear();
                 }
                 super::selection::SelectionEvent::Selection(selection) => {
                     self.to_select = Some(selection.clone());
@@ -77,54 +127,48 @@ impl super::DataEventNotifyable for HeatmapTab {
             selected,
         }) = self.to_select.take()
         {
-            if let Some(heatmap_state) = self.heatmap_state.as_mut() {
-                let mut to_select = std::collections::HashSet::new();
-                if let (Some(x_key_output), Some(y_key_output)) = (&self.x_key, &self.y_key) {
-                    for CoordinatePoint { x: xx, y: yy } in selected.into_iter() {
-                        for (_, (_, data, limit_sorting)) in state.files.iter_loaded() {
-                            if let (
-                                Some(x_column),
-                                Some(y_column),
-                                Some(x_output),
-                                Some(y_output),
-                            ) = (
-                                limit_sorting.get(&x_key),
-                                limit_sorting.get(&y_key),
-                                limit_sorting.get(x_key_output),
-                                limit_sorting.get(y_key_output),
-                            ) {
-                                let x_data = data.get_column(*x_column).as_int();
-                                let y_data = data.get_column(*y_column).as_int();
-                                let x_output = data.get_column(*x_output).as_int();
-                                let y_output = data.get_column(*y_output).as_int();
-                                if let (
-                                    Some(x_data),
-                                    Some(y_data),
-                                    Some(x_output),
-                                    Some(y_output),
-                                ) = (x_data, y_data, x_output, y_output)
+            let mut to_select = std::collections::HashSet::new();
+            if let (Some(x_key_output), Some(y_key_output)) = (&self.x_key, &self.y_key) {
+                for CoordinatePoint { x: xx, y: yy } in selected.into_iter() {
+                    for (_, (_, data, limit_sorting)) in state.files.iter_loaded() {
+                        if let (Some(x_column), Some(y_column), Some(x_output), Some(y_output)) = (
+                            limit_sorting.get(&x_key),
+                            limit_sorting.get(&y_key),
+                            limit_sorting.get(x_key_output),
+                            limit_sorting.get(y_key_output),
+                        ) {
+                            let x_data = data.get_column(*x_column).as_int();
+                            let y_data = data.get_column(*y_column).as_int();
+                            let x_output = data.get_column(*x_output).as_int();
+                            let y_output = data.get_column(*y_output).as_int();
+                            if let (Some(x_data), Some(y_data), Some(x_output), Some(y_output)) =
+                                (x_data, y_data, x_output, y_output)
+                            {
+                                if let Some(index) = x_data
+                                    .iter()
+                                    .zip(y_data.iter())
+                                    .position(|(&x, &y)| x == xx && y == yy)
                                 {
-                                    if let Some(index) = x_data
-                                        .iter()
-                                        .zip(y_data.iter())
-                                        .position(|(&x, &y)| x == xx && y == yy)
-                                    {
-                                        let x = x_output[index];
-                                        let y = y_output[index];
-                                        to_select.insert(CoordinatePoint { x, y });
-                                        continue;
-                                    }
+                                    let x = x_output[index];
+                                    let y = y_output[index];
+                                    to_select.insert(CoordinatePoint { x, y });
+                                    continue;
                                 }
                             }
                         }
                     }
                 }
-                heatmap_state.make_selected(to_select);
             }
+            if let Some(heatmap_state) = self.heatmap_state.as_mut() {
+                heatmap_state.make_selected(to_select.clone());
+            }
+            self.wafer_selected = to_select;
         }
         if let Some(heatmap_state) = self.heatmap_state.as_mut() {
             for event in heatmap_state.events() {
                 let event = match event {
+                    // the stacked map is no file
+                    egui_heatmap::Event::Hide(_) if self.stacking.is_some() => continue,
                     egui_heatmap::Event::Hide(key) => {
                         DataEvent::FileRequest(super::files::FileRequest::Hide(key))
                     }
@@ -190,15 +234,128 @@ impl super::TabTrait for HeatmapTab {
                     .localize(state.language),
                 );
                 ui.checkbox(&mut self.restrict_limit_by_shown_area, "");
+                if ui
+                    .checkbox(
+                        &mut self.wafer_map,
+                        LocalizableStr {
+                            english: "Wafer map",
+                        }
+                        .localize(state.language),
+                    )
+                    .changed()
+                {
+                    self.state = HeatmapState::Recompute;
+                }
+                let limit_key = self.to_show.get(state.locked_limits).1.cloned();
+                let has_bins = limit_key
+                    .as_ref()
+                    .and_then(|key| state.center.limit(key))
+                    .map(is_categorical)
+                    .unwrap_or_default();
+                if ui
+                    .add_enabled(
+                        has_bins && self.stacking.is_none(),
+                        egui::Checkbox::new(
+                            &mut self.categorical,
+                            LocalizableStr { english: "Bins" }.localize(state.language),
+                        ),
+                    )
+                    .on_disabled_hover_text(
+                        LocalizableStr {
+                            english: "Only integer columns with at most 100 different values, \
+                            each file on its own",
+                        }
+                        .localize(state.language),
+                    )
+                    .changed()
+                {
+                    self.state = HeatmapState::Recompute;
+                }
+                let each_file = LocalizableStr {
+                    english: "Each file",
+                }
+                .localize(state.language);
+                let before = self.stacking;
+                egui::ComboBox::from_id_source("Stacking")
+                    .selected_text(
+                        self.stacking
+                            .map(|stacking| stacking.label().localize(state.language))
+                            .unwrap_or(each_file),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.stacking, None, each_file);
+                        for stacking in Stacking::all() {
+                            ui.selectable_value(
+                                &mut self.stacking,
+                                Some(stacking),
+                                stacking.label().localize(state.language),
+                            );
+                        }
+                    });
+                if self.stacking != before {
+                    self.state = HeatmapState::Recompute;
+                }
             });
+            if let (Some(legend), Some(limit_key)) = (
+                self.legend.as_ref(),
+                self.to_show.get(state.locked_limits).1,
+            ) {
+                egui::CollapsingHeader::new(
+                    LocalizableStr { english: "Bins" }.localize(state.language),
+                )
+                .default_open(true)
+                .show(ui, |ui| {
+                    if self.bins.show(ui, state.language, limit_key, legend) {
+                        self.state = HeatmapState::Recompute;
+                    }
+                });
+            }
+            if self.legend.is_none() {
+                egui::CollapsingHeader::new(
+                    LocalizableStr { english: "Colors" }.localize(state.language),
+                )
+                .show(ui, |ui| {
+                    if self.color_map.show(ui, state.language) {
+                        self.state = HeatmapState::Recompute;
+                    }
+                });
+            }
+            if !self.inspected.is_empty() {
+                egui::CollapsingHeader::new(
+                    LocalizableStr { english: "Die" }.localize(state.language),
+                )
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(200.)
+                        .id_source("DieInspection")
+                        .show(ui, |ui| {
+                            for die in &self.inspected {
+                                die.show(ui, state.language);
+                            }
+                        });
+                });
+            }
+            if self.wafer_map {
+                egui::CollapsingHeader::new(
+                    LocalizableStr {
+                        english: "Wafer geometry",
+                    }
+                    .localize(state.language),
+                )
+                .show(ui, |ui| {
+                    // painted anew each frame, so nothing to recompute
+                    let _ = self.wafer.show(ui, state.language);
+                });
+            }
             ui.with_layout(
                 egui::Layout::bottom_up(egui::Align::Min).with_cross_justify(true),
                 |ui| {
                     let before = (self.x_key.clone(), self.y_key.clone());
                     ui.horizontal(|ui| {
                         let int_limits = state
-                            .limits
-                            .iter()
+                            .center
+                            .limits()
                             .filter(|(_, l)| l.is_int() && !l.is_trivial())
                             .collect::<Vec<_>>();
                         let mut needs_recompute = Self::axis_selection(
@@ -228,7 +385,7 @@ impl super::TabTrait for HeatmapTab {
                     if (self.x_key.clone(), self.y_key.clone()) != before {
                         self.state = HeatmapState::Recompute;
                     }
-                    match &mut self.state {
+                    let hovered = match &mut self.state {
                         HeatmapState::Recompute => {
                             ui.label(
                             LocalizableString {
@@ -244,6 +401,7 @@ impl super::TabTrait for HeatmapTab {
                                 }
                                 .localize(state.language),
                             );
+                            None
                         }
                         HeatmapState::Heatmap(heatmap) => {
                             if self.heatmap_state.is_none() {
@@ -257,61 +415,75 @@ impl super::TabTrait for HeatmapTab {
                                         .background_color(egui::Color32::RED),
                                 );
                             }
-                            let label = match heatmap_state.hover() {
-                                egui_heatmap::MultiMapPosition::NotHovering => LocalizableString {
-                                    english: "Mouse not above heatmap".into(),
-                                },
-                                egui_heatmap::MultiMapPosition::NoData(
-                                    file_key,
-                                    CoordinatePoint { x, y },
-                                ) => {
-                                    let file = state
-                                        .files
-                                        .get(file_key)
-                                        .and_then(|x| x.get_loaded())
-                                        .map(|l| l.0.as_str())
-                                        .unwrap_or(
-                                            LocalizableStr {
-                                                english: "File does not exist",
-                                            }
-                                            .localize(state.language),
-                                        );
-                                    LocalizableString {
-                                        english: format!("{file}: {x}/{y} - no data"),
-                                    }
+                            let label = hover_label(heatmap_state.hover(), self.stacking, state);
+                            ui.label(label.localize(state.language));
+                            if let Some(colorbar) = &self.colorbar {
+                                colorbar.show(ui);
+                            }
+                            heatmap.ui(ui, heatmap_state);
+                            match heatmap_state.hover() {
+                                egui_heatmap::MultiMapPosition::Pixel(file_key, point) => {
+                                    Some((file_key.clone(), point.clone()))
                                 }
-                                egui_heatmap::MultiMapPosition::Pixel(
-                                    file_key,
-                                    CoordinatePoint { x, y },
-                                ) => {
-                                    let file = state
-                                        .files
-                                        .get(file_key)
-                                        .and_then(|x| x.get_loaded())
-                                        .map(|l| l.0.as_str())
-                                        .unwrap_or(
-                                            LocalizableStr {
-                                                english: "File does not exist",
-                                            }
-                                            .localize(state.language),
-                                        );
-                                    LocalizableString {
-                                        english: format!("{file}: {x}/{y}"),
+                                _ => None,
+                            }
+                        }
+                        HeatmapState::Wafer(images) => {
+                            // the label is below the map, it is filled once the die under the
+                            // mouse is known
+                            let (label_rect, _) = ui.allocate_exact_size(
+                                egui::vec2(
+                                    ui.available_width(),
+                                    ui.text_style_height(&egui::TextStyle::Body),
+                                ),
+                                egui::Sense::hover(),
+                            );
+                            let (response, painter) =
+                                ui.allocate_painter(ui.available_size(), egui::Sense::click());
+                            let view = WaferView {
+                                geometry: &self.wafer,
+                                pointer: response.hover_pos(),
+                                selected: &self.wafer_selected,
+                            };
+                            let hover = paint_images(&painter, response.rect, images, Some(view));
+                            let label = hover_label(&hover, self.stacking, state);
+                            ui.allocate_ui_at_rect(label_rect, |ui| {
+                                ui.label(label.localize(state.language))
+                            });
+                            match hover {
+                                egui_heatmap::MultiMapPosition::Pixel(file_key, point) => {
+                                    // a click selects the die or, if it was selected, unselects it
+                                    if let (true, Some(x_key), Some(y_key)) =
+                                        (response.clicked(), &self.x_key, &self.y_key)
+                                    {
+                                        if !self.wafer_selected.remove(&point) {
+                                            self.wafer_selected.insert(point.clone());
+                                        }
+                                        state.data_events.push(DataEvent::SelectionRequest(
+                                            super::selection::SelectionRequest::Selection(
+                                                Selection {
+                                                    x_key: x_key.clone(),
+                                                    y_key: y_key.clone(),
+                                                    selected: self.wafer_selected.clone(),
+                                                },
+                                            ),
+                                        ));
                                     }
+                                    Some((file_key, point))
                                 }
-                                egui_heatmap::MultiMapPosition::Colorbar(f) => LocalizableString {
-                                    english: format!("Colorbar: {f}"),
-                                },
-                            };
-                            ui.label(label.localize(state.language));
-                            heatmap.ui(ui, heatmap_state);
+                                _ => None,
+                            }
                         }
                         HeatmapState::Error(msg) => {
                             ui.label(msg.as_str().localize(state.language));
                             ui.heading(
                                 LocalizableStr { english: "ERROR" }.localize(state.language),
                             );
+                            None
                         }
+                    };
+                    if let Some((file_key, point)) = hovered {
+                        self.inspect(state, ui, &file_key, &point);
                     }
                 },
             );
@@ -324,109 +496,57 @@ impl HeatmapTab {
         let x = check_key(&mut self.x_key, state);
         let y = check_key(&mut self.y_key, state);
 
-        if let (
-            Some((x_key, min_x, max_x)),
-            Some((y_key, min_y, max_y)),
-            Some((limit_key, limit)),
-        ) = (
+        if let (Some(x), Some(y), Some((limit_key, limit))) = (
             x,
             y,
             self.to_show
                 .get(state.locked_limits)
                 .1
-                .and_then(|k| state.limits.get(k).map(|l| (k, l))),
+                .and_then(|k| state.center.limit(k).map(|l| (k, l))),
         ) {
-            let (mut min_vis, mut max_vis) = limit.get_limits();
-            let mut data = Vec::new();
-            // find files which need to be drawn, and compute limits (if non are given, min/max will be used)
-            for (file_key, (file_label, file, sorting)) in state.files.iter_loaded() {
-                let filtering = state.total_filterings.get(file_key);
-                let vis_data = sorting
-                    .get(limit_key)
-                    .map(|column| file.get_column(*column));
-                let x_data = sorting
-                    .get(&x_key)
-                    .and_then(|column| file.get_column(*column).as_int());
-                let y_data = sorting
-                    .get(&y_key)
-                    .and_then(|column| file.get_column(*column).as_int());
-                if let (Some(filtering), Some(vis_data), Some(x_data), Some(y_data)) =
-                    (filtering, vis_data, x_data, y_data)
-                {
-                    let filtered = vis_data.simple_filter(filtering);
-                    if filtered.is_empty() {
-                        continue;
-                    }
-                    {
-                        let min_f = *filtered.iter().min().expect("Empty-case already covered");
-                        let min_vis = min_vis.get_or_insert(min_f);
-                        *min_vis = std::cmp::min(*min_vis, min_f);
-                    }
-                    {
-                        let max_f = *filtered.iter().max().expect("Empty-case already covered");
-                        let max_vis = max_vis.get_or_insert(max_f);
-                        *max_vis = std::cmp::max(*max_vis, max_f);
-                    }
-                    data.push((
-                        file_key.clone(),
-                        filtering,
-                        vis_data,
-                        x_data,
-                        y_data,
-                        file_label,
-                    ));
-                }
+            self.legend = None;
+            self.colorbar = None;
+            self.inspected.clear();
+            self.inspected_at = None;
+            let coloring = Coloring {
+                bins: (self.categorical && self.stacking.is_none() && is_categorical(limit))
+                    .then_some(&self.bins),
+                color_map: &self.color_map,
+                stacking: self.stacking,
+            };
+            let images =
+                match HeatmapImages::new(x, y, limit_key, limit, coloring, &state.plot_files()) {
+                    Ok(images) => images,
+                    Err(msg) => return HeatmapState::Error(msg),
+                };
+            self.legend = images.legend.clone();
+            if self.wafer_map {
+                return HeatmapState::Wafer(images.into());
             }
-            if min_vis.is_none() || max_vis.is_none() {
-                return HeatmapState::Error(LocalizableString {
-                    english: "No data after filtering - check limits".into(),
-                });
+            let HeatmapImages {
+                width,
+                height,
+                first_point_coordinate,
+                scale,
+                images,
+                legend,
+            } = images;
+            let colorbar = legend.is_none().then(|| scale.widget_colorbar()).flatten();
+            if legend.is_none() && colorbar.is_none() {
+                self.colorbar = Some(scale);
             }
-            let min_vis = min_vis.unwrap().inner();
-            let max_vis = max_vis.unwrap().inner();
-            let delta_vis = max_vis - min_vis;
-            // compute data
-            let width = (max_x - min_x + 1) as usize;
-            let height = (max_y - min_y + 1) as usize;
-            let gradient = egui_heatmap::colors::Gradient::with_options(
-                &egui_heatmap::colors::ColorGradientOptions::StartCenterEnd {
-                    start: egui::Color32::BLUE,
-                    center: egui::Color32::GREEN,
-                    end: egui::Color32::RED,
-                    steps: 63,
-                },
-            );
-            let filtered_color = egui::Color32::GRAY;
-            let background_color = egui::Color32::BLACK;
-            let first_point_coordinate = egui_heatmap::CoordinatePoint { x: min_x, y: min_y };
-            let data = data
+            let data = images
                 .into_iter()
-                .map(|(key, filtering, vis_data, x_data, y_data, label)| {
-                    let mut data = vec![background_color; width * height];
-                    for (((&x, &y), vis), filter) in x_data
-                        .iter()
-                        .zip(y_data.iter())
-                        .zip(vis_data.iter_float())
-                        .zip(filtering.iter().map(|&f| f == 0))
-                    {
-                        let i = {
-                            let x = (x - min_x) as usize;
-                            let y = (y - min_y) as usize;
-                            x + y * width
-                        };
-                        data[i] = if filter {
-                            let vis = (vis - min_vis) / delta_vis;
-                            gradient.lookup_color(vis)
-                        } else {
-                            filtered_color
-                        };
-                    }
+                .map(|(key, label, data)| {
                     (
                         key,
                         egui_heatmap::Data {
                             width,
                             height,
-                            data,
+                            data: data
+                                .into_iter()
+                                .map(|color| color.unwrap_or(BACKGROUND_COLOR))
+                                .collect(),
                             first_point_coordinate: first_point_coordinate.clone(),
                             overlay: egui_heatmap::Overlay::new(
                                 egui_heatmap::FontOptions {
@@ -451,8 +571,8 @@ impl HeatmapTab {
                     color: egui::Color32::DARK_GRAY,
                     thickness: 5,
                 },
-                colorbar: Some((gradient, 100, (min_vis, max_vis))),
-                background: background_color,
+                colorbar,
+                background: BACKGROUND_COLOR,
                 boundary_unselected: egui_heatmap::ColorWithThickness {
                     color: egui::Color32::BROWN,
                     thickness: 3,
@@ -491,7 +611,7 @@ impl HeatmapTab {
                 .cloned();
         }
         let selected_label = if let Some(key) = value.as_ref() {
-            if let Some(limit) = state.limits.get(key) {
+            if let Some(limit) = state.center.limit(key) {
                 limit.get_label().as_str()
             } else {
                 axis_selection_text
@@ -530,15 +650,624 @@ impl HeatmapTab {
         needs_recompute
     }
 
+    /// Inspects the hovered die, of all files for the stacked map, and shows it in a tooltip
+    fn inspect(
+        &mut self,
+        state: &super::AppState,
+        ui: &egui::Ui,
+        file_key: &crate::data_types::FileKey,
+        point: &CoordinatePoint,
+    ) {
+        let (Some(x_key), Some(y_key)) = (&self.x_key, &self.y_key) else {
+            return;
+        };
+        let position = (file_key.clone(), point.clone());
+        if self.inspected_at.as_ref() != Some(&position) {
+            let file_keys = if self.stacking.is_some() {
+                state
+                    .plot_files()
+                    .into_iter()
+                    .map(|file| file.key.clone())
+                    .collect()
+            } else {
+                vec![file_key.clone()]
+            };
+            self.inspected = file_keys
+                .iter()
+                .filter_map(|file_key| {
+                    super::selection::DieInspection::new(state, file_key, x_key, y_key, point)
+                })
+                .collect();
+            self.inspected_at = Some(position);
+        }
+        egui::show_tooltip_at_pointer(ui.ctx(), egui::Id::new("DieInspection"), |ui| {
+            for die in &self.inspected {
+                die.show_summary(ui, state.language);
+            }
+        });
+    }
+
     fn needs_recompute(&mut self) {
         self.state.needs_recompute()
     }
 }
 
+/// Text for the position under the mouse, in the heatmap widget or on the wafer map
+fn hover_label(
+    hover: &egui_heatmap::MultiMapPosition<crate::data_types::FileKey>,
+    stacking: Option<Stacking>,
+    state: &super::AppState,
+) -> LocalizableString {
+    let file = |file_key: &crate::data_types::FileKey| match stacking {
+        Some(stacking) => stacking.label().localize(state.language),
+        None => state
+            .files
+            .get(file_key)
+            .and_then(|x| x.get_loaded())
+            .map(|l| l.0.as_str())
+            .unwrap_or(
+                LocalizableStr {
+                    english: "File does not exist",
+                }
+                .localize(state.language),
+            ),
+    };
+    match hover {
+        egui_heatmap::MultiMapPosition::NotHovering => LocalizableString {
+            english: "Mouse not above heatmap".into(),
+        },
+        egui_heatmap::MultiMapPosition::NoData(file_key, CoordinatePoint { x, y }) => {
+            LocalizableString {
+                english: format!("{}: {x}/{y} - no data", file(file_key)),
+            }
+        }
+        egui_heatmap::MultiMapPosition::Pixel(file_key, CoordinatePoint { x, y }) => {
+            LocalizableString {
+                english: format!("{}: {x}/{y}", file(file_key)),
+            }
+        }
+        egui_heatmap::MultiMapPosition::Colorbar(f) => LocalizableString {
+            english: format!("Colorbar: {f}"),
+        },
+    }
+}
+
+/// Integer columns with few different values can be shown as bins
+fn is_categorical(limit: &super::limits::Limit) -> bool {
+    matches!(
+        limit.data_kind(),
+        LimitDataKind::Int {
+            uniques: UniqueInt::Uniques(_),
+            ..
+        }
+    )
+}
+
+/// Dies without data, e.g. outside of the wafer or missing in the wafer map
+pub(super) const BACKGROUND_COLOR: egui::Color32 = egui::Color32::BLACK;
+/// Dies which are filtered by any limit
+pub(super) const FILTERED_COLOR: egui::Color32 = egui::Color32::GRAY;
+/// Wafer map: the wafer between the dies, the die grid, the edge exclusion ring and the cross
+/// on dies with data, which are not entirely on the wafer, and the frame of selected dies
+const WAFER_COLOR: egui::Color32 = egui::Color32::from_gray(225);
+const GRID_COLOR: egui::Color32 = egui::Color32::from_gray(150);
+const EDGE_EXCLUSION_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 140, 0);
+const OUTSIDE_COLOR: egui::Color32 = egui::Color32::RED;
+const SELECTED_COLOR: egui::Color32 = egui::Color32::WHITE;
+
+pub(super) fn gradient() -> egui_heatmap::colors::Gradient<egui::Color32> {
+    egui_heatmap::colors::Gradient::with_options(
+        &egui_heatmap::colors::ColorGradientOptions::StartCenterEnd {
+            start: egui::Color32::BLUE,
+            center: egui::Color32::GREEN,
+            end: egui::Color32::RED,
+            steps: 63,
+        },
+    )
+}
+
+/// One image per file, all on the bounding box of the x- and y-axis
+/// Each pixel is a die, colored by the scale between the limits (or the data range, if there are none)
+/// or, if there is a legend, by its bin
+/// Dies without data have no color, any color, even the background color, is a die
+pub(super) struct HeatmapImages {
+    pub(super) width: usize,
+    pub(super) height: usize,
+    pub(super) first_point_coordinate: CoordinatePoint,
+    pub(super) scale: ColorScale,
+    pub(super) images: Vec<(
+        crate::data_types::FileKey,
+        crate::data_types::FileLabel,
+        Vec<Option<egui::Color32>>,
+    )>,
+    pub(super) legend: Option<Legend>,
+}
+/// How the dies of the images are colored
+pub(super) struct Coloring<'a> {
+    /// Dies are colored by their bin, if the column is an integer column
+    bins: Option<&'a BinSettings>,
+    color_map: &'a ColorMap,
+    /// One image combining all files
+    stacking: Option<Stacking>,
+}
+
+impl HeatmapImages {
+    /// Axes are given as key, minimum and maximum of the integer column
+    pub(super) fn new(
+        (x_key, min_x, max_x): (LimitKey, i32, i32),
+        (y_key, min_y, max_y): (LimitKey, i32, i32),
+        limit_key: &LimitKey,
+        limit: &super::limits::Limit,
+        Coloring {
+            bins,
+            color_map,
+            stacking,
+        }: Coloring,
+        files: &[super::PlotFile],
+    ) -> Result<Self, LocalizableString> {
+        let (mut min_vis, mut max_vis) = limit.get_limits();
+        let mut data = Vec::new();
+        let mut values = Vec::new();
+        // find files which need to be drawn, and compute limits (if non are given, min/max will be used)
+        for super::PlotFile {
+            key: file_key,
+            label: file_label,
+            data: file,
+            sorting,
+            filtering,
+        } in files
+        {
+            let vis_data = sorting
+                .get(limit_key)
+                .map(|column| file.get_column(*column));
+            let x_data = sorting
+                .get(&x_key)
+                .and_then(|column| file.get_column(*column).as_int());
+            let y_data = sorting
+                .get(&y_key)
+                .and_then(|column| file.get_column(*column).as_int());
+            if let (Some(vis_data), Some(x_data), Some(y_data)) = (vis_data, x_data, y_data) {
+                let filtered = vis_data.simple_filter(filtering);
+                if !filtered.is_empty() {
+                    {
+                        let min_f = *filtered.iter().min().expect("Empty-case already covered");
+                        let min_vis = min_vis.get_or_insert(min_f);
+                        *min_vis = std::cmp::min(*min_vis, min_f);
+                    }
+                    {
+                        let max_f = *filtered.iter().max().expect("Empty-case already covered");
+                        let max_vis = max_vis.get_or_insert(max_f);
+                        *max_vis = std::cmp::max(*max_vis, max_f);
+                    }
+                    if color_map.needs_values() {
+                        values.extend(filtered.iter().map(|v| f64::from(v.inner())));
+                    }
+                } else if stacking.is_none() {
+                    continue;
+                }
+                // the stacked map counts the failures of entirely filtered files, too
+                data.push((file_key, filtering, vis_data, x_data, y_data, file_label));
+            }
+        }
+        let no_data = || LocalizableString {
+            english: "No data after filtering - check limits".into(),
+        };
+        if data.is_empty() {
+            return Err(no_data());
+        }
+        let (min_vis, max_vis) = match (min_vis, max_vis) {
+            (Some(min_vis), Some(max_vis)) => (min_vis.inner(), max_vis.inner()),
+            // all dies are filtered, the stacked map still shows the failures
+            _ if stacking.is_some() => (0., 0.),
+            _ => return Err(no_data()),
+        };
+        // compute data
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+        let index = |x: i32, y: i32| (x - min_x) as usize + (y - min_y) as usize * width;
+        if let Some(stacking) = stacking {
+            let mut positions = vec![stack::Position::default(); width * height];
+            for (_, filtering, vis_data, x_data, y_data, _) in &data {
+                for (((&x, &y), vis), &f) in x_data
+                    .iter()
+                    .zip(y_data.iter())
+                    .zip(vis_data.iter_float())
+                    .zip(filtering.iter())
+                {
+                    positions[index(x, y)].push(vis, f != 0);
+                }
+            }
+            let combined = positions
+                .iter_mut()
+                .map(|position| stacking.combine(position))
+                .collect::<Vec<_>>();
+            let mut values = combined
+                .iter()
+                .flatten()
+                .map(|&v| f64::from(v))
+                .collect::<Vec<_>>();
+            values.sort_by(|a, b| a.total_cmp(b));
+            let range = match (stacking.uses_limits(), values.first(), values.last()) {
+                (false, Some(&min), Some(&max)) => (min as f32, max as f32),
+                _ => (min_vis, max_vis),
+            };
+            let scale = color_map.scale(range, &values);
+            let image = positions
+                .iter()
+                .zip(combined)
+                .map(|(position, combined)| match combined {
+                    Some(value) => Some(scale.color(value)),
+                    None if position.is_empty() => None,
+                    None => Some(FILTERED_COLOR),
+                })
+                .collect();
+            let label = format!("{} ({} files)", stacking.label().english, data.len());
+            return Ok(Self {
+                width,
+                height,
+                first_point_coordinate: CoordinatePoint { x: min_x, y: min_y },
+                scale,
+                images: vec![(files[0].key.clone(), label.into(), image)],
+                legend: None,
+            });
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        let scale = color_map.scale((min_vis, max_vis), &values);
+        let bins = bins.filter(|_| {
+            data.iter()
+                .all(|(_, _, vis_data, _, _, _)| vis_data.as_int().is_some())
+        });
+        let mut legend = bins.map(|_| Legend::default());
+        let column_values = match limit.data_kind() {
+            LimitDataKind::Int {
+                uniques: UniqueInt::Uniques(uniques),
+                ..
+            } => {
+                let mut values = uniques.iter().copied().collect::<Vec<_>>();
+                values.sort_unstable();
+                values
+            }
+            _ => Vec::new(),
+        };
+        let images = data
+            .into_iter()
+            .map(|(key, filtering, vis_data, x_data, y_data, label)| {
+                let mut data = vec![None; width * height];
+                for (((&x, &y), vis), filter) in x_data
+                    .iter()
+                    .zip(y_data.iter())
+                    .zip(vis_data.iter_float())
+                    .zip(filtering.iter().map(|&f| f == 0))
+                {
+                    data[index(x, y)] = Some(match (filter, bins) {
+                        (false, _) => FILTERED_COLOR,
+                        (true, Some(bins)) => bins.color(limit_key, vis as i32, &column_values),
+                        (true, None) => scale.color(vis),
+                    });
+                }
+                if let (Some(legend), Some(values)) = (legend.as_mut(), vis_data.as_int()) {
+                    legend.push((*label).clone(), values, filtering);
+                }
+                ((*key).clone(), (*label).clone(), data)
+            })
+            .collect();
+        if let (Some(legend), Some(bins)) = (legend.as_mut(), bins) {
+            legend.finish(bins, limit_key, &column_values);
+        }
+        Ok(Self {
+            width,
+            height,
+            first_point_coordinate: CoordinatePoint { x: min_x, y: min_y },
+            scale,
+            images,
+            legend,
+        })
+    }
+}
+
+/// Headless counterpart of the heatmap tab: one panel per file in a grid, the colorbar to the right
+/// Axes are the x- and y-axis, as for the images
+pub(super) fn paint(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    (x, y): ((LimitKey, i32, i32), (LimitKey, i32, i32)),
+    limit_key: &LimitKey,
+    limit: &super::limits::Limit,
+    color_map: &ColorMap,
+    files: &[super::PlotFile],
+) -> Result<(), LocalizableString> {
+    let coloring = Coloring {
+        bins: None,
+        color_map,
+        stacking: None,
+    };
+    let images = HeatmapImages::new(x, y, limit_key, limit, coloring, files)?;
+    let _ = paint_images(painter, rect, &images, None);
+    Ok(())
+}
+
+/// Wafer map as shown in the tab, with the pointer to find the hovered die
+struct WaferView<'a> {
+    geometry: &'a WaferGeometry,
+    pointer: Option<egui::Pos2>,
+    selected: &'a std::collections::HashSet<CoordinatePoint>,
+}
+
+/// Panels of the images, as bitmap or, if a wafer view is given, as wafer map
+/// Returns the die under the pointer, which is only known for the wafer map
+fn paint_images(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    heatmap: &HeatmapImages,
+    wafer: Option<WaferView>,
+) -> egui_heatmap::MultiMapPosition<crate::data_types::FileKey> {
+    let HeatmapImages {
+        width,
+        height,
+        scale,
+        images,
+        legend,
+        ..
+    } = heatmap;
+    let (width, height) = (*width, *height);
+    const MARGIN: f32 = 10.;
+    const COLORBAR_WIDTH: f32 = 20.;
+    const COLORBAR_STEPS: usize = 100;
+    const LABEL_WIDTH: f32 = 60.;
+    let font = egui::FontId::proportional(14.);
+    let text_color = egui::Color32::BLACK;
+    // gradient colors are additive (zero alpha), the heatmap widget draws them opaque
+    let opaque = |color: egui::Color32| egui::Color32::from_rgb(color.r(), color.g(), color.b());
+    painter.rect_filled(rect, 0., egui::Color32::WHITE);
+
+    // colorbar, minimum at the bottom, or the legend of the bins
+    let colorbar = egui::Rect::from_min_max(
+        egui::pos2(
+            rect.max.x - MARGIN - LABEL_WIDTH - COLORBAR_WIDTH,
+            rect.min.y + MARGIN + font.size,
+        ),
+        egui::pos2(
+            rect.max.x - MARGIN - LABEL_WIDTH,
+            rect.max.y - MARGIN - font.size,
+        ),
+    );
+    if let Some(legend) = legend {
+        // legend instead, one square per bin from the top
+        let step_height = font.size + MARGIN / 2.;
+        for (index, (name, color)) in legend.bins.iter().enumerate() {
+            let square = egui::Rect::from_min_size(
+                colorbar.min + egui::vec2(0., index as f32 * step_height),
+                egui::Vec2::splat(font.size),
+            );
+            painter.rect_filled(square, 0., opaque(*color));
+            painter.rect_stroke(square, 0., (1., text_color));
+            painter.text(
+                square.right_center() + egui::vec2(MARGIN / 2., 0.),
+                egui::Align2::LEFT_CENTER,
+                name,
+                font.clone(),
+                text_color,
+            );
+        }
+    } else {
+        let step_height = colorbar.height() / COLORBAR_STEPS as f32;
+        let mut mesh = egui::Mesh::default();
+        for step in 0..COLORBAR_STEPS {
+            let bottom = colorbar.max.y - step as f32 * step_height;
+            mesh.add_colored_rect(
+                egui::Rect::from_x_y_ranges(colorbar.x_range(), bottom - step_height..=bottom),
+                opaque(scale.color_at((step as f32 + 0.5) / COLORBAR_STEPS as f32)),
+            );
+        }
+        painter.add(mesh);
+        painter.rect_stroke(colorbar, 0., (1., text_color));
+        for (position, y) in [
+            (1., colorbar.min.y),
+            (0.5, colorbar.center().y),
+            (0., colorbar.max.y),
+        ] {
+            painter.text(
+                egui::pos2(colorbar.max.x + MARGIN / 2., y),
+                egui::Align2::LEFT_CENTER,
+                scale.value(position),
+                font.clone(),
+                text_color,
+            );
+        }
+    }
+
+    // panels, each die is a quad of one mesh, so neighbouring dies have no seams
+    let area = egui::Rect::from_min_max(
+        rect.min + egui::vec2(MARGIN, MARGIN),
+        egui::pos2(colorbar.min.x - MARGIN, rect.max.y - MARGIN),
+    );
+    let columns = (images.len() as f32).sqrt().ceil().max(1.) as usize;
+    let rows = images.len().div_ceil(columns).max(1);
+    let panel_size = egui::vec2(area.width() / columns as f32, area.height() / rows as f32);
+    let mut hover = egui_heatmap::MultiMapPosition::NotHovering;
+    for (index, (key, label, data)) in images.iter().enumerate() {
+        let panel = egui::Rect::from_min_size(
+            area.min
+                + egui::vec2(
+                    (index % columns) as f32 * panel_size.x,
+                    (index / columns) as f32 * panel_size.y,
+                ),
+            panel_size,
+        )
+        .shrink(MARGIN / 2.);
+        painter.text(
+            panel.center_top(),
+            egui::Align2::CENTER_TOP,
+            label.as_str(),
+            font.clone(),
+            text_color,
+        );
+        let image_area = egui::Rect::from_min_max(
+            panel.min + egui::vec2(0., font.size + MARGIN / 2.),
+            panel.max,
+        );
+        if let Some(wafer) = &wafer {
+            match paint_wafer(painter, image_area, heatmap, data, wafer) {
+                Some((point, true)) => {
+                    hover = egui_heatmap::MultiMapPosition::Pixel(key.clone(), point)
+                }
+                Some((point, false)) => {
+                    hover = egui_heatmap::MultiMapPosition::NoData(key.clone(), point)
+                }
+                None => {}
+            }
+            continue;
+        }
+        let die_size = (image_area.width() / width as f32).min(image_area.height() / height as f32);
+        let image = egui::Rect::from_center_size(
+            image_area.center(),
+            egui::vec2(width as f32, height as f32) * die_size,
+        );
+        let mut mesh = egui::Mesh::default();
+        mesh.add_colored_rect(image, BACKGROUND_COLOR);
+        for (i, color) in data.iter().enumerate() {
+            if let &Some(color) = color {
+                let min = image.min + egui::vec2((i % width) as f32, (i / width) as f32) * die_size;
+                mesh.add_colored_rect(
+                    egui::Rect::from_min_size(min, egui::Vec2::splat(die_size)),
+                    opaque(color),
+                );
+            }
+        }
+        painter.add(mesh);
+    }
+    hover
+}
+
+/// Wafer with outline, edge exclusion and die grid, each die at its physical position
+/// Dies missing in the data are drawn in the background color, dies beyond the wafer are left
+/// empty, or crossed out if they have data
+/// Returns the die under the pointer and whether it has data
+fn paint_wafer(
+    painter: &egui::Painter,
+    area: egui::Rect,
+    heatmap: &HeatmapImages,
+    data: &[Option<egui::Color32>],
+    &WaferView {
+        geometry: wafer,
+        pointer,
+        selected,
+    }: &WaferView,
+) -> Option<(CoordinatePoint, bool)> {
+    let HeatmapImages {
+        width,
+        height,
+        first_point_coordinate: first,
+        ..
+    } = heatmap;
+    let (width, height) = (*width as i32, *height as i32);
+    let opaque = |color: egui::Color32| egui::Color32::from_rgb(color.r(), color.g(), color.b());
+    let color = |x: i32, y: i32| {
+        let (x, y) = (x - first.x, y - first.y);
+        ((0..width).contains(&x) && (0..height).contains(&y))
+            .then(|| data[(x + y * width) as usize])
+            .flatten()
+    };
+    let middle = (
+        first.x as f32 + (width - 1) as f32 / 2.,
+        first.y as f32 + (height - 1) as f32 / 2.,
+    );
+    let center = wafer.center(middle);
+    // dies with data beyond the wafer are shown, too
+    let (x_range, y_range) = wafer.die_range(center);
+    let x_range = *x_range.start().min(&first.x)..=*x_range.end().max(&(first.x + width - 1));
+    let y_range = *y_range.start().min(&first.y)..=*y_range.end().max(&(first.y + height - 1));
+    let half = wafer.pitch() / 2.;
+    let r = wafer.radius();
+    let min = wafer
+        .die_position((*x_range.start(), *y_range.start()), center)
+        .min(egui::vec2(-r, -r) + half)
+        - half;
+    let max = wafer
+        .die_position((*x_range.end(), *y_range.end()), center)
+        .max(egui::vec2(r, r) - half)
+        + half;
+    let scale = (area.width() / (max.x - min.x)).min(area.height() / (max.y - min.y));
+    let origin = area.center() - (min + max) / 2. * scale;
+    let to_screen = |position: egui::Vec2| origin + position * scale;
+
+    // the outline is concave at the notch, but each of its points is visible from the center,
+    // so a fan from there fills it
+    let outline = wafer.outline();
+    let mut mesh = egui::Mesh::default();
+    mesh.colored_vertex(to_screen(egui::Vec2::ZERO), WAFER_COLOR);
+    for &point in &outline {
+        mesh.colored_vertex(to_screen(point), WAFER_COLOR);
+    }
+    let points = outline.len() as u32;
+    for index in 1..=points {
+        mesh.add_triangle(0, index, index % points + 1);
+    }
+    painter.add(mesh);
+    let mut mesh = egui::Mesh::default();
+    let mut grid = Vec::new();
+    let mut crossed = Vec::new();
+    let mut marked = Vec::new();
+    let mut hovered = None;
+    for y in y_range {
+        for x in x_range.clone() {
+            let site = wafer.site((x, y), center);
+            let die = egui::Rect::from_center_size(
+                to_screen(wafer.die_position((x, y), center)),
+                wafer.pitch() * scale,
+            );
+            let point = CoordinatePoint { x, y };
+            let color = color(x, y);
+            match (color, site) {
+                (None, DieSite::Outside) => continue,
+                (None, _) => mesh.add_colored_rect(die, BACKGROUND_COLOR),
+                (Some(color), site) => {
+                    mesh.add_colored_rect(die, opaque(color));
+                    if site == DieSite::Outside {
+                        crossed.push(die);
+                    }
+                    if selected.contains(&point) {
+                        marked.push(die);
+                    }
+                }
+            }
+            if pointer.is_some_and(|pointer| die.contains(pointer)) {
+                hovered = Some((point, color.is_some()));
+            }
+            grid.push(die);
+        }
+    }
+    painter.add(mesh);
+    let grid_stroke = egui::Stroke::new(0.5, GRID_COLOR);
+    for die in grid {
+        painter.rect_stroke(die, 0., grid_stroke);
+    }
+    let cross_stroke = egui::Stroke::new(1.5, OUTSIDE_COLOR);
+    for die in crossed {
+        painter.line_segment([die.left_top(), die.right_bottom()], cross_stroke);
+        painter.line_segment([die.right_top(), die.left_bottom()], cross_stroke);
+    }
+    let selected_stroke = egui::Stroke::new(2., SELECTED_COLOR);
+    for die in marked {
+        painter.rect_stroke(die, 0., selected_stroke);
+    }
+    // the edge exclusion follows the outline, shrunk towards the center
+    let shrink = (r - wafer.edge_exclusion()).max(0.) / r;
+    painter.add(egui::Shape::closed_line(
+        outline.iter().map(|&p| to_screen(p * shrink)).collect(),
+        egui::Stroke::new(1., EDGE_EXCLUSION_COLOR),
+    ));
+    painter.add(egui::Shape::closed_line(
+        outline.into_iter().map(to_screen).collect(),
+        egui::Stroke::new(1.5, GRID_COLOR),
+    ));
+    hovered
+}
+
 #[must_use]
 fn check_key(key: &mut Option<LimitKey>, state: &super::AppState) -> Option<(LimitKey, i32, i32)> {
     if let Some(limit_key) = key.as_ref() {
-        if let Some(limit) = state.limits.get(limit_key) {
+        if let Some(limit) = state.center.limit(limit_key) {
             if let LimitDataKind::Int {
                 uniques: _,
                 min,
@@ -558,3 +1287,88 @@ fn check_key(key: &mut Option<LimitKey>, state: &super::AppState) -> Option<(Lim
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{stack::Stacking, Coloring, HeatmapImages};
+    use crate::app::files::FileData;
+    use data_format::{ColumnInfo, DataFormat};
+
+    #[test]
+    fn stacked_images() {
+        // values above 0.55 fail, the first wafer fails entirely
+        let file = |values: Vec<f32>| {
+            let mut data = DataFormat::new("", vec![0, 1, 2], vec![0, 0, 0]).unwrap();
+            data.add_data("Vth", values, ColumnInfo::new(Some(0.), Some(0.55)))
+                .unwrap();
+            FileData::from(data)
+        };
+        let center = crate::app::report::center_from_files(
+            vec![
+                ("failing".into(), file(vec![1., 1., 1.])),
+                ("passing".into(), file(vec![0.1, 0.2, 0.9])),
+            ],
+            None,
+        );
+        let key = |label: &str| {
+            center
+                .limits()
+                .find(|(_, limit)| limit.get_label().as_str() == label)
+                .unwrap()
+        };
+        let (x_key, _) = key(data_format::COLUMN_LABEL);
+        let (y_key, _) = key(data_format::ROW_LABEL);
+        let (limit_key, limit) = key("Vth");
+        let files = center
+            .files()
+            .map(|(key, label, data)| super::super::PlotFile {
+                key,
+                label,
+                data,
+                sorting: center.limit_indices(key).unwrap(),
+                filtering: center.filters_summed(key).unwrap(),
+            })
+            .collect::<Vec<_>>();
+        let images = |stacking, files: &[super::super::PlotFile]| {
+            let coloring = Coloring {
+                bins: None,
+                color_map: &Default::default(),
+                stacking,
+            };
+            HeatmapImages::new(
+                (x_key.clone(), 0, 2),
+                (y_key.clone(), 0, 0),
+                limit_key,
+                limit,
+                coloring,
+                files,
+            )
+            .ok()
+        };
+
+        // the failing wafer counts: one failure on each die, two on the last one
+        let stacked = images(Some(Stacking::FailCount), &files).unwrap();
+        assert_eq!(stacked.images.len(), 1);
+        assert_eq!(
+            stacked.images[0].1.as_str(),
+            "Stacked: fail count (2 files)"
+        );
+        assert!(stacked.images[0].2.iter().all(Option::is_some));
+        assert_eq!((stacked.scale.value(0.), stacked.scale.value(1.)), (1., 2.));
+        let stacked = images(Some(Stacking::FailPercent), &files[..1]).unwrap();
+        assert_eq!(stacked.scale.value(0.), 100.);
+        // without values, e.g. as all dies failed, the statistics show the filtered dies
+        let stacked = images(Some(Stacking::Mean), &files[..1]).unwrap();
+        assert!(stacked.images[0]
+            .2
+            .iter()
+            .all(|&color| color == Some(super::FILTERED_COLOR)));
+
+        // each file on its own, the failing wafer is not shown
+        let single = images(None, &files).unwrap();
+        assert_eq!(single.images.len(), 1);
+        assert_eq!(single.images[0].1.as_str(), "passing");
+        assert!(images(None, &files[..1]).is_none());
+        assert!(images(Some(Stacking::FailCount), &[]).is_none());
+    }
+}
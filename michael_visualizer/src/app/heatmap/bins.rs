@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    data_types::{FileLabel, LimitKey},
+    LocalizableStr,
+};
+
+/// Name and color given to a bin by the user
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+struct Bin {
+    name: String,
+    color: egui::Color32,
+}
+
+/// Bins of the integer columns, bins without settings are named by their value and colored by
+/// their position among the values of the column, so a bin keeps its color regardless of the
+/// filtering and all bins of a column have distinct colors
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(crate) struct BinSettings(HashMap<LimitKey, BTreeMap<i32, Bin>>);
+impl BinSettings {
+    fn get(&self, limit_key: &LimitKey, value: i32) -> Option<&Bin> {
+        self.0.get(limit_key).and_then(|bins| bins.get(&value))
+    }
+
+    /// Values are all values of the column, sorted
+    pub(crate) fn color(&self, limit_key: &LimitKey, value: i32, values: &[i32]) -> egui::Color32 {
+        self.get(limit_key, value)
+            .map(|bin| bin.color)
+            .unwrap_or_else(|| {
+                let index = values.binary_search(&value).unwrap_or_else(|index| index);
+                default_color(index, values.len().max(index + 1))
+            })
+    }
+
+    pub(crate) fn name(&self, limit_key: &LimitKey, value: i32) -> String {
+        self.get(limit_key, value)
+            .map(|bin| bin.name.clone())
+            .unwrap_or_else(|| value.to_string())
+    }
+
+    /// Bins of the legend with their counts per file, name and color are editable
+    /// Returns true if any name or color changed
+    pub(crate) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        language: crate::Language,
+        limit_key: &LimitKey,
+        legend: &Legend,
+    ) -> bool {
+        let mut changed = false;
+        egui::ScrollArea::both()
+            .max_height(200.)
+            .id_source("Bins")
+            .show(ui, |ui| {
+                egui::Grid::new("Bins").striped(true).show(ui, |ui| {
+                    for header in ["Bin", "Name", "Color"] {
+                        ui.strong(LocalizableStr { english: header }.localize(language));
+                    }
+                    for (label, _, _) in &legend.files {
+                        ui.strong(label.as_str());
+                    }
+                    ui.end_row();
+                    for (&value, (name, color)) in legend.values.iter().zip(&legend.bins) {
+                        ui.label(value.to_string());
+                        let mut bin = Bin {
+                            name: name.clone(),
+                            color: *color,
+                        };
+                        let mut edited = ui
+                            .add(egui::TextEdit::singleline(&mut bin.name).desired_width(100.))
+                            .changed();
+                        ui.horizontal(|ui| {
+                            edited |= ui.color_edit_button_srgba(&mut bin.color).changed();
+                            if self.get(limit_key, value).is_some()
+                                && ui
+                                    .small_button("⟲")
+                                    .on_hover_text(
+                                        LocalizableStr {
+                                            english: "Reset name and color",
+                                        }
+                                        .localize(language),
+                                    )
+                                    .clicked()
+                            {
+                                if let Some(bins) = self.0.get_mut(limit_key) {
+                                    bins.remove(&value);
+                                }
+                                changed = true;
+                            }
+                        });
+                        if edited {
+                            self.0
+                                .entry(limit_key.clone())
+                                .or_default()
+                                .insert(value, bin);
+                            changed = true;
+                        }
+                        for (_, counts, rows) in &legend.files {
+                            let count = counts.get(&value).copied().unwrap_or_default();
+                            ui.label(format!(
+                                "{count} ({:.2}%)",
+                                100. * count as f64 / (*rows).max(1) as f64
+                            ));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        changed
+    }
+}
+
+/// Rows per bin of each file, filtered rows are not counted
+#[derive(Clone, Default)]
+pub(crate) struct Legend {
+    /// Bins present in any file, sorted
+    pub(crate) values: Vec<i32>,
+    /// Label, rows per bin and count of rows which are not filtered
+    pub(crate) files: Vec<(FileLabel, BTreeMap<i32, usize>, usize)>,
+    /// Name and color of each bin at the time of computation, same order as the values
+    pub(crate) bins: Vec<(String, egui::Color32)>,
+}
+impl Legend {
+    pub(crate) fn push(&mut self, label: FileLabel, values: &[i32], filtering: &[u32]) {
+        let counts = count(values, filtering);
+        let rows = counts.values().sum();
+        self.files.push((label, counts, rows));
+    }
+
+    /// Collects the bins of all files, after all of them are pushed
+    /// Column values are all values of the column, sorted
+    pub(crate) fn finish(
+        &mut self,
+        settings: &BinSettings,
+        limit_key: &LimitKey,
+        column_values: &[i32],
+    ) {
+        let mut values = self
+            .files
+            .iter()
+            .flat_map(|(_, counts, _)| counts.keys().copied())
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+        self.bins = values
+            .iter()
+            .map(|&value| {
+                (
+                    settings.name(limit_key, value),
+                    settings.color(limit_key, value, column_values),
+                )
+            })
+            .collect();
+        self.values = values;
+    }
+}
+
+/// Up to 26 bins get the distinguishable colors, more bins get evenly spaced hues
+/// Neither is black, which would look like a die without data
+fn default_color(index: usize, count: usize) -> egui::Color32 {
+    let colors = egui_heatmap::colors::DISTINGUISHABLE_COLORS;
+    if count <= colors.len() {
+        colors[index]
+    } else {
+        // neighbouring hues alternate in brightness, to tell them apart
+        let value = if index.is_multiple_of(2) { 1. } else { 0.7 };
+        egui::ecolor::Hsva::new(index as f32 / count as f32, 0.9, value, 1.).into()
+    }
+}
+
+/// Rows per value, only rows which are not filtered
+fn count(values: &[i32], filtering: &[u32]) -> BTreeMap<i32, usize> {
+    let mut counts = BTreeMap::new();
+    for (&value, _) in values.iter().zip(filtering).filter(|(_, &f)| f == 0) {
+        *counts.entry(value).or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bins() {
+        let counts = count(&[1, 2, 1, 7, 1], &[0, 0, 1, 0, 0]);
+        assert_eq!(counts, BTreeMap::from([(1, 2), (2, 1), (7, 1)]));
+
+        let limit_key = LimitKey::default();
+        let mut settings = BinSettings::default();
+        let values = [1, 2, 7];
+        let default = settings.color(&limit_key, 1, &values);
+        assert_eq!(settings.name(&limit_key, 1), "1");
+        assert_ne!(default, settings.color(&limit_key, 2, &values));
+        settings.0.entry(limit_key.clone()).or_default().insert(
+            1,
+            Bin {
+                name: "Pass".into(),
+                color: egui::Color32::GREEN,
+            },
+        );
+        assert_eq!(settings.name(&limit_key, 1), "Pass");
+        assert_eq!(settings.color(&limit_key, 1, &values), egui::Color32::GREEN);
+        // other bins keep their color
+        assert_eq!(
+            settings.color(&limit_key, 2, &values),
+            BinSettings::default().color(&limit_key, 2, &values)
+        );
+
+        // up to 100 bins, all distinct and none black
+        for count in [26, 27, 100] {
+            let colors = (0..count)
+                .map(|index| default_color(index, count))
+                .collect::<std::collections::HashSet<_>>();
+            assert_eq!(colors.len(), count);
+            assert!(!colors.contains(&super::super::BACKGROUND_COLOR));
+        }
+    }
+}
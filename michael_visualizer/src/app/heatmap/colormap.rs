@@ -0,0 +1,448 @@
+use egui::Color32;
+
+use crate::LocalizableStr;
+
+/// Sampled evenly from the matplotlib color maps
+const VIRIDIS: [Color32; 9] = [
+    Color32::from_rgb(68, 1, 84),
+    Color32::from_rgb(71, 44, 122),
+    Color32::from_rgb(59, 81, 139),
+    Color32::from_rgb(44, 113, 142),
+    Color32::from_rgb(33, 144, 141),
+    Color32::from_rgb(39, 173, 129),
+    Color32::from_rgb(92, 200, 99),
+    Color32::from_rgb(170, 220, 50),
+    Color32::from_rgb(253, 231, 37),
+];
+const CIVIDIS: [Color32; 9] = [
+    Color32::from_rgb(0, 34, 78),
+    Color32::from_rgb(18, 53, 112),
+    Color32::from_rgb(59, 73, 108),
+    Color32::from_rgb(87, 93, 109),
+    Color32::from_rgb(112, 113, 115),
+    Color32::from_rgb(138, 134, 120),
+    Color32::from_rgb(165, 156, 116),
+    Color32::from_rgb(195, 179, 105),
+    Color32::from_rgb(254, 232, 56),
+];
+/// Blue - white - red, as the ColorBrewer RdBu map
+const DIVERGING: [Color32; 5] = [
+    Color32::from_rgb(33, 102, 172),
+    Color32::from_rgb(146, 197, 222),
+    Color32::from_rgb(247, 247, 247),
+    Color32::from_rgb(244, 165, 130),
+    Color32::from_rgb(178, 24, 43),
+];
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Default, Debug)]
+pub(crate) enum Palette {
+    /// Blue - green - red
+    #[default]
+    Classic,
+    Viridis,
+    Cividis,
+    Diverging,
+    Custom,
+}
+impl Palette {
+    fn all() -> [Palette; 5] {
+        [
+            Palette::Classic,
+            Palette::Viridis,
+            Palette::Cividis,
+            Palette::Diverging,
+            Palette::Custom,
+        ]
+    }
+
+    fn label(&self) -> LocalizableStr<'static> {
+        match self {
+            Palette::Classic => LocalizableStr {
+                english: "Blue - green - red",
+            },
+            Palette::Viridis => LocalizableStr { english: "Viridis" },
+            Palette::Cividis => LocalizableStr { english: "Cividis" },
+            Palette::Diverging => LocalizableStr {
+                english: "Diverging",
+            },
+            Palette::Custom => LocalizableStr { english: "Custom" },
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Default, Debug)]
+pub(crate) enum Scaling {
+    #[default]
+    Linear,
+    /// Logarithmic, non-positive values get the lowest color
+    Log,
+    /// Linear, zero in the middle
+    Symmetric,
+    /// Linear between two percentiles of the data, so outliers do not wash out the map
+    Percentile,
+}
+impl Scaling {
+    fn all() -> [Scaling; 4] {
+        [
+            Scaling::Linear,
+            Scaling::Log,
+            Scaling::Symmetric,
+            Scaling::Percentile,
+        ]
+    }
+
+    fn label(&self) -> LocalizableStr<'static> {
+        match self {
+            Scaling::Linear => LocalizableStr { english: "Linear" },
+            Scaling::Log => LocalizableStr {
+                english: "Logarithmic",
+            },
+            Scaling::Symmetric => LocalizableStr {
+                english: "Symmetric around zero",
+            },
+            Scaling::Percentile => LocalizableStr {
+                english: "Percentile clipped",
+            },
+        }
+    }
+}
+
+/// Palette and scaling chosen by the user
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub(crate) struct ColorMap {
+    palette: Palette,
+    reversed: bool,
+    /// Stops of the custom palette, at least two
+    custom: Vec<Color32>,
+    scaling: Scaling,
+    /// Lower and upper percentile of the percentile scaling
+    clip: (f32, f32),
+}
+impl Default for ColorMap {
+    fn default() -> Self {
+        Self {
+            palette: Default::default(),
+            reversed: false,
+            custom: vec![Color32::from_rgb(0, 0, 96), Color32::YELLOW],
+            scaling: Default::default(),
+            clip: (1., 99.),
+        }
+    }
+}
+
+impl ColorMap {
+    /// The shown values are needed to compute the scale
+    pub(crate) fn needs_values(&self) -> bool {
+        matches!(self.scaling, Scaling::Log | Scaling::Percentile)
+    }
+
+    /// `(lower, upper)` are the limits of the column, or the range of its data if there are none
+    /// `sorted` are the shown values, only needed if `needs_values`
+    pub(crate) fn scale(&self, (lower, upper): (f32, f32), sorted: &[f64]) -> ColorScale {
+        let (log, range) = match self.scaling {
+            Scaling::Linear => (false, (lower, upper)),
+            Scaling::Symmetric => {
+                let max = lower.abs().max(upper.abs());
+                (false, (-max, max))
+            }
+            Scaling::Percentile if !sorted.is_empty() => {
+                let (low, high) = self.clip;
+                let percentile = |percent: f32| {
+                    super::super::statistics::percentile(sorted, percent.into()) as f32
+                };
+                (false, (percentile(low), percentile(high)))
+            }
+            Scaling::Percentile => (false, (lower, upper)),
+            Scaling::Log if upper > 0. => {
+                let lower = if lower > 0. {
+                    lower
+                } else {
+                    sorted
+                        .iter()
+                        .find(|&&v| v > 0.)
+                        .map(|&v| v as f32)
+                        .unwrap_or(upper / 1000.)
+                };
+                (true, (lower, upper))
+            }
+            // nothing to show logarithmically
+            Scaling::Log => (false, (lower, upper)),
+        };
+        let colors = match self.palette {
+            Palette::Classic => Colors::Classic(classic(self.reversed), self.reversed),
+            palette => {
+                let mut stops = match palette {
+                    Palette::Classic => unreachable!("Handled above"),
+                    Palette::Viridis => VIRIDIS.to_vec(),
+                    Palette::Cividis => CIVIDIS.to_vec(),
+                    Palette::Diverging => DIVERGING.to_vec(),
+                    Palette::Custom => self.custom.clone(),
+                };
+                if self.reversed {
+                    stops.reverse();
+                }
+                Colors::Stops(stops)
+            }
+        };
+        ColorScale { colors, log, range }
+    }
+
+    /// Editor of palette and scaling, returns true if it changed
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, language: crate::Language) -> bool {
+        let Self {
+            palette,
+            reversed,
+            custom,
+            scaling,
+            clip,
+        } = self;
+        let mut changed = false;
+        egui::Grid::new("ColorMap").show(ui, |ui| {
+            ui.label(LocalizableStr { english: "Palette" }.localize(language));
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("Palette")
+                    .selected_text(palette.label().localize(language))
+                    .show_ui(ui, |ui| {
+                        for p in Palette::all() {
+                            changed |= ui
+                                .selectable_value(palette, p, p.label().localize(language))
+                                .changed();
+                        }
+                    });
+                changed |= ui
+                    .checkbox(
+                        reversed,
+                        LocalizableStr {
+                            english: "Reversed",
+                        }
+                        .localize(language),
+                    )
+                    .changed();
+            });
+            ui.end_row();
+            if *palette == Palette::Custom {
+                ui.label(LocalizableStr { english: "Colors" }.localize(language));
+                ui.horizontal(|ui| {
+                    for color in custom.iter_mut() {
+                        changed |= ui.color_edit_button_srgba(color).changed();
+                    }
+                    if ui.small_button("+").clicked() {
+                        custom.push(custom.last().copied().unwrap_or(Color32::WHITE));
+                        changed = true;
+                    }
+                    if custom.len() > 2 && ui.small_button("-").clicked() {
+                        custom.pop();
+                        changed = true;
+                    }
+                });
+                ui.end_row();
+            }
+            ui.label(LocalizableStr { english: "Scaling" }.localize(language));
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("Scaling")
+                    .selected_text(scaling.label().localize(language))
+                    .show_ui(ui, |ui| {
+                        for s in Scaling::all() {
+                            changed |= ui
+                                .selectable_value(scaling, s, s.label().localize(language))
+                                .changed();
+                        }
+                    });
+                if *scaling == Scaling::Percentile {
+                    let (low, high) = clip;
+                    for (value, range) in [(low, 0.0..=50.), (high, 50.0..=100.)] {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(value)
+                                    .speed(0.1)
+                                    .clamp_range(range)
+                                    .suffix(" %"),
+                            )
+                            .changed();
+                    }
+                }
+            });
+            ui.end_row();
+        });
+        changed
+    }
+}
+
+fn classic(reversed: bool) -> egui_heatmap::colors::Gradient<Color32> {
+    if reversed {
+        egui_heatmap::colors::Gradient::with_options(
+            &egui_heatmap::colors::ColorGradientOptions::StartCenterEnd {
+                start: Color32::RED,
+                center: Color32::GREEN,
+                end: Color32::BLUE,
+                steps: 63,
+            },
+        )
+    } else {
+        super::gradient()
+    }
+}
+
+enum Colors {
+    /// The gradient of the heatmap widget, and whether it is reversed
+    Classic(egui_heatmap::colors::Gradient<Color32>, bool),
+    /// Interpolated linearly
+    Stops(Vec<Color32>),
+}
+
+/// Colors of the values of one heatmap
+pub(crate) struct ColorScale {
+    colors: Colors,
+    log: bool,
+    range: (f32, f32),
+}
+impl ColorScale {
+    /// Position of a value on the colorbar, 0 at the lower end of the range, 1 at the upper end
+    /// The middle, if the range is empty, e.g. since all values are equal
+    fn position(&self, value: f32) -> f32 {
+        let (lower, upper) = self.range;
+        if self.log {
+            if value <= 0. {
+                0.
+            } else if upper.ln() - lower.ln() == 0. {
+                0.5
+            } else {
+                (value.ln() - lower.ln()) / (upper.ln() - lower.ln())
+            }
+        } else if upper - lower == 0. {
+            0.5
+        } else {
+            (value - lower) / (upper - lower)
+        }
+    }
+
+    /// Value at a position of the colorbar
+    pub(crate) fn value(&self, position: f32) -> f32 {
+        let (lower, upper) = self.range;
+        if self.log {
+            (lower.ln() + (upper.ln() - lower.ln()) * position).exp()
+        } else {
+            lower + (upper - lower) * position
+        }
+    }
+
+    pub(crate) fn color_at(&self, position: f32) -> Color32 {
+        match &self.colors {
+            Colors::Classic(gradient, _) => gradient.lookup_color(position),
+            Colors::Stops(stops) => interpolate(stops, position),
+        }
+    }
+
+    pub(crate) fn color(&self, value: f32) -> Color32 {
+        self.color_at(self.position(value))
+    }
+
+    /// Colorbar of the heatmap widget, which shows linear scales of its own gradients only
+    pub(crate) fn widget_colorbar(
+        &self,
+    ) -> Option<(egui_heatmap::colors::Gradient<Color32>, usize, (f32, f32))> {
+        match self.colors {
+            Colors::Classic(_, reversed) if !self.log => Some((classic(reversed), 100, self.range)),
+            _ => None,
+        }
+    }
+
+    /// Horizontal colorbar with lower, middle and upper value, for colors the heatmap widget can
+    /// not show
+    pub(crate) fn show(&self, ui: &mut egui::Ui) {
+        const STEPS: usize = 100;
+        let height = ui.text_style_height(&egui::TextStyle::Body);
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(ui.available_width(), 2. * height),
+            egui::Sense::hover(),
+        );
+        let bar =
+            egui::Rect::from_min_size(response.rect.min, egui::vec2(response.rect.width(), height));
+        let step_width = bar.width() / STEPS as f32;
+        let mut mesh = egui::Mesh::default();
+        for step in 0..STEPS {
+            let left = bar.min.x + step as f32 * step_width;
+            mesh.add_colored_rect(
+                egui::Rect::from_x_y_ranges(left..=left + step_width, bar.y_range()),
+                self.color_at((step as f32 + 0.5) / STEPS as f32),
+            );
+        }
+        painter.add(mesh);
+        let font = egui::TextStyle::Body.resolve(ui.style());
+        for (position, align) in [
+            (0., egui::Align2::LEFT_TOP),
+            (0.5, egui::Align2::CENTER_TOP),
+            (1., egui::Align2::RIGHT_TOP),
+        ] {
+            painter.text(
+                egui::pos2(bar.min.x + position * bar.width(), bar.max.y),
+                align,
+                self.value(position),
+                font.clone(),
+                ui.visuals().text_color(),
+            );
+        }
+    }
+}
+
+fn interpolate(stops: &[Color32], position: f32) -> Color32 {
+    let position = if position.is_nan() {
+        0.
+    } else {
+        position.clamp(0., 1.)
+    };
+    match stops {
+        [] => super::BACKGROUND_COLOR,
+        [color] => *color,
+        _ => {
+            let position = position * (stops.len() - 1) as f32;
+            let index = (position.floor() as usize).min(stops.len() - 2);
+            let fraction = position - index as f32;
+            let (a, b) = (stops[index], stops[index + 1]);
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+            Color32::from_rgb(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scales() {
+        let mut map = ColorMap {
+            palette: Palette::Viridis,
+            ..Default::default()
+        };
+        let scale = map.scale((0., 10.), &[]);
+        assert_eq!(scale.color(0.), VIRIDIS[0]);
+        assert_eq!(scale.color(10.), VIRIDIS[8]);
+        assert_eq!(scale.color(20.), VIRIDIS[8]);
+        assert_eq!(scale.value(0.5), 5.);
+        // all values equal
+        assert_eq!(map.scale((3., 3.), &[]).position(3.), 0.5);
+
+        map.reversed = true;
+        assert_eq!(map.scale((0., 10.), &[]).color(0.), VIRIDIS[8]);
+
+        map.scaling = Scaling::Symmetric;
+        assert_eq!(map.scale((-2., 10.), &[]).range, (-10., 10.));
+
+        map.scaling = Scaling::Log;
+        let scale = map.scale((0., 100.), &[0., 1., 50., 100.]);
+        assert_eq!(scale.range, (1., 100.));
+        assert!((scale.position(10.) - 0.5).abs() < 1e-6);
+        assert!((scale.value(0.5) - 10.).abs() < 1e-4);
+        assert_eq!(scale.position(-1.), 0.);
+        assert_eq!(map.scale((2., 2.), &[2.]).position(2.), 0.5);
+
+        // a single outlier no longer stretches the scale
+        map.scaling = Scaling::Percentile;
+        map.clip = (0., 90.);
+        let mut sorted = (0..20).map(f64::from).collect::<Vec<_>>();
+        sorted.push(1000.);
+        let scale = map.scale((0., 1000.), &sorted);
+        assert_eq!(scale.range, (0., 18.));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::LocalizableStr;
+
+/// Combination of the dies at the same position over all shown files
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Stacking {
+    Mean,
+    Median,
+    StdDev,
+    /// Files in which the die is filtered
+    FailCount,
+    /// Share of the files with this die, in which it is filtered
+    FailPercent,
+}
+impl Stacking {
+    pub(crate) fn all() -> [Stacking; 5] {
+        [
+            Stacking::Mean,
+            Stacking::Median,
+            Stacking::StdDev,
+            Stacking::FailCount,
+            Stacking::FailPercent,
+        ]
+    }
+
+    pub(crate) fn label(&self) -> LocalizableStr<'static> {
+        match self {
+            Stacking::Mean => LocalizableStr {
+                english: "Stacked: mean",
+            },
+            Stacking::Median => LocalizableStr {
+                english: "Stacked: median",
+            },
+            Stacking::StdDev => LocalizableStr {
+                english: "Stacked: standard deviation",
+            },
+            Stacking::FailCount => LocalizableStr {
+                english: "Stacked: fail count",
+            },
+            Stacking::FailPercent => LocalizableStr {
+                english: "Stacked: fail percentage",
+            },
+        }
+    }
+
+    /// The result is in the unit of the column, so its limits apply to the scale
+    pub(crate) fn uses_limits(&self) -> bool {
+        matches!(self, Stacking::Mean | Stacking::Median)
+    }
+
+    /// None if the position has no die, or, for the statistics, all its dies are filtered
+    pub(crate) fn combine(&self, position: &mut Position) -> Option<f32> {
+        let Position { values, failed } = position;
+        let present = values.len() + *failed;
+        let mean = || values.iter().sum::<f32>() / values.len() as f32;
+        match self {
+            _ if present == 0 => None,
+            Stacking::Mean | Stacking::Median | Stacking::StdDev if values.is_empty() => None,
+            Stacking::Mean => Some(mean()),
+            Stacking::Median => {
+                values.sort_by(|a, b| a.total_cmp(b));
+                let middle = values.len() / 2;
+                Some(if values.len() % 2 == 0 {
+                    (values[middle - 1] + values[middle]) / 2.
+                } else {
+                    values[middle]
+                })
+            }
+            Stacking::StdDev => {
+                let mean = mean();
+                let variance =
+                    values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+                Some(variance.sqrt())
+            }
+            Stacking::FailCount => Some(*failed as f32),
+            Stacking::FailPercent => Some(100. * *failed as f32 / present as f32),
+        }
+    }
+}
+
+/// Dies of one position over all files
+#[derive(Clone, Default, Debug)]
+pub(crate) struct Position {
+    /// Values of the dies which are not filtered
+    values: Vec<f32>,
+    failed: usize,
+}
+impl Position {
+    pub(crate) fn push(&mut self, value: f32, filtered: bool) {
+        if filtered {
+            self.failed += 1;
+        } else {
+            self.values.push(value);
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.failed == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn combine() {
+        let mut position = Position::default();
+        for stacking in Stacking::all() {
+            assert_eq!(stacking.combine(&mut position), None);
+        }
+        for (value, filtered) in [(4., false), (1., false), (100., true), (7., false)] {
+            position.push(value, filtered);
+        }
+        let combined = Stacking::all().map(|stacking| stacking.combine(&mut position.clone()));
+        assert_eq!(
+            combined,
+            [Some(4.), Some(4.), Some(6f32.sqrt()), Some(1.), Some(25.)]
+        );
+
+        // all dies filtered: no statistics, but the failures count
+        let mut position = Position::default();
+        position.push(1., true);
+        assert_eq!(Stacking::Mean.combine(&mut position), None);
+        assert_eq!(Stacking::FailPercent.combine(&mut position), Some(100.));
+    }
+}
@@ -0,0 +1,327 @@
+use crate::LocalizableStr;
+
+/// Distance of the flat from the center, relative to the radius (SEMI flat of a 200 mm wafer)
+const FLAT_DISTANCE: f32 = 0.958;
+/// Depth and half of the width of the notch in mm
+const NOTCH_DEPTH: f32 = 1.;
+const NOTCH_HALF_WIDTH: f32 = 1.5;
+/// Points of the outline
+const OUTLINE_STEPS: usize = 180;
+
+/// Side of the wafer with the notch or flat, as seen in the map
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Default, Debug)]
+pub(crate) enum Orientation {
+    #[default]
+    Down,
+    Left,
+    Up,
+    Right,
+}
+impl Orientation {
+    fn all() -> [Orientation; 4] {
+        [
+            Orientation::Down,
+            Orientation::Left,
+            Orientation::Up,
+            Orientation::Right,
+        ]
+    }
+
+    fn label(&self) -> LocalizableStr<'static> {
+        match self {
+            Orientation::Down => LocalizableStr { english: "Down" },
+            Orientation::Left => LocalizableStr { english: "Left" },
+            Orientation::Up => LocalizableStr { english: "Up" },
+            Orientation::Right => LocalizableStr { english: "Right" },
+        }
+    }
+
+    /// Unit vector from the center, y points down as the rows of the map
+    fn direction(&self) -> egui::Vec2 {
+        match self {
+            Orientation::Down => egui::vec2(0., 1.),
+            Orientation::Left => egui::vec2(-1., 0.),
+            Orientation::Up => egui::vec2(0., -1.),
+            Orientation::Right => egui::vec2(1., 0.),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Default, Debug)]
+pub(crate) enum OrientationMark {
+    #[default]
+    Notch,
+    Flat,
+}
+impl OrientationMark {
+    fn label(&self) -> LocalizableStr<'static> {
+        match self {
+            OrientationMark::Notch => LocalizableStr { english: "Notch" },
+            OrientationMark::Flat => LocalizableStr { english: "Flat" },
+        }
+    }
+}
+
+/// Position of a die relative to the wafer
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum DieSite {
+    Inside,
+    /// On the wafer, but within the edge exclusion
+    EdgeExclusion,
+    /// Not entirely on the wafer
+    Outside,
+}
+
+/// Physical layout of the wafer, lengths in mm
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub(crate) struct WaferGeometry {
+    diameter: f32,
+    pitch_x: f32,
+    pitch_y: f32,
+    orientation: Orientation,
+    mark: OrientationMark,
+    edge_exclusion: f32,
+    /// Die coordinate at the center of the wafer, the middle of the dies if none
+    center: Option<(f32, f32)>,
+}
+impl Default for WaferGeometry {
+    fn default() -> Self {
+        Self {
+            diameter: 200.,
+            pitch_x: 10.,
+            pitch_y: 10.,
+            orientation: Default::default(),
+            mark: Default::default(),
+            edge_exclusion: 3.,
+            center: None,
+        }
+    }
+}
+
+impl WaferGeometry {
+    pub(crate) fn radius(&self) -> f32 {
+        self.diameter / 2.
+    }
+
+    pub(crate) fn edge_exclusion(&self) -> f32 {
+        self.edge_exclusion
+    }
+
+    pub(crate) fn pitch(&self) -> egui::Vec2 {
+        egui::vec2(self.pitch_x, self.pitch_y)
+    }
+
+    /// Die coordinate at the center of the wafer, `middle` is used if none is configured
+    pub(crate) fn center(&self, middle: (f32, f32)) -> egui::Vec2 {
+        let (x, y) = self.center.unwrap_or(middle);
+        egui::vec2(x, y)
+    }
+
+    /// Position of the center of a die in mm, relative to the wafer center
+    pub(crate) fn die_position(&self, die: (i32, i32), center: egui::Vec2) -> egui::Vec2 {
+        (egui::vec2(die.0 as f32, die.1 as f32) - center) * self.pitch()
+    }
+
+    /// Distance of a point from the wafer edge towards the center, negative outside
+    fn inset(&self, point: egui::Vec2) -> f32 {
+        let inset = self.radius() - point.length();
+        match self.mark {
+            OrientationMark::Notch => inset,
+            OrientationMark::Flat => {
+                let flat = FLAT_DISTANCE * self.radius() - point.dot(self.orientation.direction());
+                inset.min(flat)
+            }
+        }
+    }
+
+    pub(crate) fn site(&self, die: (i32, i32), center: egui::Vec2) -> DieSite {
+        let position = self.die_position(die, center);
+        let half = self.pitch() / 2.;
+        let inset = [(-1., -1.), (-1., 1.), (1., -1.), (1., 1.)]
+            .into_iter()
+            .map(|(x, y)| self.inset(position + egui::vec2(x * half.x, y * half.y)))
+            .fold(f32::INFINITY, f32::min);
+        if inset < 0. {
+            DieSite::Outside
+        } else if inset < self.edge_exclusion {
+            DieSite::EdgeExclusion
+        } else {
+            DieSite::Inside
+        }
+    }
+
+    /// Dies which are at least partially on the wafer, as inclusive ranges of die coordinates
+    pub(crate) fn die_range(
+        &self,
+        center: egui::Vec2,
+    ) -> (std::ops::RangeInclusive<i32>, std::ops::RangeInclusive<i32>) {
+        let half = egui::vec2(self.radius() / self.pitch_x, self.radius() / self.pitch_y);
+        let range = |center: f32, half: f32| {
+            (center - half - 0.5).floor() as i32..=(center + half + 0.5).ceil() as i32
+        };
+        (range(center.x, half.x), range(center.y, half.y))
+    }
+
+    /// Outline in mm relative to the center, with notch or flat, each point is visible from the center
+    pub(crate) fn outline(&self) -> Vec<egui::Vec2> {
+        let direction = self.orientation.direction();
+        let base = direction.y.atan2(direction.x);
+        let r = self.radius();
+        let half_angle = match self.mark {
+            OrientationMark::Notch => (NOTCH_HALF_WIDTH / r).min(1.).asin(),
+            OrientationMark::Flat => 0.,
+        };
+        let points = (0..=OUTLINE_STEPS)
+            .map(|step| {
+                let angle = base
+                    + half_angle
+                    + (std::f32::consts::TAU - 2. * half_angle) * step as f32
+                        / OUTLINE_STEPS as f32;
+                let point = egui::vec2(angle.cos(), angle.sin()) * r;
+                match self.mark {
+                    OrientationMark::Notch => point,
+                    OrientationMark::Flat => {
+                        let beyond = point.dot(direction) - FLAT_DISTANCE * r;
+                        point - direction * beyond.max(0.)
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        match self.mark {
+            // the tip closes the outline between both ends of the arc
+            OrientationMark::Notch => std::iter::once(direction * (r - NOTCH_DEPTH))
+                .chain(points)
+                .collect(),
+            OrientationMark::Flat => points,
+        }
+    }
+
+    /// Editor of the geometry, returns true if it changed
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, language: crate::Language) -> bool {
+        let Self {
+            diameter,
+            pitch_x,
+            pitch_y,
+            orientation,
+            mark,
+            edge_exclusion,
+            center,
+        } = self;
+        let mut changed = false;
+        egui::Grid::new("WaferGeometry").show(ui, |ui| {
+            ui.label(
+                LocalizableStr {
+                    english: "Diameter",
+                }
+                .localize(language),
+            );
+            changed |= ui.add(mm(diameter, 10.0..=450.)).changed();
+            ui.end_row();
+            ui.label(
+                LocalizableStr {
+                    english: "Die pitch",
+                }
+                .localize(language),
+            );
+            ui.horizontal(|ui| {
+                changed |= ui.add(mm(pitch_x, 0.1..=100.)).changed();
+                ui.label("×");
+                changed |= ui.add(mm(pitch_y, 0.1..=100.)).changed();
+            });
+            ui.end_row();
+            ui.label(
+                LocalizableStr {
+                    english: "Edge exclusion",
+                }
+                .localize(language),
+            );
+            let radius = *diameter / 2.;
+            changed |= ui.add(mm(edge_exclusion, 0.0..=radius)).changed();
+            ui.end_row();
+            ui.label(
+                LocalizableStr {
+                    english: "Orientation",
+                }
+                .localize(language),
+            );
+            ui.horizontal(|ui| {
+                for m in [OrientationMark::Notch, OrientationMark::Flat] {
+                    changed |= ui
+                        .radio_value(mark, m, m.label().localize(language))
+                        .changed();
+                }
+                egui::ComboBox::from_id_source("WaferOrientation")
+                    .selected_text(orientation.label().localize(language))
+                    .show_ui(ui, |ui| {
+                        for o in Orientation::all() {
+                            changed |= ui
+                                .selectable_value(orientation, o, o.label().localize(language))
+                                .changed();
+                        }
+                    });
+            });
+            ui.end_row();
+            let mut automatic = center.is_none();
+            if ui
+                .checkbox(
+                    &mut automatic,
+                    LocalizableStr {
+                        english: "Center in the middle of the dies",
+                    }
+                    .localize(language),
+                )
+                .changed()
+            {
+                *center = (!automatic).then_some((0., 0.));
+                changed = true;
+            }
+            if let Some((x, y)) = center {
+                ui.horizontal(|ui| {
+                    changed |= ui.add(egui::DragValue::new(x).speed(0.1)).changed();
+                    changed |= ui.add(egui::DragValue::new(y).speed(0.1)).changed();
+                });
+            }
+            ui.end_row();
+        });
+        if *edge_exclusion > *diameter / 2. {
+            *edge_exclusion = *diameter / 2.;
+        }
+        changed
+    }
+}
+
+fn mm(value: &mut f32, range: std::ops::RangeInclusive<f32>) -> egui::DragValue<'_> {
+    egui::DragValue::new(value)
+        .speed(0.1)
+        .clamp_range(range)
+        .suffix(" mm")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sites() {
+        let mut wafer = WaferGeometry {
+            diameter: 100.,
+            ..Default::default()
+        };
+        let center = wafer.center((5., 5.));
+        assert_eq!(wafer.site((5, 5), center), DieSite::Inside);
+        assert_eq!(wafer.site((5, 9), center), DieSite::Inside);
+        // the outer corner is 47.4 mm away from the center, i.e. within 3 mm of the edge
+        assert_eq!(wafer.site((6, 9), center), DieSite::EdgeExclusion);
+        assert_eq!(wafer.site((9, 9), center), DieSite::Outside);
+        assert_eq!(wafer.site((5, 10), center), DieSite::Outside);
+        let (x, y) = wafer.die_range(center);
+        assert!(x.contains(&0) && x.contains(&10) && y.contains(&0) && y.contains(&10));
+
+        // the flat at the bottom is closer than the round edge
+        wafer.mark = OrientationMark::Flat;
+        assert_eq!(wafer.site((5, 1), center), DieSite::Inside);
+        assert_eq!(wafer.site((5, 9), center), DieSite::EdgeExclusion);
+        let outline = wafer.outline();
+        assert!(outline.iter().all(|p| p.y <= FLAT_DISTANCE * 50. + 1e-3));
+    }
+}
@@ -0,0 +1,392 @@
+use super::{files, limits, App, DataEvent};
+use crate::data_types::{FileKey, FileLabel, LimitKey};
+use crate::{Language, LocalizableStr};
+
+/// Older steps are dropped
+const LENGTH: usize = 100;
+
+/// Change done by the user, which can be reverted and done again
+pub(super) enum Change {
+    /// Label or values of a limit were edited
+    Limit {
+        key: LimitKey,
+        before: Box<limits::Limit>,
+        after: Box<limits::Limit>,
+    },
+    /// The file is kept while the removal is undone
+    FileRemoved {
+        key: FileKey,
+        label: FileLabel,
+        index: usize,
+        file: Option<Box<files::File>>,
+        /// Limits which were pruned with the file, they are restored with it
+        limits: super::PrunedLimits,
+    },
+    FileMoved {
+        key: FileKey,
+        label: FileLabel,
+        up: bool,
+    },
+}
+impl Change {
+    fn describe(&self, language: Language) -> String {
+        match self {
+            Change::Limit { before, after, .. } => {
+                let (before_label, after_label) = (before.get_label(), after.get_label());
+                if before_label != after_label {
+                    format!(
+                        "{}: '{}' → '{}'",
+                        LocalizableStr {
+                            english: "Rename limit"
+                        }
+                        .localize(language),
+                        before_label.as_str(),
+                        after_label.as_str()
+                    )
+                } else {
+                    let range = |limit: &limits::Limit| {
+                        let value = |v: Option<crate::data_types::finite_f32::FiniteF32>| {
+                            v.map(|v| v.to_string()).unwrap_or("-".into())
+                        };
+                        let (lower, upper) = limit.get_limits();
+                        let rule = limit.get_rule().describe(language);
+                        if rule.is_empty() {
+                            format!("[{}, {}]", value(lower), value(upper))
+                        } else {
+                            format!("[{}, {}] {rule}", value(lower), value(upper))
+                        }
+                    };
+                    format!(
+                        "{} '{}': {} → {}",
+                        LocalizableStr { english: "Limit" }.localize(language),
+                        after_label.as_str(),
+                        range(before),
+                        range(after)
+                    )
+                }
+            }
+            Change::FileRemoved { label, .. } => format!(
+                "{}: '{}'",
+                LocalizableStr {
+                    english: "Remove file"
+                }
+                .localize(language),
+                label.as_str()
+            ),
+            Change::FileMoved { label, up, .. } => format!(
+                "{}: '{}'",
+                if *up {
+                    LocalizableStr {
+                        english: "Move file up",
+                    }
+                } else {
+                    LocalizableStr {
+                        english: "Move file down",
+                    }
+                }
+                .localize(language),
+                label.as_str()
+            ),
+        }
+    }
+}
+
+/// Changes which are undone together, i.e. all changes caused by one user action
+struct Step {
+    changes: Vec<Change>,
+}
+impl Step {
+    fn describe(&self, language: Language) -> String {
+        let description = self.changes[0].describe(language);
+        match self.changes.len() {
+            1 => description,
+            n => format!(
+                "{description} (+{} {})",
+                n - 1,
+                LocalizableStr { english: "more" }.localize(language)
+            ),
+        }
+    }
+}
+
+/// Undo and redo stack, fed by the data events of the app
+#[derive(Default)]
+pub(super) struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    /// Changes caused by the events of the current frame
+    current: Vec<Change>,
+    /// Limits as of their last change, to know the state before an edit
+    limits: std::collections::HashMap<LimitKey, limits::Limit>,
+    /// Count of steps which shall be done, set by the history tab
+    requested: Option<usize>,
+}
+impl History {
+    /// Starts a new history, e.g. after a project was opened
+    pub(super) fn reset(&mut self, center: &super::DataCenter) {
+        *self = Self::default();
+        self.limits = center
+            .limits()
+            .map(|(key, limit)| (key.clone(), limit.clone()))
+            .collect();
+    }
+
+    pub(super) fn limit_added(&mut self, key: &LimitKey, center: &super::DataCenter) {
+        if let Some(limit) = center.limit(key) {
+            self.limits.insert(key.clone(), limit.clone());
+        }
+    }
+
+    /// Records a change, unless the limit is as before, e.g. since the change is an undo
+    pub(super) fn limit_changed(&mut self, key: &LimitKey, center: &super::DataCenter) {
+        let Some(limit) = center.limit(key) else {
+            return;
+        };
+        match self.limits.insert(key.clone(), limit.clone()) {
+            Some(before) if !before.has_same_setting(limit) => self.current.push(Change::Limit {
+                key: key.clone(),
+                before: Box::new(before),
+                after: Box::new(limit.clone()),
+            }),
+            _ => {}
+        }
+    }
+
+    pub(super) fn push(&mut self, change: Change) {
+        self.current.push(change);
+    }
+
+    /// Closes the step of the current frame, a new step discards the undone steps
+    pub(super) fn commit(&mut self) {
+        if !self.current.is_empty() {
+            self.redo.clear();
+            self.undo.push(Step {
+                changes: std::mem::take(&mut self.current),
+            });
+            if self.undo.len() > LENGTH {
+                self.undo.remove(0);
+            }
+        }
+    }
+
+    pub(super) fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub(super) fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Steps to undo or redo, to reach the requested count of done steps
+    pub(super) fn take_request(&mut self) -> Option<isize> {
+        let requested = self.requested.take()?;
+        Some(self.undo.len() as isize - requested as isize)
+    }
+}
+
+impl App {
+    pub(super) fn undo(&mut self) {
+        if let Some(mut step) = self.history.undo.pop() {
+            for change in step.changes.iter_mut().rev() {
+                self.apply(change, false);
+            }
+            self.history.redo.push(step);
+        }
+    }
+
+    pub(super) fn redo(&mut self) {
+        if let Some(mut step) = self.history.redo.pop() {
+            for change in step.changes.iter_mut() {
+                self.apply(change, true);
+            }
+            self.history.undo.push(step);
+        }
+    }
+
+    /// Redoes (forward) or reverts a change, the resulting events are not recorded again
+    fn apply(&mut self, change: &mut Change, forward: bool) {
+        match change {
+            Change::Limit { key, before, after } => {
+                let state = if forward { after } else { before };
+                if let Some(limit) = self.center.limit_mut(key) {
+                    limit.restore(state);
+                    self.history.limits.insert(key.clone(), limit.clone());
+                    self.data_events
+                        .push(DataEvent::Limit(limits::LimitEvent::Label(key.clone())));
+                    self.data_events
+                        .push(DataEvent::Limit(limits::LimitEvent::Limit(key.clone())));
+                }
+            }
+            Change::FileRemoved {
+                key,
+                label: _,
+                index,
+                file,
+                limits,
+            } => {
+                if forward {
+                    *file = self.remove_file(key).map(|(_, removed, pruned)| {
+                        *limits = pruned;
+                        Box::new(removed)
+                    });
+                } else if let Some(removed) = file.take() {
+                    self.center.restore_limits(std::mem::take(limits));
+                    self.reinsert_file(*index, key, *removed);
+                }
+            }
+            Change::FileMoved { key, label: _, up } => {
+                self.move_file(key, *up == forward);
+            }
+        }
+    }
+
+    fn reinsert_file(&mut self, index: usize, key: &FileKey, file: files::File) {
+        match self.files.reinsert(index, key.clone(), file) {
+            Some(files::FileEvent::Loaded {
+                key,
+                file,
+                parser_config,
+                non_conforming_tooltip,
+            }) => {
+                self.file_loaded(&key, &file, &parser_config, &non_conforming_tooltip);
+                // the data center appends the file, it is moved back to its former position
+                let following = self
+                    .files
+                    .keys()
+                    .skip(index + 1)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for other in following.into_iter().rev() {
+                    self.progress_center(michael_visualizer_basic::FileEvent::OrderSwitched(
+                        key.clone(),
+                        other,
+                    ));
+                }
+            }
+            Some(event) => self.data_events.push(DataEvent::File(event)),
+            None => {}
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(super) struct HistoryTab {}
+impl super::TabTrait for HistoryTab {
+    fn title(&self, state: &super::AppState) -> &str {
+        LocalizableStr { english: "History" }.localize(state.language)
+    }
+
+    fn show(&mut self, state: &mut super::AppState, ui: &mut egui::Ui) {
+        let super::AppState {
+            language, history, ..
+        } = state;
+        let language = *language;
+        let History {
+            undo,
+            redo,
+            requested,
+            ..
+        } = &mut **history;
+        ui.label(
+            LocalizableStr {
+                english: "Ctrl+Z undoes, Ctrl+Y redoes, click an entry to go back to it",
+            }
+            .localize(language),
+        );
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let done = undo.len();
+            if ui
+                .selectable_label(
+                    done == 0,
+                    LocalizableStr {
+                        english: "Initial state",
+                    }
+                    .localize(language),
+                )
+                .clicked()
+            {
+                *requested = Some(0);
+            }
+            for (index, step) in undo.iter().chain(redo.iter().rev()).enumerate() {
+                let count = index + 1;
+                let mut text = egui::RichText::new(step.describe(language));
+                if count > done {
+                    text = text.weak();
+                }
+                if ui.selectable_label(count == done, text).clicked() {
+                    *requested = Some(count);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::App;
+    use crate::app::{app_with_example, files::FileEvent, limits::LimitEvent, DataEvent};
+    use crate::data_types::finite_f32::FiniteF32;
+
+    #[test]
+    fn undo_and_redo() {
+        let (mut app, key) = app_with_example("Example");
+        assert!(!app.history.can_undo());
+        let (limit_key, limit) = app
+            .center
+            .limits()
+            .find(|(_, limit)| !limit.is_trivial())
+            .map(|(key, limit)| (key.clone(), limit.clone()))
+            .unwrap();
+        let limits = |app: &App| app.center.limit(&limit_key).unwrap().get_limits();
+
+        let edited = (Some(FiniteF32::new(-1.)), Some(FiniteF32::new(1.)));
+        app.center
+            .limit_mut(&limit_key)
+            .unwrap()
+            .set_limits(edited.0, edited.1);
+        app.data_events
+            .push(DataEvent::Limit(LimitEvent::Limit(limit_key.clone())));
+        app.process_data_events();
+        app.data_events
+            .push(DataEvent::File(FileEvent::Remove(key.clone())));
+        app.process_data_events();
+        assert_eq!(app.history.undo.len(), 2);
+        assert!(app.files.get(&key).is_none());
+        assert_eq!(app.center.files().count(), 0);
+
+        app.undo();
+        app.process_data_events();
+        assert!(app.files.get(&key).is_some());
+        assert_eq!(app.center.files().count(), 1);
+        assert!(app.center.filters_summed(&key).is_some());
+        app.undo();
+        app.process_data_events();
+        assert_eq!(limits(&app), limit.get_limits());
+        // undoing does not add steps
+        assert_eq!((app.history.undo.len(), app.history.redo.len()), (0, 2));
+
+        app.redo();
+        app.process_data_events();
+        assert_eq!(limits(&app), edited);
+        app.redo();
+        app.process_data_events();
+        assert!(app.files.get(&key).is_none());
+        assert_eq!((app.history.undo.len(), app.history.redo.len()), (2, 0));
+
+        // a new change discards the undone steps
+        app.undo();
+        app.data_events
+            .push(DataEvent::Limit(LimitEvent::Limit(limit_key.clone())));
+        app.process_data_events();
+        assert_eq!(app.history.redo.len(), 1);
+        app.center
+            .limit_mut(&limit_key)
+            .unwrap()
+            .set_limits(None, None);
+        app.data_events
+            .push(DataEvent::Limit(LimitEvent::Limit(limit_key)));
+        app.process_data_events();
+        assert_eq!((app.history.undo.len(), app.history.redo.len()), (2, 0));
+    }
+}
@@ -1,18 +1,63 @@
+mod limit_set;
+mod rule;
 use super::{DataEvent, DataEvents};
 use crate::data_types::finite_f32::FiniteF32;
 use crate::data_types::{LimitKey, LimitLabel};
 use crate::{LocalizableStr, LocalizableString};
+pub(super) use limit_set::{LimitSet, LimitSetFormat};
 
+/// Editing state of the limit table, the limits themselves are held by the data center
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 pub(super) struct LimitContainer {
-    limits: indexmap::IndexMap<LimitKey, Limit>,
+    /// Limit whose rule is edited above the table
+    #[serde(skip)]
+    editing: Option<LimitKey>,
 }
 
 impl LimitContainer {
-    fn show(&mut self, ui: &mut egui::Ui, language: crate::Language, data_events: &mut DataEvents) {
-        let Self { limits } = self;
+    fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        language: crate::Language,
+        center: &mut super::DataCenter,
+        data_events: &mut DataEvents,
+    ) {
+        let Self { editing } = self;
+        if let Some(key) = editing.clone() {
+            let others = center
+                .limits()
+                .filter(|(k, limit)| *k != &key && !limit.is_trivial())
+                .map(|(_, limit)| (limit.original_label.clone(), limit.label.clone()))
+                .collect::<Vec<_>>();
+            if let Some(limit) = center.limit_mut(&key) {
+                ui.horizontal(|ui| {
+                    ui.heading(format!(
+                        "{}: {}",
+                        LocalizableStr { english: "Rule" }.localize(language),
+                        limit.label.as_str()
+                    ));
+                    if ui
+                        .button(LocalizableStr { english: "Close" }.localize(language))
+                        .clicked()
+                    {
+                        *editing = None;
+                    }
+                });
+                if limit.rule.show(ui, language, &limit.data_kind, &others) {
+                    data_events.push(DataEvent::Limit(LimitEvent::Limit(key)));
+                }
+                ui.separator();
+            } else {
+                *editing = None;
+            }
+        }
+        let keys = center
+            .limits()
+            .filter(|(_, limit)| !limit.is_trivial())
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
         egui_extras::TableBuilder::new(ui)
-            .columns(egui_extras::Column::auto().resizable(true), 3)
+            .columns(egui_extras::Column::auto().resizable(true), 4)
             .header(14., |mut header| {
                 header.col(|ui| {
                     ui.heading(LocalizableStr { english: "Label" }.localize(language));
@@ -23,12 +68,15 @@ impl LimitContainer {
                 header.col(|ui| {
                     ui.heading(LocalizableStr { english: "Upper" }.localize(language));
                 });
+                header.col(|ui| {
+                    ui.heading(LocalizableStr { english: "Rule" }.localize(language));
+                });
             })
             .body(|mut body| {
-                for (key, limit) in limits.iter_mut() {
-                    if limit.is_trivial() {
+                for key in &keys {
+                    let Some(limit) = center.limit_mut(key) else {
                         continue;
-                    }
+                    };
                     body.row(30.0, |mut row| {
                         let mut changed = false;
                         row.col(|ui| {
@@ -42,6 +90,18 @@ impl LimitContainer {
                         row.col(|ui| {
                             changed |= limit.show_upper(ui, language);
                         });
+                        row.col(|ui| {
+                            let description = limit.rule.describe(language);
+                            let is_edited = editing.as_ref() == Some(key);
+                            let text = if description.is_empty() {
+                                "…".to_string()
+                            } else {
+                                description
+                            };
+                            if ui.selectable_label(is_edited, text).clicked() {
+                                *editing = (!is_edited).then(|| key.clone());
+                            }
+                        });
                         if changed {
                             data_events.push(DataEvent::Limit(LimitEvent::Limit(key.clone())));
                         }
@@ -49,51 +109,6 @@ impl LimitContainer {
                 }
             });
     }
-
-    pub(crate) fn insert(
-        &mut self,
-        limit_key_generator: &mut crate::data_types::LimitKeyGenerator,
-        limit: Limit,
-    ) -> (bool, LimitKey) {
-        if let Some((key, current)) = self
-            .limits
-            .iter_mut()
-            .find(|(_, l)| l.original_label == limit.original_label)
-        {
-            current.update_kind(limit.data_kind());
-            (false, key.clone())
-        } else {
-            let key = limit_key_generator.next();
-            self.limits.insert(key.clone(), limit);
-            (true, key)
-        }
-    }
-
-    pub(crate) fn get(&self, limit_key: &LimitKey) -> Option<&Limit> {
-        self.limits.get(limit_key)
-    }
-
-    #[must_use]
-    pub(crate) fn get_mut(&mut self, key: &LimitKey) -> Option<&mut Limit> {
-        self.limits.get_mut(key)
-    }
-
-    pub(crate) fn iter(&self) -> impl Iterator<Item = (&LimitKey, &Limit)> {
-        self.limits.iter()
-    }
-
-    #[must_use]
-    pub(crate) fn is_empty(&self) -> bool {
-        self.limits.is_empty()
-    }
-
-    pub(crate) fn len(&self) -> usize {
-        self.limits.len()
-    }
-
-    pub(crate) fn element_at(&self, index: usize) -> Option<&Limit> {
-        self.limits.get_index(index).map(|(_, x)| x)
-    }
 }
 impl super::DataEventNotifyable for LimitContainer {
     fn notify(&mut self, _event: &super::DataEvent) -> Vec<super::DataEvent> {
@@ -119,7 +134,11 @@ pub enum LimitRequest {
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
-pub struct LimitTab {}
+pub struct LimitTab {
+    /// Imported limit set, shown as preview until it is applied or discarded
+    #[serde(skip)]
+    import: Option<(String, LimitSet)>,
+}
 impl super::TabTrait for LimitTab {
     fn title(&self, state: &super::AppState) -> &str {
         LocalizableStr { english: "Limits" }.localize(state.language)
@@ -129,22 +148,233 @@ impl super::TabTrait for LimitTab {
         let super::AppState {
             language,
             limits,
+            center,
             data_events,
+            app_events,
             ..
         } = state;
-        limits.show(ui, *language, data_events);
+        let language = *language;
+        ui.horizontal(|ui| {
+            if ui
+                .button(
+                    LocalizableStr {
+                        english: "Import limit set …",
+                    }
+                    .localize(language),
+                )
+                .clicked()
+            {
+                if let Some(path) = limit_set_dialog(language).pick_file() {
+                    match LimitSet::read_path(&path) {
+                        Ok(set) => {
+                            let name = path
+                                .file_name()
+                                .map(|name| name.to_string_lossy().to_string())
+                                .unwrap_or_default();
+                            self.import = Some((name, set));
+                        }
+                        Err(message) => app_events.push(limit_set_error(message, language)),
+                    }
+                }
+            }
+            if ui
+                .button(
+                    LocalizableStr {
+                        english: "Export limit set …",
+                    }
+                    .localize(language),
+                )
+                .clicked()
+            {
+                if let Some(path) = limit_set_dialog(language).save_file() {
+                    if let Err(message) =
+                        LimitSet::from_limits(center.limits().map(|(_, limit)| limit))
+                            .write_path(&path)
+                    {
+                        app_events.push(limit_set_error(message, language));
+                    }
+                }
+            }
+        });
+        if let Some((name, set)) = &self.import {
+            // computed when shown, so the preview follows edits of the limits
+            let diff = set.diff(center.limits());
+            match show_import(ui, language, name, &diff) {
+                Some(true) => {
+                    for change in &diff.changes {
+                        if let Some(limit) = center.limit_mut(&change.key) {
+                            limit.apply(&change.after);
+                            data_events
+                                .push(DataEvent::Limit(LimitEvent::Limit(change.key.clone())));
+                        }
+                    }
+                    self.import = None;
+                }
+                Some(false) => self.import = None,
+                None => {}
+            }
+            ui.separator();
+        }
+        limits.show(ui, language, center, data_events);
     }
 }
 
+fn limit_set_dialog(language: crate::Language) -> rfd::FileDialog {
+    LimitSetFormat::all()
+        .into_iter()
+        .fold(rfd::FileDialog::new(), |dialog, format| {
+            dialog.add_filter(format.label().localize(language), &[format.extension()])
+        })
+}
+
+fn limit_set_error(message: String, language: crate::Language) -> super::AppEvent {
+    super::AppEvent::Dialog(crate::dialog::Dialog::new(
+        LocalizableStr {
+            english: "Limit set",
+        }
+        .localize(language)
+        .to_string(),
+        Box::new(move |ui| {
+            ui.label(&message);
+            false
+        }),
+        crate::dialog::DialogKind::ok(),
+    ))
+}
+
+/// Changes of an imported limit set, returns Some(true) to apply and Some(false) to discard
+fn show_import(
+    ui: &mut egui::Ui,
+    language: crate::Language,
+    name: &str,
+    diff: &limit_set::LimitSetDiff,
+) -> Option<bool> {
+    let limit_set::LimitSetDiff {
+        changes,
+        unchanged,
+        unmatched,
+    } = diff;
+    ui.heading(format!(
+        "{}: {name}",
+        LocalizableStr {
+            english: "Limit set"
+        }
+        .localize(language)
+    ));
+    let value = |value: Option<FiniteF32>| value.map(|v| v.to_string()).unwrap_or("-".into());
+    let text = |text: &Option<String>| text.clone().unwrap_or_default();
+    egui::ScrollArea::vertical()
+        .id_source("limit_set_import")
+        .max_height(200.)
+        .show(ui, |ui| {
+            egui::Grid::new("limit_set_changes")
+                .striped(true)
+                .show(ui, |ui| {
+                    for english in ["Label", "Lower", "Upper", "Unit"] {
+                        ui.strong(LocalizableStr { english }.localize(language));
+                    }
+                    ui.end_row();
+                    for limit_set::LimitChange { before, after, .. } in changes {
+                        ui.label(before.label.as_str());
+                        for (before, after) in [
+                            (value(before.lower), value(after.lower)),
+                            (value(before.upper), value(after.upper)),
+                            (text(&before.unit), text(&after.unit)),
+                        ] {
+                            if before == after {
+                                ui.label(before);
+                            } else {
+                                ui.colored_label(
+                                    ui.visuals().warn_fg_color,
+                                    format!("{before} → {after}"),
+                                );
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    ui.label(format!(
+        "{}: {}, {}: {unchanged}",
+        LocalizableStr { english: "Changed" }.localize(language),
+        changes.len(),
+        LocalizableStr {
+            english: "Unchanged"
+        }
+        .localize(language),
+    ));
+    if !unmatched.is_empty() {
+        ui.label(format!(
+            "{}: {}",
+            LocalizableStr {
+                english: "Without matching limit"
+            }
+            .localize(language),
+            unmatched
+                .iter()
+                .map(|label| label.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    ui.horizontal(|ui| {
+        let apply = ui
+            .add_enabled(
+                !changes.is_empty(),
+                egui::Button::new(LocalizableStr { english: "Apply" }.localize(language)),
+            )
+            .clicked();
+        let discard = ui
+            .button(LocalizableStr { english: "Discard" }.localize(language))
+            .clicked();
+        (apply || discard).then_some(apply)
+    })
+    .inner
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct Limit {
     original_label: LimitLabel,
     label: LimitLabel,
     label_previous: LimitLabel,
     tooltip_original: LocalizableString,
+    #[serde(default)]
+    unit: Option<String>,
     lower: LimitValue,
     upper: LimitValue,
     data_kind: LimitDataKind,
+    #[serde(default)]
+    rule: rule::LimitRule,
+}
+/// Limits of different files are the same, if their original labels match
+impl michael_visualizer_basic::LimitTrait for Limit {
+    fn has_same_label(&self, other: &Self) -> bool {
+        self.original_label == other.original_label
+    }
+
+    fn change_label(&mut self, label: LimitLabel) -> bool {
+        Limit::change_label(self, label.as_str())
+    }
+
+    fn label(&self) -> &LimitLabel {
+        &self.label
+    }
+
+    fn original_label(&self) -> &LimitLabel {
+        &self.original_label
+    }
+
+    fn combination(&self) -> Option<(michael_visualizer_basic::Combination, &[LimitLabel])> {
+        self.rule.combination()
+    }
+
+    fn is_counted(&self) -> bool {
+        self.rule.is_counted()
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.update_kind(other.data_kind());
+    }
 }
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct LimitValue {
@@ -402,13 +632,29 @@ impl LimitValue {
 
     fn set(&mut self, value: i32, info: LocalizableStr, data_kind: &LimitDataKind) {
         if let Ok(value) = value.try_into() {
-            self.value = Some(value);
-            self.current = value.to_string();
-            self.parsed = value.to_string();
-            self.tooltip =
-                Self::compute_tooltip(info, self.value, self.value_original, None, data_kind);
+            self.set_value(Some(value), info, data_kind);
         }
     }
+
+    fn set_value(
+        &mut self,
+        value: Option<FiniteF32>,
+        info: LocalizableStr,
+        data_kind: &LimitDataKind,
+    ) {
+        self.value = value;
+        self.current = value.map(|f| f.to_string()).unwrap_or_default();
+        self.parsed = self.current.clone();
+        self.parse_issue = false;
+        self.warn = value.and_then(|value| data_kind.check(value));
+        self.tooltip = Self::compute_tooltip(
+            info,
+            self.value,
+            self.value_original,
+            self.warn.as_ref().map(|x| x.as_str()),
+            data_kind,
+        );
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
@@ -417,6 +663,7 @@ pub(super) struct LimitData {
     pub lower: Option<FiniteF32>,
     pub upper: Option<FiniteF32>,
     pub info: LocalizableString,
+    pub unit: Option<String>,
     pub data_kind: LimitDataKind,
 }
 impl Limit {
@@ -426,6 +673,7 @@ impl Limit {
             lower,
             upper,
             info,
+            unit,
             data_kind,
         } = data;
         Self {
@@ -435,7 +683,9 @@ impl Limit {
             lower: LimitValue::new(lower, info.as_str(), &data_kind),
             upper: LimitValue::new(upper, info.as_str(), &data_kind),
             tooltip_original: info,
+            unit,
             data_kind,
+            rule: Default::default(),
         }
     }
 
@@ -445,19 +695,28 @@ impl Limit {
             label,
             label_previous,
             tooltip_original: info,
+            unit,
             lower: _,
             upper: _,
             data_kind: _,
+            rule: _,
         } = self;
         let has_focus = ui
             .text_edit_singleline(label.get_mut())
             .on_hover_text(format!(
-                "{info}\n{original_header}: {original_label:?}",
+                "{info}\n{original_header}: {original_label:?}{unit}",
                 info = info.as_str().localize(language),
                 original_header = LocalizableStr {
                     english: "Original label"
                 }
-                .localize(language)
+                .localize(language),
+                unit = unit
+                    .as_ref()
+                    .map(|unit| format!(
+                        "\n{unit_header}: {unit}",
+                        unit_header = LocalizableStr { english: "Unit" }.localize(language)
+                    ))
+                    .unwrap_or_default()
             ))
             .context_menu(|ui| {
                 if ui.button(super::RESET.localize(language)).clicked() {
@@ -491,7 +750,16 @@ impl Limit {
         )
     }
 
-    pub(super) fn is_outside(&self, x: f32) -> bool {
+    /// Relative bounds of the rule, computed from the values of one file
+    pub(super) fn sigma_bounds(
+        &self,
+        values: impl Iterator<Item = f32>,
+    ) -> Option<(FiniteF32, FiniteF32)> {
+        self.rule.sigma_bounds(values)
+    }
+
+    /// `sigma_bounds` as given by [`Self::sigma_bounds`] for the file of the value
+    pub(super) fn is_outside(&self, x: f32, sigma_bounds: Option<(FiniteF32, FiniteF32)>) -> bool {
         if let Some(x) = FiniteF32::new_checked(x) {
             if let Some(l) = self.lower.value {
                 if x < l {
@@ -503,7 +771,12 @@ impl Limit {
                     return true;
                 }
             }
-            false
+            if let Some((l, u)) = sigma_bounds {
+                if x < l || x > u {
+                    return true;
+                }
+            }
+            self.rule.is_outside(x)
         } else {
             true
         }
@@ -515,6 +788,7 @@ impl Limit {
             lower: self.lower.value,
             upper: self.upper.value,
             info: self.tooltip_original.clone(),
+            unit: self.unit.clone(),
             data_kind: self.data_kind.clone(),
         }
     }
@@ -569,6 +843,43 @@ impl Limit {
         (self.lower.value, self.upper.value)
     }
 
+    pub(crate) fn get_rule(&self) -> &rule::LimitRule {
+        &self.rule
+    }
+
+    /// Replace both values, None disables that side of the limit
+    pub(crate) fn set_limits(&mut self, lower: Option<FiniteF32>, upper: Option<FiniteF32>) {
+        self.lower
+            .set_value(lower, self.tooltip_original.as_str(), &self.data_kind);
+        self.upper
+            .set_value(upper, self.tooltip_original.as_str(), &self.data_kind);
+    }
+
+    /// Values and unit of a limit set entry, label and info are kept
+    pub(crate) fn apply(&mut self, entry: &limit_set::LimitSetEntry) {
+        if let Some(unit) = &entry.unit {
+            self.unit = (!unit.is_empty()).then(|| unit.clone());
+        }
+        self.set_limits(entry.lower, entry.upper);
+    }
+
+    /// Label, values, rule, info and unit agree, i.e. both are shown as the same limit
+    pub(crate) fn has_same_setting(&self, other: &Limit) -> bool {
+        self.label == other.label
+            && self.get_limits() == other.get_limits()
+            && self.rule == other.rule
+            && self.tooltip_original.english == other.tooltip_original.english
+            && self.unit == other.unit
+    }
+
+    /// Takes over label and values of an earlier state, e.g. to undo an edit
+    /// The data kind is kept, since files might have been loaded in between
+    pub(crate) fn restore(&mut self, earlier: &Limit) {
+        let data_kind = std::mem::replace(&mut self.data_kind, LimitDataKind::Float);
+        *self = earlier.clone();
+        self.update_kind(&data_kind);
+    }
+
     pub(crate) fn change(&mut self, lower: i32, upper: i32) {
         self.lower
             .set(lower, self.tooltip_original.as_str(), &self.data_kind);
@@ -576,3 +887,83 @@ impl Limit {
             .set(upper, self.tooltip_original.as_str(), &self.data_kind);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::LimitEvent;
+    use crate::app::{
+        app_with_example,
+        files::{FileData, FileEvent, ParserConfig},
+        App, DataEvent,
+    };
+
+    #[test]
+    fn limits_of_the_data_center() {
+        let (mut app, key) = app_with_example("Example");
+        // second file, whose second data column has the label of the first one
+        let mut bytes = Vec::new();
+        data_format::DataFormat::example_rectangle_simple(10)
+            .write_mv01(&mut bytes)
+            .unwrap();
+        let text = String::from_utf8(bytes).unwrap().replacen(";d2", ";d1", 1);
+        let duplicate = app.file_key_generator.next();
+        app.files.make_parsing(&duplicate, "Duplicate");
+        app.data_events.push(DataEvent::File(FileEvent::Loaded {
+            key: duplicate.clone(),
+            file: FileData::parse(text.into_bytes(), &ParserConfig::default()).unwrap(),
+            parser_config: None,
+            non_conforming_tooltip: None,
+        }));
+        app.process_data_events();
+        let limit_count = app.center.limits().count();
+        assert!(app
+            .files
+            .get(&duplicate)
+            .unwrap()
+            .tooltip()
+            .english
+            .contains("only the first one is used: d1"));
+
+        // an edit of the table is the limit the data center filters with
+        let limit_key = app
+            .center
+            .limits()
+            .find(|(_, limit)| limit.get_label().as_str() == "d1")
+            .map(|(key, _)| key.clone())
+            .unwrap();
+        let passing = |app: &App| {
+            app.center
+                .filters_summed(&key)
+                .unwrap()
+                .iter()
+                .filter(|&&n| n == 0)
+                .count()
+        };
+        assert!(passing(&app) > 0);
+        let lower = crate::data_types::finite_f32::FiniteF32::new(1e9);
+        app.center
+            .limit_mut(&limit_key)
+            .unwrap()
+            .set_limits(Some(lower), None);
+        app.data_events
+            .push(DataEvent::Limit(LimitEvent::Limit(limit_key.clone())));
+        app.process_data_events();
+        let _ = app.center.wait();
+        assert_eq!(passing(&app), 0);
+
+        // limits of a removed file stay as long as another file has them
+        app.data_events
+            .push(DataEvent::File(FileEvent::Remove(key.clone())));
+        app.process_data_events();
+        assert_eq!(app.center.limits().count(), limit_count - 1);
+        assert!(app.center.limit(&limit_key).is_some());
+        assert!(app
+            .center
+            .limits()
+            .all(|(_, limit)| limit.get_label().as_str() != "d2"));
+        app.data_events
+            .push(DataEvent::File(FileEvent::Remove(duplicate)));
+        app.process_data_events();
+        assert_eq!(app.center.limits().count(), 0);
+    }
+}
@@ -0,0 +1,466 @@
+use super::Limit;
+use crate::data_types::finite_f32::FiniteF32;
+use crate::data_types::{LimitKey, LimitLabel};
+use crate::LocalizableStr;
+
+/// Lower and upper values for limits, maintained independently of the data files
+/// Entries are matched to limits by label
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct LimitSet {
+    entries: Vec<LimitSetEntry>,
+}
+/// Info is only written for reference, the info of a limit is the one of its file
+/// The unit is only changed if it is given, an empty unit removes it
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LimitSetEntry {
+    pub label: LimitLabel,
+    pub lower: Option<FiniteF32>,
+    pub upper: Option<FiniteF32>,
+    pub info: Option<String>,
+    pub unit: Option<String>,
+}
+impl From<&Limit> for LimitSetEntry {
+    fn from(limit: &Limit) -> Self {
+        let (lower, upper) = limit.get_limits();
+        Self {
+            label: limit.label.clone(),
+            lower,
+            upper,
+            info: Some(limit.tooltip_original.english.clone()),
+            unit: Some(limit.unit.clone().unwrap_or_default()),
+        }
+    }
+}
+impl LimitSetEntry {
+    /// Entry as the limit would be after applying this entry
+    fn applied_to(&self, current: &LimitSetEntry) -> LimitSetEntry {
+        LimitSetEntry {
+            label: current.label.clone(),
+            lower: self.lower,
+            upper: self.upper,
+            info: current.info.clone(),
+            unit: self.unit.clone().or_else(|| current.unit.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LimitSetFormat {
+    Csv,
+    Json,
+    Toml,
+}
+impl LimitSetFormat {
+    pub(crate) fn all() -> [LimitSetFormat; 3] {
+        [
+            LimitSetFormat::Csv,
+            LimitSetFormat::Json,
+            LimitSetFormat::Toml,
+        ]
+    }
+
+    /// Unknown extensions are read as CSV
+    pub(crate) fn from_path(path: &std::path::Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("json") => LimitSetFormat::Json,
+            Some("toml") => LimitSetFormat::Toml,
+            _ => LimitSetFormat::Csv,
+        }
+    }
+
+    pub(crate) fn label(&self) -> LocalizableStr<'static> {
+        match self {
+            LimitSetFormat::Csv => LocalizableStr { english: "CSV" },
+            LimitSetFormat::Json => LocalizableStr { english: "JSON" },
+            LimitSetFormat::Toml => LocalizableStr { english: "TOML" },
+        }
+    }
+
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            LimitSetFormat::Csv => "csv",
+            LimitSetFormat::Json => "json",
+            LimitSetFormat::Toml => "toml",
+        }
+    }
+}
+
+/// Layout of an entry in JSON and TOML, a missing lower or upper value means the side is unused
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Record {
+    label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lower: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    upper: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    info: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unit: Option<String>,
+}
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Records {
+    limits: Vec<Record>,
+}
+impl Record {
+    fn value(value: Option<f64>, label: &str, side: &str) -> Result<Option<FiniteF32>, String> {
+        value
+            .map(|v| {
+                FiniteF32::new_checked(v as f32)
+                    .ok_or_else(|| format!("'{label}': {side} value is not a finite number"))
+            })
+            .transpose()
+    }
+}
+/// Via the shortest text, so 0.1 is written as 0.1 and not as the exact value of the f32
+fn to_f64(value: FiniteF32) -> f64 {
+    value
+        .to_string()
+        .parse()
+        .expect("Finite floats are written as numbers")
+}
+
+/// Changes a limit set makes to the current limits
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct LimitSetDiff {
+    pub changes: Vec<LimitChange>,
+    /// Count of matched limits, which already have the values of the set
+    pub unchanged: usize,
+    /// Entries of the set without a limit of the same label
+    pub unmatched: Vec<LimitLabel>,
+}
+#[derive(Debug, PartialEq)]
+pub(crate) struct LimitChange {
+    pub key: LimitKey,
+    pub before: LimitSetEntry,
+    pub after: LimitSetEntry,
+}
+
+impl LimitSet {
+    /// Limits with their current values, trivial ones (single integer value) are left out
+    pub(crate) fn from_limits<'a>(limits: impl Iterator<Item = &'a Limit>) -> Self {
+        Self {
+            entries: limits
+                .filter(|limit| !limit.is_trivial())
+                .map(LimitSetEntry::from)
+                .collect(),
+        }
+    }
+
+    pub(crate) fn read_path(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {e}", path.display()))?;
+        Self::parse(&text, LimitSetFormat::from_path(path))
+    }
+
+    pub(crate) fn write_path(&self, path: &std::path::Path) -> Result<(), String> {
+        let text = self.write(LimitSetFormat::from_path(path))?;
+        std::fs::write(path, text).map_err(|e| format!("Failed to write '{}': {e}", path.display()))
+    }
+
+    pub(crate) fn parse(text: &str, format: LimitSetFormat) -> Result<Self, String> {
+        match format {
+            LimitSetFormat::Csv => Self::parse_csv(text),
+            LimitSetFormat::Json => Self::parse_json(text),
+            LimitSetFormat::Toml => Self::parse_toml(text),
+        }
+    }
+
+    pub(crate) fn write(&self, format: LimitSetFormat) -> Result<String, String> {
+        match format {
+            LimitSetFormat::Csv => self.to_csv(),
+            LimitSetFormat::Json => self.to_json(),
+            LimitSetFormat::Toml => self.to_toml(),
+        }
+    }
+
+    /// Labels have to be unique and lower must not be above upper
+    fn push(&mut self, entry: LimitSetEntry) -> Result<(), String> {
+        let label = entry.label.as_str();
+        if label.is_empty() {
+            return Err("Label is empty".into());
+        }
+        if self.get(&entry.label).is_some() {
+            return Err(format!("Label '{label}' occurs multiple times"));
+        }
+        if let (Some(lower), Some(upper)) = (entry.lower, entry.upper) {
+            if lower > upper {
+                return Err(format!(
+                    "'{label}': lower value {lower} is above upper value {upper}",
+                    lower = *lower,
+                    upper = *upper
+                ));
+            }
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Delimiter is ';', like in mv01 files
+    /// The first line names the columns, 'label', 'lower' and 'upper' are required,
+    /// 'info' and 'unit' are optional, others are ignored
+    /// An empty unit removes it
+    /// Empty cells or '-' leave that side of the limit unused
+    pub(crate) fn parse_csv(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let (_, header) = lines.next().ok_or("Limit set is empty")?;
+        let header = header
+            .split(';')
+            .map(|h| h.trim().to_lowercase())
+            .collect::<Vec<_>>();
+        let optional_column = |name: &str| header.iter().position(|h| h == name);
+        let column = |name: &str| {
+            optional_column(name).ok_or_else(|| format!("Limit set has no column '{name}'"))
+        };
+        let (label_column, lower_column, upper_column) =
+            (column("label")?, column("lower")?, column("upper")?);
+        let (info_column, unit_column) = (optional_column("info"), optional_column("unit"));
+        let mut set = Self::default();
+        for (row, line) in lines {
+            let row = row + 1;
+            let cells = line.split(';').map(str::trim).collect::<Vec<_>>();
+            if cells.len() != header.len() {
+                return Err(format!(
+                    "Line {row}: expected {expected} columns, but found {found}",
+                    expected = header.len(),
+                    found = cells.len()
+                ));
+            }
+            let value = |column: usize| match cells[column] {
+                "" | "-" => Ok(None),
+                cell => cell
+                    .parse::<f32>()
+                    .ok()
+                    .and_then(FiniteF32::new_checked)
+                    .map(Some)
+                    .ok_or_else(|| format!("Line {row}: value is not a finite number: '{cell}'")),
+            };
+            let text = |column: Option<usize>| column.map(|column| cells[column].to_string());
+            set.push(LimitSetEntry {
+                label: cells[label_column].to_string().into(),
+                lower: value(lower_column)?,
+                upper: value(upper_column)?,
+                info: text(info_column).filter(|info| !info.is_empty()),
+                unit: text(unit_column),
+            })
+            .map_err(|msg| format!("Line {row}: {msg}"))?;
+        }
+        Ok(set)
+    }
+
+    fn to_csv(&self) -> Result<String, String> {
+        let value = |v: Option<FiniteF32>| v.map(|v| v.to_string()).unwrap_or("-".into());
+        let mut text = "label;lower;upper;info;unit\n".to_string();
+        for entry in &self.entries {
+            let texts = [
+                entry.label.as_str(),
+                entry.info.as_deref().unwrap_or_default(),
+                entry.unit.as_deref().unwrap_or_default(),
+            ];
+            if let Some(invalid) = texts.iter().find(|t| t.contains([';', '\n', '\r'])) {
+                return Err(format!(
+                    "'{invalid}' contains a delimiter or line break, it cannot be written as CSV"
+                ));
+            }
+            let [label, info, unit] = texts;
+            text += &format!(
+                "{label};{lower};{upper};{info};{unit}\n",
+                lower = value(entry.lower),
+                upper = value(entry.upper)
+            );
+        }
+        Ok(text)
+    }
+
+    fn from_records(records: Vec<Record>) -> Result<Self, String> {
+        let mut set = Self::default();
+        for record in records {
+            set.push(LimitSetEntry {
+                lower: Record::value(record.lower, &record.label, "lower")?,
+                upper: Record::value(record.upper, &record.label, "upper")?,
+                label: record.label.into(),
+                info: record.info,
+                unit: record.unit,
+            })?;
+        }
+        Ok(set)
+    }
+
+    fn records(&self) -> Records {
+        Records {
+            limits: self
+                .entries
+                .iter()
+                .map(|entry| Record {
+                    label: entry.label.as_str().to_string(),
+                    lower: entry.lower.map(to_f64),
+                    upper: entry.upper.map(to_f64),
+                    info: entry.info.clone(),
+                    unit: entry.unit.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// An object with the entries as array 'limits', missing values and null are the same
+    fn parse_json(text: &str) -> Result<Self, String> {
+        let Records { limits } =
+            serde_json::from_str(text).map_err(|e| format!("Limit set is no valid JSON: {e}"))?;
+        Self::from_records(limits)
+    }
+
+    fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.records())
+            .map(|text| text + "\n")
+            .map_err(|e| e.to_string())
+    }
+
+    /// An array of tables 'limits'
+    fn parse_toml(text: &str) -> Result<Self, String> {
+        let Records { limits } =
+            toml::from_str(text).map_err(|e| format!("Limit set is no valid TOML: {e}"))?;
+        Self::from_records(limits)
+    }
+
+    fn to_toml(&self) -> Result<String, String> {
+        toml::to_string(&self.records()).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn get(&self, label: &LimitLabel) -> Option<&LimitSetEntry> {
+        self.entries.iter().find(|e| &e.label == label)
+    }
+
+    /// Matches by the current label of the limits
+    pub(crate) fn diff<'a>(
+        &self,
+        limits: impl Iterator<Item = (&'a LimitKey, &'a Limit)>,
+    ) -> LimitSetDiff {
+        let mut diff = LimitSetDiff::default();
+        let mut matched = std::collections::HashSet::new();
+        for (key, limit) in limits {
+            if let Some(entry) = self.get(limit.get_label()) {
+                matched.insert(entry.label.as_str());
+                let before = LimitSetEntry::from(limit);
+                let after = entry.applied_to(&before);
+                if before == after {
+                    diff.unchanged += 1;
+                } else {
+                    diff.changes.push(LimitChange {
+                        key: key.clone(),
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+        diff.unmatched = self
+            .entries
+            .iter()
+            .filter(|e| !matched.contains(e.label.as_str()))
+            .map(|e| e.label.clone())
+            .collect();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LimitSet, LimitSetEntry, LimitSetFormat};
+    use crate::data_types::{finite_f32::FiniteF32, LimitKey};
+
+    #[test]
+    fn parse_csv() {
+        let set =
+            LimitSet::parse_csv("Label;Unit;Lower;Upper\nVth;V;0.1;0.6\nIdd;A;-;5\n\n").unwrap();
+        let vth = set.get(&"Vth".to_string().into()).unwrap();
+        assert_eq!(vth.lower, Some(FiniteF32::new(0.1)));
+        assert_eq!(vth.upper, Some(FiniteF32::new(0.6)));
+        assert_eq!(
+            (vth.info.as_deref(), vth.unit.as_deref()),
+            (None, Some("V"))
+        );
+        let idd = set.get(&"Idd".to_string().into()).unwrap();
+        assert_eq!((idd.lower, idd.upper), (None, Some(FiniteF32::new(5.))));
+        assert!(set.get(&"X".to_string().into()).is_none());
+
+        assert!(LimitSet::parse_csv("label;lower\nVth;0").is_err());
+        assert!(LimitSet::parse_csv("label;lower;upper\nVth;a;0").is_err());
+        assert!(LimitSet::parse_csv("label;lower;upper\nVth;0;1\nVth;0;2").is_err());
+        assert!(LimitSet::parse_csv("label;lower;upper\nVth;2;1").is_err());
+    }
+
+    #[test]
+    fn formats() {
+        let set = LimitSet::parse_csv(
+            "label;lower;upper;info;unit\nVth;0.1;-;threshold \"typ\";V\nIdd;-;5;;\n",
+        )
+        .unwrap();
+        for format in LimitSetFormat::all() {
+            let text = set.write(format).unwrap();
+            assert_eq!(LimitSet::parse(&text, format), Ok(set.clone()), "{text}");
+        }
+        assert!(set
+            .write(LimitSetFormat::Toml)
+            .unwrap()
+            .contains("lower = 0.1\n"));
+        let json = r#"{"limits": [{"label": "Vth", "upper": 1}, {"label": "Idd", "lower": null}]}"#;
+        let set = LimitSet::parse(json, LimitSetFormat::Json).unwrap();
+        let vth = set.get(&"Vth".to_string().into()).unwrap();
+        assert_eq!((vth.lower, vth.upper), (None, Some(FiniteF32::new(1.))));
+        assert_eq!(vth.info, None);
+        assert!(LimitSet::parse(r#"{"limits": [{"lower": 1}]}"#, LimitSetFormat::Json).is_err());
+        assert!(LimitSet::parse("[[limits]]\nlabel = 1", LimitSetFormat::Toml).is_err());
+    }
+
+    #[test]
+    fn diff() {
+        use super::super::{Limit, LimitData, LimitDataKind};
+        use michael_visualizer_basic::Incrementable;
+        let limit = |label: &str, upper: f32| {
+            Limit::new(LimitData {
+                label: label.to_string().into(),
+                lower: None,
+                upper: Some(FiniteF32::new(upper)),
+                info: crate::LocalizableString {
+                    english: "info".into(),
+                },
+                unit: None,
+                data_kind: LimitDataKind::Float,
+            })
+        };
+        let (vth_key, idd_key) = (LimitKey::default(), LimitKey::default().increment());
+        let mut limits = [
+            (vth_key.clone(), limit("Vth", 1.)),
+            (idd_key, limit("Idd", 5.)),
+        ];
+        let set =
+            LimitSet::parse_csv("label;lower;upper;unit\nVth;0;1;V\nIdd;-;5;\nX;0;1;\n").unwrap();
+        let diff = set.diff(limits.iter().map(|(key, limit)| (key, limit)));
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.unmatched, vec!["X".to_string().into()]);
+        assert_eq!(diff.changes.len(), 1);
+        let change = &diff.changes[0];
+        assert_eq!(change.key, vth_key);
+        assert_eq!(change.before.lower, None);
+        assert_eq!(change.after.lower, Some(FiniteF32::new(0.)));
+        assert_eq!(change.after.info.as_deref(), Some("info"));
+
+        limits[0].1.apply(&change.after);
+        assert_eq!(LimitSetEntry::from(&limits[0].1), change.after);
+        assert_eq!(limits[0].1.tooltip_original.english, "info");
+        let diff = set.diff(limits.iter().map(|(key, limit)| (key, limit)));
+        assert_eq!((diff.changes.len(), diff.unchanged), (0, 2));
+        // info is not applied
+        let set = LimitSet::parse_csv("label;lower;upper;info\nIdd;-;5;other").unwrap();
+        let diff = set.diff(limits.iter().map(|(key, limit)| (key, limit)));
+        assert_eq!((diff.changes.len(), diff.unchanged), (0, 1));
+    }
+}
@@ -0,0 +1,357 @@
+use super::{LimitDataKind, UniqueInt};
+use crate::data_types::finite_f32::FiniteF32;
+use crate::data_types::LimitLabel;
+use crate::LocalizableStr;
+use michael_visualizer_basic::Combination;
+
+/// Factor of σ, if relative bounds are switched on
+const SIGMA_DEFAULT: f32 = 3.;
+
+/// Pass/fail rules of a limit, in addition to its lower and upper value
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, PartialEq)]
+pub(crate) struct LimitRule {
+    /// Values within one of these ranges fail, bounds included
+    excluded: Vec<(FiniteF32, FiniteF32)>,
+    /// Only these values pass, for integer data with few different values
+    allowed: Option<std::collections::BTreeSet<i32>>,
+    /// Values further than k·σ away from the mean of the file's column fail
+    sigma: Option<FiniteF32>,
+    /// Other limits, given by their original label, which are judged together with this one
+    combination: Option<(Combination, Vec<LimitLabel>)>,
+    /// The limit is not counted on its own, only as part of other limits' combinations
+    combined_only: bool,
+}
+
+impl LimitRule {
+    pub(super) fn is_outside(&self, x: FiniteF32) -> bool {
+        self.excluded
+            .iter()
+            .any(|(lower, upper)| lower <= &x && &x <= upper)
+            || self.allowed.as_ref().is_some_and(|allowed| {
+                x.round() != x.inner() || !allowed.contains(&(x.inner() as i32))
+            })
+    }
+
+    /// Mean ± k·σ of the finite values, if relative bounds are used
+    pub(super) fn sigma_bounds(
+        &self,
+        values: impl Iterator<Item = f32>,
+    ) -> Option<(FiniteF32, FiniteF32)> {
+        let k = self.sigma?.inner() as f64;
+        let (count, sum, sum_squared) = values
+            .filter(|x| x.is_finite())
+            .map(|x| x as f64)
+            .fold((0usize, 0., 0.), |(count, sum, sum_squared), x| {
+                (count + 1, sum + x, sum_squared + x * x)
+            });
+        if count == 0 {
+            return None;
+        }
+        let mean = sum / count as f64;
+        let sigma = (sum_squared / count as f64 - mean * mean).max(0.).sqrt();
+        Some((
+            FiniteF32::new_checked((mean - k * sigma) as f32)?,
+            FiniteF32::new_checked((mean + k * sigma) as f32)?,
+        ))
+    }
+
+    pub(super) fn combination(&self) -> Option<(Combination, &[LimitLabel])> {
+        self.combination
+            .as_ref()
+            .map(|(combination, labels)| (*combination, &labels[..]))
+    }
+
+    pub(super) fn is_counted(&self) -> bool {
+        !self.combined_only
+    }
+
+    /// Short summary, empty if no rule is in use
+    pub(crate) fn describe(&self, language: crate::Language) -> String {
+        let mut parts = Vec::new();
+        for (lower, upper) in &self.excluded {
+            parts.push(format!("∉ [{}, {}]", lower.inner(), upper.inner()));
+        }
+        if let Some(allowed) = &self.allowed {
+            parts.push(format!(
+                "∈ {{{}}}",
+                allowed
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if let Some(k) = self.sigma {
+            parts.push(format!("μ ± {}σ", k.inner()));
+        }
+        if let Some((combination, labels)) = &self.combination {
+            parts.push(format!(
+                "{} {}",
+                combination_label(*combination).localize(language),
+                labels
+                    .iter()
+                    .map(|label| label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if self.combined_only {
+            parts.push(
+                LocalizableStr {
+                    english: "combined only",
+                }
+                .localize(language)
+                .to_string(),
+            );
+        }
+        parts.join("; ")
+    }
+
+    /// Editor of the rule, `others` are the original and current labels of the other limits
+    /// Returns true if the rule changed
+    pub(super) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        language: crate::Language,
+        data_kind: &LimitDataKind,
+        others: &[(LimitLabel, LimitLabel)],
+    ) -> bool {
+        let Self {
+            excluded,
+            allowed,
+            sigma,
+            combination,
+            combined_only,
+        } = self;
+        let mut changed = false;
+
+        ui.label(
+            LocalizableStr {
+                english: "Excluded ranges",
+            }
+            .localize(language),
+        );
+        let mut to_remove = None;
+        for (index, (lower, upper)) in excluded.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= drag_value(ui, lower);
+                changed |= drag_value(ui, upper);
+                if ui.button("\u{1F5D1}").clicked() {
+                    to_remove = Some(index);
+                }
+            });
+            if upper < lower {
+                std::mem::swap(lower, upper);
+            }
+        }
+        if let Some(index) = to_remove {
+            excluded.remove(index);
+            changed = true;
+        }
+        if ui
+            .button(
+                LocalizableStr {
+                    english: "Add range",
+                }
+                .localize(language),
+            )
+            .clicked()
+        {
+            excluded.push((FiniteF32::new(0.), FiniteF32::new(0.)));
+            changed = true;
+        }
+
+        if let LimitDataKind::Int {
+            uniques: UniqueInt::Uniques(uniques),
+            ..
+        } = data_kind
+        {
+            ui.separator();
+            let mut restricted = allowed.is_some();
+            if ui
+                .checkbox(
+                    &mut restricted,
+                    LocalizableStr {
+                        english: "Allowed values only",
+                    }
+                    .localize(language),
+                )
+                .changed()
+            {
+                *allowed = restricted.then(|| uniques.iter().copied().collect());
+                changed = true;
+            }
+            if let Some(allowed) = allowed {
+                let mut values = uniques.iter().copied().collect::<Vec<_>>();
+                values.sort();
+                ui.horizontal_wrapped(|ui| {
+                    for value in values {
+                        let mut is_allowed = allowed.contains(&value);
+                        if ui.checkbox(&mut is_allowed, value.to_string()).changed() {
+                            if is_allowed {
+                                allowed.insert(value);
+                            } else {
+                                allowed.remove(&value);
+                            }
+                            changed = true;
+                        }
+                    }
+                });
+            }
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            let mut relative = sigma.is_some();
+            if ui
+                .checkbox(
+                    &mut relative,
+                    LocalizableStr {
+                        english: "Mean ± k·σ, k =",
+                    }
+                    .localize(language),
+                )
+                .changed()
+            {
+                *sigma = relative.then(|| FiniteF32::new(SIGMA_DEFAULT));
+                changed = true;
+            }
+            if let Some(sigma) = sigma {
+                let mut k = sigma.inner();
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut k)
+                            .speed(0.1)
+                            .clamp_range(0.1..=100.),
+                    )
+                    .changed()
+                {
+                    *sigma = FiniteF32::new(k);
+                    changed = true;
+                }
+            }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(
+                LocalizableStr {
+                    english: "Combination",
+                }
+                .localize(language),
+            );
+            let mut selected = combination.as_ref().map(|(c, _)| *c);
+            egui::ComboBox::from_id_source("LimitRuleCombination")
+                .selected_text(
+                    selected
+                        .map(|c| combination_label(c).localize(language))
+                        .unwrap_or("-"),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, None, "-");
+                    for c in [Combination::And, Combination::Or] {
+                        ui.selectable_value(
+                            &mut selected,
+                            Some(c),
+                            combination_label(c).localize(language),
+                        )
+                        .on_hover_text(combination_tooltip(c).localize(language));
+                    }
+                });
+            if selected != combination.as_ref().map(|(c, _)| *c) {
+                *combination = selected.map(|c| {
+                    (
+                        c,
+                        combination
+                            .take()
+                            .map(|(_, labels)| labels)
+                            .unwrap_or_default(),
+                    )
+                });
+                changed = true;
+            }
+        });
+        if let Some((_, labels)) = combination {
+            egui::ScrollArea::vertical()
+                .id_source("LimitRuleCombinationLabels")
+                .max_height(150.)
+                .show(ui, |ui| {
+                    for (original, label) in others {
+                        let mut selected = labels.contains(original);
+                        if ui.checkbox(&mut selected, label.as_str()).changed() {
+                            if selected {
+                                labels.push(original.clone());
+                            } else {
+                                labels.retain(|l| l != original);
+                            }
+                            changed = true;
+                        }
+                    }
+                });
+        }
+        changed |= ui
+            .checkbox(
+                combined_only,
+                LocalizableStr {
+                    english: "Only counted within combinations of other limits",
+                }
+                .localize(language),
+            )
+            .changed();
+        changed
+    }
+}
+
+fn drag_value(ui: &mut egui::Ui, value: &mut FiniteF32) -> bool {
+    let mut x = value.inner();
+    let changed = ui.add(egui::DragValue::new(&mut x).speed(0.1)).changed();
+    match FiniteF32::new_checked(x) {
+        Some(x) if changed => {
+            *value = x;
+            true
+        }
+        _ => false,
+    }
+}
+
+fn combination_label(combination: Combination) -> LocalizableStr<'static> {
+    match combination {
+        Combination::And => LocalizableStr { english: "and" },
+        Combination::Or => LocalizableStr { english: "or" },
+    }
+}
+
+fn combination_tooltip(combination: Combination) -> LocalizableStr<'static> {
+    match combination {
+        Combination::And => LocalizableStr {
+            english: "A row passes, if it passes this limit and all selected limits",
+        },
+        Combination::Or => LocalizableStr {
+            english: "A row passes, if it passes this limit or any selected limit",
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let x = |x: f32| FiniteF32::new(x);
+        let mut rule = LimitRule {
+            excluded: vec![(x(1.), x(2.))],
+            allowed: Some([0, 1, 5].into_iter().collect()),
+            ..Default::default()
+        };
+        let outside = [0., 1., 1.5, 3., 5., 0.5].map(|v| rule.is_outside(x(v)));
+        assert_eq!(outside, [false, true, true, true, false, true]);
+
+        assert_eq!(rule.sigma_bounds([1., 2.].into_iter()), None);
+        rule.sigma = Some(x(2.));
+        assert_eq!(
+            rule.sigma_bounds([1., 3., f32::NAN].into_iter()),
+            Some((x(0.), x(4.)))
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use super::{limits::LimitEvent, DataEvent, LockableLimitKey};
+use crate::{
+    data_types::{FileKey, LimitKey},
+    LocalizableStr,
+};
+use michael_visualizer_basic::Bitset;
+
+/// Failing rows of a limit
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+struct Failures {
+    /// Rows failing this limit only
+    sole: usize,
+    /// Rows failing this limit, maybe among others
+    any: usize,
+}
+impl Failures {
+    /// `summed` is the count of limits failed by each row
+    fn new(filter: &Bitset, summed: &[u32]) -> Self {
+        Self {
+            sole: filter.ones().filter(|&row| summed[row] == 1).count(),
+            any: filter.count_ones(),
+        }
+    }
+
+    fn get(&self, sole: bool) -> usize {
+        if sole {
+            self.sole
+        } else {
+            self.any
+        }
+    }
+}
+impl std::ops::AddAssign for Failures {
+    fn add_assign(&mut self, rhs: Self) {
+        self.sole += rhs.sole;
+        self.any += rhs.any;
+    }
+}
+
+struct Entry {
+    limit_key: LimitKey,
+    label: String,
+    /// Same order as the files of the ranking
+    files: Vec<Failures>,
+    total: Failures,
+}
+
+struct Ranking {
+    /// Shown files, with label and row count
+    files: Vec<(FileKey, String, usize)>,
+    entries: Vec<Entry>,
+}
+impl Ranking {
+    fn new(state: &super::AppState) -> Self {
+        let plot_files = state.plot_files();
+        let files = plot_files
+            .iter()
+            .map(|file| {
+                (
+                    file.key.clone(),
+                    file.label.as_str().to_string(),
+                    file.data.data_count(),
+                )
+            })
+            .collect();
+        let entries = state
+            .center
+            .limits()
+            .map(|(limit_key, limit)| {
+                let files = plot_files
+                    .iter()
+                    .map(|file| {
+                        state
+                            .center
+                            .filter(file.key, limit_key)
+                            .map(|filter| Failures::new(filter, file.filtering))
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>();
+                let mut total = Failures::default();
+                for failures in &files {
+                    total += *failures;
+                }
+                Entry {
+                    limit_key: limit_key.clone(),
+                    label: limit.get_label().as_str().to_string(),
+                    files,
+                    total,
+                }
+            })
+            .collect();
+        Self { files, entries }
+    }
+
+    /// Limits with failing rows, most failures first, together with their failure count
+    /// `file` is the index of a file, all files are summed up if none
+    fn ranked(&self, file: Option<usize>, sole: bool) -> Vec<(&Entry, usize)> {
+        let mut ranked = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let failures = match file {
+                    Some(index) => entry.files[index],
+                    None => entry.total,
+                };
+                (entry, failures.get(sole))
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(a, a_count), (b, b_count)| {
+            b_count.cmp(a_count).then_with(|| a.label.cmp(&b.label))
+        });
+        ranked
+    }
+
+    fn rows(&self, file: Option<usize>) -> usize {
+        match file {
+            Some(index) => self.files[index].2,
+            None => self.files.iter().map(|(_, _, rows)| rows).sum(),
+        }
+    }
+}
+
+/// Ranks the limits by their failing rows, clicking a limit shows it in the other tabs via locking
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(super) struct ParetoTab {
+    to_show: LockableLimitKey,
+    /// Count rows failing only one limit, otherwise rows failing several limits count for each
+    sole: bool,
+    /// File to rank, all shown files are summed up if none
+    file: Option<FileKey>,
+    #[serde(skip)]
+    ranking: Option<Ranking>,
+}
+impl ParetoTab {
+    fn needs_recompute(&mut self) {
+        self.ranking = None;
+    }
+
+    fn select(&mut self, limit_key: &LimitKey, state: &mut super::AppState) {
+        if let Some(index) = self.to_show.update(limit_key.clone(), state.locked_limits) {
+            state
+                .data_events
+                .push(DataEvent::Limit(LimitEvent::LockableLimit(index)))
+        }
+    }
+}
+
+impl super::DataEventNotifyable for ParetoTab {
+    fn notify(&mut self, event: &DataEvent) -> Vec<DataEvent> {
+        match event {
+            DataEvent::Limit(limit) => match limit {
+                LimitEvent::LockableLimit(_) => {}
+                LimitEvent::Label(_) => self.needs_recompute(),
+                LimitEvent::Limit(_) => self.needs_recompute(),
+                LimitEvent::New(_) => self.needs_recompute(),
+            },
+            DataEvent::File(event) => match event {
+                super::files::FileEvent::LoadFromPath { .. } => {}
+                super::files::FileEvent::ParseFromBytes { .. } => {}
+                super::files::FileEvent::FromData { .. } => {}
+                super::files::FileEvent::ToShow(_) => self.needs_recompute(),
+                super::files::FileEvent::Remove(_) => self.needs_recompute(),
+                super::files::FileEvent::MoveUp(_) => self.needs_recompute(),
+                super::files::FileEvent::MoveDown(_) => self.needs_recompute(),
+                super::files::FileEvent::Label(_) => self.needs_recompute(),
+                super::files::FileEvent::Reload(_) => self.needs_recompute(),
+                super::files::FileEvent::Cancel(_) => {}
+                super::files::FileEvent::LoadError { .. } => {}
+                super::files::FileEvent::ParseError { .. } => {}
+                super::files::FileEvent::Loaded { .. } => self.needs_recompute(),
+            },
+            DataEvent::Filtering => self.needs_recompute(),
+            DataEvent::Formulas => {}
+            DataEvent::Scenarios => {}
+            DataEvent::LimitRequest(_) => {}
+            DataEvent::FileRequest(_) => {}
+            DataEvent::SelectionRequest(_) => {}
+            DataEvent::SelectionEvent(_) => {}
+        }
+        Default::default()
+    }
+
+    fn progress(&mut self, _state: &mut super::AppState) {}
+}
+
+impl super::TabTrait for ParetoTab {
+    fn title(&self, state: &super::AppState) -> &str {
+        LocalizableStr {
+            english: "Yield loss Pareto",
+        }
+        .localize(state.language)
+    }
+
+    fn show(&mut self, state: &mut super::AppState, ui: &mut egui::Ui) {
+        let language = state.language;
+        let ranking = match self.ranking.take() {
+            Some(ranking) => ranking,
+            None => Ranking::new(state),
+        };
+        let file = self
+            .file
+            .as_ref()
+            .and_then(|key| ranking.files.iter().position(|(k, _, _)| k == key));
+
+        let _ = state.ui_selectable_limit(ui, &mut self.to_show);
+        ui.horizontal(|ui| {
+            let all_files = LocalizableStr {
+                english: "All shown files",
+            }
+            .localize(language);
+            egui::ComboBox::from_id_source("ParetoFile")
+                .selected_text(
+                    file.map(|i| ranking.files[i].1.as_str())
+                        .unwrap_or(all_files),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.file, None, all_files);
+                    for (key, label, _) in &ranking.files {
+                        ui.selectable_value(&mut self.file, Some(key.clone()), label);
+                    }
+                });
+            ui.radio_value(
+                &mut self.sole,
+                false,
+                LocalizableStr {
+                    english: "Any failure",
+                }
+                .localize(language),
+            );
+            ui.radio_value(
+                &mut self.sole,
+                true,
+                LocalizableStr {
+                    english: "Sole failure",
+                }
+                .localize(language),
+            );
+        });
+        ui.separator();
+
+        let ranked = ranking.ranked(file, self.sole);
+        let selected = self.to_show.get(state.locked_limits).1.cloned();
+        let mut clicked = None;
+        if ranked.is_empty() {
+            ui.label(
+                LocalizableStr {
+                    english: "No failing rows",
+                }
+                .localize(language),
+            );
+        } else {
+            let rows = ranking.rows(file);
+            let failures = ranked.iter().map(|(_, count)| count).sum::<usize>() as f64;
+            let mut cumulative = 0.;
+            let mut bars = Vec::new();
+            let mut line = Vec::new();
+            for (index, (entry, count)) in ranked.iter().enumerate() {
+                let share = 100. * *count as f64 / failures;
+                cumulative += share;
+                let mut bar = egui::plot::Bar::new(index as f64, share)
+                    .name(format!("{}: {count}", entry.label))
+                    .width(0.8);
+                if selected.as_ref() == Some(&entry.limit_key) {
+                    bar = bar.fill(ui.visuals().selection.bg_fill);
+                }
+                bars.push(bar);
+                line.push([index as f64, cumulative]);
+            }
+            let labels = ranked
+                .iter()
+                .map(|(entry, _)| entry.label.clone())
+                .collect::<Vec<_>>();
+            let count = ranked.len();
+            let response = egui::plot::Plot::new(ui.id().with("pareto"))
+                .height(ui.available_height() * 0.6)
+                .include_y(0.)
+                .include_y(100.)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .allow_boxed_zoom(false)
+                .x_axis_formatter(move |x, _| {
+                    let index = x.round();
+                    if (x - index).abs() < 1e-6 && index >= 0. && (index as usize) < count {
+                        labels[index as usize].clone()
+                    } else {
+                        String::new()
+                    }
+                })
+                .y_axis_formatter(|y, _| format!("{y}%"))
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(egui::plot::BarChart::new(bars).element_formatter(Box::new(
+                        |bar, _| format!("{}\n{:.2}%", bar.name, bar.value),
+                    )));
+                    plot_ui.line(
+                        egui::plot::Line::new(egui::plot::PlotPoints::new(line)).name(
+                            LocalizableStr {
+                                english: "Cumulative",
+                            }
+                            .localize(language),
+                        ),
+                    );
+                    plot_ui.pointer_coordinate()
+                });
+            if response.response.clicked() {
+                if let Some(pointer) = response.inner {
+                    let index = pointer.x.round();
+                    if (pointer.x - index).abs() <= 0.4 && index >= 0. {
+                        clicked = ranked.get(index as usize).map(|(entry, _)| entry);
+                    }
+                }
+            }
+
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("Pareto").striped(true).show(ui, |ui| {
+                    for header in ["Limit", "Failing rows", "Yield loss", "Share"] {
+                        ui.strong(LocalizableStr { english: header }.localize(language));
+                    }
+                    ui.end_row();
+                    for (entry, count) in &ranked {
+                        let is_selected = selected.as_ref() == Some(&entry.limit_key);
+                        if ui.selectable_label(is_selected, &entry.label).clicked() {
+                            clicked = Some(entry);
+                        }
+                        ui.label(count.to_string());
+                        ui.label(format!("{:.2}%", 100. * *count as f64 / rows.max(1) as f64));
+                        ui.label(format!("{:.2}%", 100. * *count as f64 / failures));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        let clicked = clicked.map(|entry| entry.limit_key.clone());
+        drop(ranked);
+        if let Some(limit_key) = clicked {
+            self.select(&limit_key, state);
+        }
+        self.ranking = Some(ranking);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Failures;
+    use michael_visualizer_basic::Bitset;
+
+    #[test]
+    fn failures() {
+        let filter = Bitset::from_fn(6, |row| row % 2 == 0);
+        let summed = [1, 1, 2, 0, 1, 3];
+        let failures = Failures::new(&filter, &summed);
+        assert_eq!(failures, Failures { sole: 2, any: 3 });
+        assert_eq!((failures.get(true), failures.get(false)), (2, 3));
+    }
+}
@@ -95,15 +95,21 @@ impl super::DataEventNotifyable for PlotTab {
             DataEvent::File(event) => match event {
                 super::files::FileEvent::LoadFromPath { .. } => {}
                 super::files::FileEvent::ParseFromBytes { .. } => {}
+                super::files::FileEvent::FromData { .. } => {}
                 super::files::FileEvent::ToShow(_) => self.needs_recompute(),
                 super::files::FileEvent::Remove(_) => self.needs_recompute(),
                 super::files::FileEvent::MoveUp(_) => self.needs_recompute(),
                 super::files::FileEvent::MoveDown(_) => self.needs_recompute(),
                 super::files::FileEvent::Label(_) => self.needs_recompute(),
+                super::files::FileEvent::Reload(_) => self.needs_recompute(),
+                super::files::FileEvent::Cancel(_) => {}
                 super::files::FileEvent::LoadError { .. } => {}
+                super::files::FileEvent::ParseError { .. } => {}
                 super::files::FileEvent::Loaded { .. } => self.needs_recompute(),
             },
             DataEvent::Filtering => self.needs_recompute(),
+            DataEvent::Formulas => {}
+            DataEvent::Scenarios => {}
             DataEvent::LimitRequest(_) => {}
             DataEvent::FileRequest(_) => {}
             DataEvent::SelectionRequest(_) => {}
@@ -178,11 +184,11 @@ impl PlotTab {
             self.x_key
                 .get(state.locked_limits)
                 .1
-                .and_then(|k| state.limits.get(k).map(|l| (k, l))),
+                .and_then(|k| state.center.limit(k).map(|l| (k, l))),
             self.y_key
                 .get(state.locked_limits)
                 .1
-                .and_then(|k| state.limits.get(k).map(|l| (k, l))),
+                .and_then(|k| state.center.limit(k).map(|l| (k, l))),
         ) {
             let mut data = Vec::new();
 
@@ -193,7 +199,7 @@ impl PlotTab {
 
             // find files which need to be drawn, and compute limits (if non are given, min/max will be used)
             for (file_key, (file_label, file, sorting)) in state.files.iter_loaded() {
-                let filtering = state.total_filterings.get(file_key);
+                let filtering = state.center.filters_summed(file_key);
                 let x_data = sorting.get(x_key).map(|column| file.get_column(*column));
                 let y_data = sorting.get(y_key).map(|column| file.get_column(*column));
                 if let (Some(filtering), Some(x_data), Some(y_data)) = (filtering, x_data, y_data) {
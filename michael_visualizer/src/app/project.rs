@@ -0,0 +1,178 @@
+use super::App;
+
+/// Increased whenever the layout of the project file changes, projects of other versions are rejected
+const VERSION: u32 = 1;
+pub(super) const EXTENSION: &str = "mvproject";
+
+#[derive(serde::Serialize)]
+struct ProjectRef<'a> {
+    version: u32,
+    app: AppRef<'a>,
+}
+/// Serialized like [`App`], but with the file paths relative to the project
+#[derive(serde::Serialize)]
+struct AppRef<'a> {
+    language: &'a crate::Language,
+    tabs: &'a super::_tabs::Tabs,
+    mode: &'a super::_dark_light::DarkLightMode,
+    limits: &'a super::limits::LimitContainer,
+    files: super::files::FileContainer,
+    selected: &'a Option<super::selection::Selection>,
+    file_key_generator: &'a crate::data_types::FileKeyGenerator,
+    file_loader: &'a super::file_loader::FileLoader,
+    center: &'a super::DataCenter,
+    locked_limits: &'a Vec<crate::data_types::LimitKey>,
+    formulas: &'a super::formulas::Formulas,
+    scenarios: &'a super::scenarios::Scenarios,
+}
+impl<'a> AppRef<'a> {
+    fn new(app: &'a App, directory: &std::path::Path) -> Self {
+        let App {
+            language,
+            tabs,
+            mode,
+            limits,
+            files,
+            selected,
+            file_key_generator,
+            file_loader,
+            data_events: _,
+            center,
+            locked_limits,
+            requested_screenshot: _,
+            history: _,
+            formulas,
+            scenarios,
+        } = app;
+        Self {
+            language,
+            tabs,
+            mode,
+            limits,
+            files: files.with_paths(|p| {
+                p.strip_prefix(directory)
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|_| p.to_path_buf())
+            }),
+            selected,
+            file_key_generator,
+            file_loader,
+            center,
+            locked_limits,
+            formulas,
+            scenarios,
+        }
+    }
+}
+/// The version is checked with [`Header`]
+#[derive(serde::Deserialize)]
+struct Project {
+    app: App,
+}
+/// Read first, so a project of another version gives a proper message instead of a parse error
+#[derive(serde::Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// Writes the whole analysis: file list, limits and labels, tab layout and selection
+/// Data files in the directory of the project (or below) are stored relative to it,
+/// so a project can be exchanged together with its data
+pub(super) fn save(app: &App, path: &std::path::Path) -> Result<(), String> {
+    let directory = path.parent().unwrap_or(std::path::Path::new(""));
+    let text = ron::ser::to_string_pretty(
+        &ProjectRef {
+            version: VERSION,
+            app: AppRef::new(app, directory),
+        },
+        Default::default(),
+    )
+    .map_err(|e| format!("Failed to write project: {e}"))?;
+    std::fs::write(path, text).map_err(|e| format!("Failed to write '{}': {e}", path.display()))
+}
+
+/// Files still have to be loaded, see [`App::restore`]
+pub(super) fn load(path: &std::path::Path) -> Result<App, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read '{}': {e}", path.display()))?;
+    let Header { version } =
+        ron::from_str(&text).map_err(|e| format!("'{}' is no project: {e}", path.display()))?;
+    match version.cmp(&VERSION) {
+        std::cmp::Ordering::Greater => return Err(format!(
+            "'{}' was written by a newer version (project version {version}, supported {VERSION})",
+            path.display()
+        )),
+        std::cmp::Ordering::Less => return Err(format!(
+            "'{}' was written by an older version (project version {version}, supported {VERSION})",
+            path.display()
+        )),
+        std::cmp::Ordering::Equal => {}
+    }
+    let Project { mut app } =
+        ron::from_str(&text).map_err(|e| format!("'{}' is damaged: {e}", path.display()))?;
+    let directory = path.parent().unwrap_or(std::path::Path::new(""));
+    app.files.map_paths(|p| directory.join(p));
+    Ok(app)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load, save, App, AppRef};
+    use crate::app::{files::FileEvent, DataEvent};
+
+    #[test]
+    fn save_and_load() {
+        // unique per process, so concurrent runs do not collide
+        let root =
+            std::env::temp_dir().join(format!("michael_visualizer_project_{}", std::process::id()));
+        let directory = root.join("project");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("a.mvproject");
+        let mut app = App::default();
+        let mut key_generator = crate::data_types::FileKeyGenerator::default();
+        let (inside, outside) = (key_generator.next(), key_generator.next());
+        let outside_path = root.join("elsewhere").join("b.mv01");
+        app.files
+            .insert(inside.clone(), directory.join("data").join("a.mv01"));
+        app.files.insert(outside.clone(), outside_path.clone());
+        app.locked_limits.push(Default::default());
+        save(&app, &path).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("version: 1"));
+        assert!(!text.contains(&*directory.to_string_lossy()));
+        assert!(text.contains(&*outside_path.to_string_lossy()));
+        // files are loaded again from their absolute paths
+        let paths = |app: &mut App| {
+            app.files
+                .init()
+                .into_iter()
+                .map(|event| match event {
+                    DataEvent::File(FileEvent::LoadFromPath { key, path }) => (key, path),
+                    _ => unreachable!("Only files are loaded"),
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![
+            (inside, directory.join("data").join("a.mv01")),
+            (outside, outside_path),
+        ];
+        let mut loaded = load(&path).unwrap();
+        assert_eq!(paths(&mut loaded), expected);
+        assert_eq!(paths(&mut app), expected);
+        assert_eq!(loaded.locked_limits, app.locked_limits);
+        // the project has the same fields as the app, apart from the paths
+        assert_eq!(
+            ron::to_string(&AppRef::new(&app, &root.join("unrelated"))).unwrap(),
+            ron::to_string(&app).unwrap()
+        );
+
+        std::fs::write(&path, text.replace("version: 1", "version: 2")).unwrap();
+        assert!(matches!(load(&path), Err(message) if message.contains("newer version")));
+        std::fs::write(&path, text.replace("version: 1", "version: 0")).unwrap();
+        assert!(matches!(load(&path), Err(message) if message.contains("older version")));
+        std::fs::write(&path, "no project").unwrap();
+        assert!(load(&path).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
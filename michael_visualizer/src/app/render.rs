@@ -0,0 +1,688 @@
+use super::{DataCenter, PlotFile};
+use crate::data_types::LimitKey;
+use egui::epaint::{ClippedPrimitive, ClippedShape, FontImage, ImageData, Primitive, Shape};
+
+pub(crate) const USAGE: &str = "\
+Usage: michael_visualizer render --plot heatmap|violin|distribution --limit <label> --output <file.png|file.svg>
+                                 [--color <label>] [--x <label>] [--y <label>] [--size <width>x<height>]
+                                 [--limits <limit set>] <file>...
+
+Loads the files, applies their limits and renders a plot of the limit to a PNG or SVG file.
+The heatmap places the dies by the integer limits given by --x and --y (default 'X' and 'Y'),
+violin plots and histograms are split by file and, if given, by the values of the --color limit.
+The limit set is the same as for the 'report' command.";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PlotKind {
+    Heatmap,
+    Violin,
+    Distribution,
+}
+
+/// Plot to render without a window, limits are referred to by label
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RenderSettings {
+    pub kind: PlotKind,
+    pub limit: String,
+    /// Integer limit to split violin plots and histograms by
+    pub color_by: Option<String>,
+    /// Integer limits to place the dies of the heatmap
+    pub x: String,
+    pub y: String,
+    /// Image size in pixels
+    pub size: egui::Vec2,
+    /// Points per violin or bins per histogram
+    pub resolution: usize,
+    /// Palette and scaling of the heatmap
+    pub color_map: super::heatmap::ColorMap,
+}
+impl RenderSettings {
+    pub(crate) fn new(kind: PlotKind, limit: impl Into<String>) -> Self {
+        Self {
+            kind,
+            limit: limit.into(),
+            color_by: None,
+            x: data_format::COLUMN_LABEL.into(),
+            y: data_format::ROW_LABEL.into(),
+            size: egui::vec2(800., 600.),
+            resolution: 31,
+            color_map: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct RenderArgs {
+    settings: RenderSettings,
+    limit_set: Option<std::path::PathBuf>,
+    output: std::path::PathBuf,
+    files: Vec<std::path::PathBuf>,
+}
+impl RenderArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut kind = None;
+        let mut limit = None;
+        let mut color_by = None;
+        let mut x = None;
+        let mut y = None;
+        let mut size = None;
+        let mut limit_set = None;
+        let mut output = None;
+        let mut files = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("Option '{arg}' requires a value"))
+            };
+            match arg.as_str() {
+                "--plot" => {
+                    kind = Some(match value()?.as_str() {
+                        "heatmap" => PlotKind::Heatmap,
+                        "violin" => PlotKind::Violin,
+                        "distribution" => PlotKind::Distribution,
+                        other => return Err(format!("Unknown plot '{other}'")),
+                    })
+                }
+                "--limit" => limit = Some(value()?),
+                "--color" => color_by = Some(value()?),
+                "--x" => x = Some(value()?),
+                "--y" => y = Some(value()?),
+                "--size" => {
+                    let size_text = value()?;
+                    size = Some(
+                        size_text
+                            .split_once('x')
+                            .and_then(|(w, h)| {
+                                Some((w.parse::<u16>().ok()?, h.parse::<u16>().ok()?))
+                            })
+                            .filter(|&(w, h)| w > 0 && h > 0)
+                            .map(|(w, h)| egui::vec2(w as f32, h as f32))
+                            .ok_or_else(|| {
+                                format!("Size is not <width>x<height>: '{size_text}'")
+                            })?,
+                    )
+                }
+                "--limits" => limit_set = Some(value()?.into()),
+                "--output" => output = Some(value()?.into()),
+                option if option.starts_with("--") => {
+                    return Err(format!("Unknown option '{option}'"))
+                }
+                file => files.push(file.into()),
+            }
+        }
+        let mut settings =
+            RenderSettings::new(kind.ok_or("No plot given")?, limit.ok_or("No limit given")?);
+        settings.color_by = color_by;
+        settings.x = x.unwrap_or(settings.x);
+        settings.y = y.unwrap_or(settings.y);
+        settings.size = size.unwrap_or(settings.size);
+        let output = output.ok_or("No output given")?;
+        if files.is_empty() {
+            return Err("No files given".into());
+        }
+        Ok(Self {
+            settings,
+            limit_set,
+            output,
+            files,
+        })
+    }
+}
+
+/// Entry point of the 'render' command, returns the exit code of the process
+pub(crate) fn run(args: &[String]) -> i32 {
+    let args = match RenderArgs::parse(args) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}\n\n{USAGE}");
+            return 2;
+        }
+    };
+    let result = super::report::load_center(&args.files, args.limit_set.as_deref())
+        .and_then(|center| render(&center, &args.settings))
+        .and_then(|image| image.save(&args.output));
+    match result {
+        Ok(()) => 0,
+        Err(msg) => {
+            eprintln!("{msg}");
+            1
+        }
+    }
+}
+
+/// Paints the plot offscreen, with the same code as the tabs (the heatmap has its own layout)
+pub(crate) fn render(center: &DataCenter, settings: &RenderSettings) -> Result<Image, String> {
+    let (limit_key, limit) = find_limit(center, &settings.limit)?;
+    let to_color_key = settings
+        .color_by
+        .as_deref()
+        .map(|label| find_limit(center, label).map(|(key, _)| key))
+        .transpose()?;
+    let axes = if settings.kind == PlotKind::Heatmap {
+        Some((axis(center, &settings.x)?, axis(center, &settings.y)?))
+    } else {
+        None
+    };
+    let files = plot_files(center);
+    Image::paint(settings.size, |painter, rect| match (settings.kind, axes) {
+        (PlotKind::Heatmap, Some(axes)) => super::heatmap::paint(
+            painter,
+            rect,
+            axes,
+            limit_key,
+            limit,
+            &settings.color_map,
+            &files,
+        ),
+        (PlotKind::Heatmap, None) => unreachable!("Axes are computed for heatmaps"),
+        (PlotKind::Violin, _) => super::violinplot::paint(
+            painter,
+            rect,
+            limit_key,
+            limit,
+            to_color_key,
+            &files,
+            settings.resolution,
+        ),
+        (PlotKind::Distribution, _) => super::distribution::paint(
+            painter,
+            rect,
+            limit_key,
+            limit,
+            to_color_key,
+            &files,
+            settings.resolution,
+        ),
+    })
+    .map_err(|msg| msg.english)
+}
+
+fn find_limit<'a>(
+    center: &'a DataCenter,
+    label: &str,
+) -> Result<(&'a LimitKey, &'a super::limits::Limit), String> {
+    center
+        .limits()
+        .find(|(_, limit)| limit.get_label().as_str() == label)
+        .ok_or_else(|| format!("No limit with label '{label}'"))
+}
+
+/// Key, minimum and maximum of an integer limit over all files
+fn axis(center: &DataCenter, label: &str) -> Result<(LimitKey, i32, i32), String> {
+    let (key, _) = find_limit(center, label)?;
+    center
+        .files()
+        .filter_map(|(file_key, _, file)| {
+            let column = *center.limit_indices(file_key)?.get(key)?;
+            file.get_column(column).as_int()
+        })
+        .flatten()
+        .fold(None, |range, &v| match range {
+            None => Some((v, v)),
+            Some((min, max)) => Some((std::cmp::min(min, v), std::cmp::max(max, v))),
+        })
+        .map(|(min, max)| (key.clone(), min, max))
+        .ok_or_else(|| format!("Limit '{label}' has no integer values, it cannot be an axis"))
+}
+
+fn plot_files(center: &DataCenter) -> Vec<PlotFile<'_>> {
+    center
+        .files()
+        .filter_map(|(key, label, data)| {
+            Some(PlotFile {
+                key,
+                label,
+                data,
+                sorting: center.limit_indices(key)?,
+                filtering: center.filters_summed(key)?,
+            })
+        })
+        .collect()
+}
+
+/// Painted plot, kept as shapes for SVG and as triangles for PNG
+pub(crate) struct Image {
+    size: [usize; 2],
+    shapes: Vec<ClippedShape>,
+    primitives: Vec<ClippedPrimitive>,
+    font_image: FontImage,
+}
+impl Image {
+    /// Runs a single frame of an egui context without a window, the painter covers the whole image
+    fn paint<E>(
+        size: egui::Vec2,
+        paint: impl FnOnce(&egui::Painter, egui::Rect) -> Result<(), E>,
+    ) -> Result<Self, E> {
+        let ctx = egui::Context::default();
+        let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, size);
+        let input = egui::RawInput {
+            screen_rect: Some(rect),
+            pixels_per_point: Some(1.),
+            ..Default::default()
+        };
+        let mut result = Ok(());
+        let output = ctx.run(input, |ctx| {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            painter.rect_filled(rect, 0., egui::Color32::WHITE);
+            result = paint(&painter, rect);
+        });
+        result?;
+        let font_image = output
+            .textures_delta
+            .set
+            .into_iter()
+            .find_map(|(id, delta)| match delta.image {
+                ImageData::Font(image) if id == egui::TextureId::default() => Some(image),
+                _ => None,
+            })
+            .expect("Font texture is created in the first frame");
+        let primitives = ctx.tessellate(output.shapes.clone());
+        Ok(Self {
+            size: [size.x as usize, size.y as usize],
+            shapes: output.shapes,
+            primitives,
+            font_image,
+        })
+    }
+
+    /// Format is chosen by the file extension, either 'png' or 'svg'
+    pub(crate) fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let bytes = match extension.as_str() {
+            "png" => self.to_png()?,
+            "svg" => self.to_svg().into_bytes(),
+            _ => return Err(format!("'{}' is neither png nor svg", path.display())),
+        };
+        std::fs::write(path, bytes)
+            .map_err(|e| format!("Failed to write '{}': {e}", path.display()))
+    }
+
+    pub(crate) fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        self.to_rgba()
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageOutputFormat::Png,
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(bytes)
+    }
+
+    /// Software rasterization of the tessellated triangles
+    /// Like the GUI backends, colors are premultiplied and blended in gamma space
+    pub(crate) fn to_rgba(&self) -> image::RgbaImage {
+        // in double precision, so pixels on an edge shared by two triangles are not missed by both
+        fn edge(a: egui::Pos2, b: egui::Pos2, p: egui::Pos2) -> f64 {
+            let [ax, ay, bx, by, px, py] = [a.x, a.y, b.x, b.y, p.x, p.y].map(f64::from);
+            (bx - ax) * (py - ay) - (by - ay) * (px - ax)
+        }
+        let [width, height] = self.size;
+        let canvas =
+            egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32));
+        let [texture_width, texture_height] = self.font_image.size;
+        let texture = self.font_image.srgba_pixels(None).collect::<Vec<_>>();
+        let mut pixels = vec![[0f32; 4]; width * height];
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in &self.primitives
+        {
+            // only the font texture is used, as there are no images in the plots
+            let Primitive::Mesh(mesh) = primitive else {
+                continue;
+            };
+            let clip = clip_rect.intersect(canvas);
+            for triangle in mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+                let area = edge(a.pos, b.pos, c.pos);
+                if area == 0. {
+                    continue;
+                }
+                let bounds = egui::Rect::from_points(&[a.pos, b.pos, c.pos]).intersect(clip);
+                if !bounds.is_positive() {
+                    continue;
+                }
+                for y in bounds.min.y.floor() as usize..bounds.max.y.ceil() as usize {
+                    for x in bounds.min.x.floor() as usize..bounds.max.x.ceil() as usize {
+                        let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+                        let weights = [
+                            edge(b.pos, c.pos, p) / area,
+                            edge(c.pos, a.pos, p) / area,
+                            edge(a.pos, b.pos, p) / area,
+                        ]
+                        .map(|w| w as f32);
+                        if !clip.contains(p) || weights.iter().any(|&w| w < 0.) {
+                            continue;
+                        }
+                        let interpolate = |f: &dyn Fn(&egui::epaint::Vertex) -> f32| {
+                            weights[0] * f(a) + weights[1] * f(b) + weights[2] * f(c)
+                        };
+                        let texel = {
+                            let u = interpolate(&|v| v.uv.x) * texture_width as f32;
+                            let v = interpolate(&|v| v.uv.y) * texture_height as f32;
+                            let u = (u.max(0.) as usize).min(texture_width - 1);
+                            let v = (v.max(0.) as usize).min(texture_height - 1);
+                            texture[u + v * texture_width]
+                        };
+                        let source = [0, 1, 2, 3].map(|i| {
+                            interpolate(&|v| v.color[i] as f32) / 255. * texel[i] as f32 / 255.
+                        });
+                        let pixel = &mut pixels[x + y * width];
+                        for i in 0..4 {
+                            pixel[i] = source[i] + pixel[i] * (1. - source[3]);
+                        }
+                    }
+                }
+            }
+        }
+        image::RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+            let [r, g, b, a] = pixels[x as usize + y as usize * width];
+            let unmultiply = |c: f32| {
+                if a > 0. {
+                    (c / a * 255.).round().clamp(0., 255.) as u8
+                } else {
+                    0
+                }
+            };
+            image::Rgba([
+                unmultiply(r),
+                unmultiply(g),
+                unmultiply(b),
+                (a * 255.).round().clamp(0., 255.) as u8,
+            ])
+        })
+    }
+
+    pub(crate) fn to_svg(&self) -> String {
+        let [width, height] = self.size;
+        let canvas =
+            egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32));
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+        );
+        let mut clips = Vec::<egui::Rect>::new();
+        for ClippedShape(clip_rect, shape) in &self.shapes {
+            if clip_rect.contains_rect(canvas) {
+                svg_shape(shape, &mut svg);
+            } else {
+                let id = clips.len();
+                clips.push(*clip_rect);
+                svg += &format!(
+                    "<clipPath id=\"clip{id}\"><rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/></clipPath>\n<g clip-path=\"url(#clip{id})\">\n",
+                    clip_rect.min.x,
+                    clip_rect.min.y,
+                    clip_rect.width(),
+                    clip_rect.height()
+                );
+                svg_shape(shape, &mut svg);
+                svg += "</g>\n";
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+}
+
+fn svg_shape(shape: &Shape, svg: &mut String) {
+    use egui::epaint::{CircleShape, CubicBezierShape, PathShape, QuadraticBezierShape, RectShape};
+    let points = |points: &[egui::Pos2]| {
+        points
+            .iter()
+            .map(|p| format!("{:.2},{:.2}", p.x, p.y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match shape {
+        Shape::Noop | Shape::Callback(_) => {}
+        Shape::Vec(shapes) => {
+            for shape in shapes {
+                svg_shape(shape, svg);
+            }
+        }
+        Shape::Circle(CircleShape {
+            center,
+            radius,
+            fill,
+            stroke,
+        }) => {
+            *svg += &format!(
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{radius:.2}\" {} {}/>\n",
+                center.x,
+                center.y,
+                svg_color("fill", *fill),
+                svg_stroke(stroke)
+            )
+        }
+        Shape::LineSegment { points, stroke } => {
+            *svg += &format!(
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" {}/>\n",
+                points[0].x,
+                points[0].y,
+                points[1].x,
+                points[1].y,
+                svg_stroke(stroke)
+            )
+        }
+        Shape::Path(PathShape {
+            points: path,
+            closed,
+            fill,
+            stroke,
+        }) => {
+            let (element, fill) = if *closed {
+                ("polygon", *fill)
+            } else {
+                ("polyline", egui::Color32::TRANSPARENT)
+            };
+            *svg += &format!(
+                "<{element} points=\"{}\" {} {}/>\n",
+                points(path),
+                svg_color("fill", fill),
+                svg_stroke(stroke)
+            )
+        }
+        Shape::Rect(RectShape {
+            rect,
+            rounding,
+            fill,
+            stroke,
+        }) => {
+            *svg += &format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" rx=\"{:.2}\" {} {}/>\n",
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height(),
+            rounding.nw,
+            svg_color("fill", *fill),
+            svg_stroke(stroke)
+        )
+        }
+        Shape::Text(text) => svg_text(text, svg),
+        Shape::Mesh(mesh) => {
+            // meshes are only used for colored quads, e.g. dies, so edges are kept sharp
+            *svg += "<g shape-rendering=\"crispEdges\">\n";
+            for triangle in mesh.indices.chunks_exact(3) {
+                let vertices = triangle.iter().map(|&i| mesh.vertices[i as usize].pos);
+                *svg += &format!(
+                    "<polygon points=\"{}\" {}/>\n",
+                    points(&vertices.collect::<Vec<_>>()),
+                    svg_color("fill", mesh.vertices[triangle[0] as usize].color)
+                );
+            }
+            *svg += "</g>\n";
+        }
+        Shape::QuadraticBezier(QuadraticBezierShape {
+            points: [p0, p1, p2],
+            closed,
+            fill,
+            stroke,
+        }) => {
+            *svg += &format!(
+                "<path d=\"M {:.2},{:.2} Q {:.2},{:.2} {:.2},{:.2}{}\" {} {}/>\n",
+                p0.x,
+                p0.y,
+                p1.x,
+                p1.y,
+                p2.x,
+                p2.y,
+                if *closed { " Z" } else { "" },
+                svg_color("fill", *fill),
+                svg_stroke(stroke)
+            )
+        }
+        Shape::CubicBezier(CubicBezierShape {
+            points: [p0, p1, p2, p3],
+            closed,
+            fill,
+            stroke,
+        }) => {
+            *svg += &format!(
+                "<path d=\"M {:.2},{:.2} C {:.2},{:.2} {:.2},{:.2} {:.2},{:.2}{}\" {} {}/>\n",
+                p0.x,
+                p0.y,
+                p1.x,
+                p1.y,
+                p2.x,
+                p2.y,
+                p3.x,
+                p3.y,
+                if *closed { " Z" } else { "" },
+                svg_color("fill", *fill),
+                svg_stroke(stroke)
+            )
+        }
+    }
+}
+
+/// One text element per run of glyphs with the same format, each glyph is placed explicitly
+fn svg_text(text: &egui::epaint::TextShape, svg: &mut String) {
+    let egui::epaint::TextShape {
+        pos,
+        galley,
+        override_text_color,
+        angle,
+        ..
+    } = text;
+    if *angle != 0. {
+        *svg += &format!(
+            "<g transform=\"rotate({:.2} {:.2} {:.2})\">\n",
+            angle.to_degrees(),
+            pos.x,
+            pos.y
+        );
+    }
+    for row in &galley.rows {
+        let glyphs = row
+            .glyphs
+            .iter()
+            .filter(|g| !g.chr.is_whitespace())
+            .collect::<Vec<_>>();
+        for run in glyphs.chunk_by(|a, b| a.section_index == b.section_index) {
+            let format = &galley.job.sections[run[0].section_index as usize].format;
+            let family = match &format.font_id.family {
+                egui::FontFamily::Proportional => "sans-serif",
+                egui::FontFamily::Monospace => "monospace",
+                egui::FontFamily::Name(name) => name,
+            };
+            let xs = run
+                .iter()
+                .map(|g| format!("{:.2}", pos.x + g.pos.x))
+                .collect::<Vec<_>>()
+                .join(" ");
+            *svg += &format!(
+                "<text x=\"{xs}\" y=\"{:.2}\" font-family=\"{}\" font-size=\"{:.2}\" {}>{}</text>\n",
+                pos.y + run[0].pos.y,
+                xml_escape(family),
+                format.font_id.size,
+                svg_color("fill", override_text_color.unwrap_or(format.color)),
+                xml_escape(&run.iter().map(|g| g.chr).collect::<String>())
+            );
+        }
+    }
+    if *angle != 0. {
+        *svg += "</g>\n";
+    }
+}
+
+fn svg_color(attribute: &str, color: egui::Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    match a {
+        0 => format!("{attribute}=\"none\""),
+        255 => format!("{attribute}=\"#{r:02x}{g:02x}{b:02x}\""),
+        a => format!(
+            "{attribute}=\"#{r:02x}{g:02x}{b:02x}\" {attribute}-opacity=\"{:.3}\"",
+            a as f32 / 255.
+        ),
+    }
+}
+
+fn svg_stroke(stroke: &egui::Stroke) -> String {
+    if stroke.width > 0. && stroke.color.a() > 0 {
+        format!(
+            "{} stroke-width=\"{:.2}\"",
+            svg_color("stroke", stroke.color),
+            stroke.width
+        )
+    } else {
+        "stroke=\"none\"".into()
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render, PlotKind, RenderArgs, RenderSettings};
+    use crate::app::files::FileData;
+    use data_format::{ColumnInfo, DataFormat};
+
+    #[test]
+    fn render_plots() {
+        let mut data = DataFormat::new("", vec![0, 1, 2, 0, 1, 2], vec![0, 0, 0, 1, 1, 1]).unwrap();
+        data.add_data(
+            "Vth",
+            vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+            ColumnInfo::new(None, Some(0.55)),
+        )
+        .unwrap();
+        let center = crate::app::report::center_from_files(
+            vec![("wafer".into(), FileData::from(data))],
+            None,
+        );
+        for kind in [PlotKind::Heatmap, PlotKind::Violin, PlotKind::Distribution] {
+            let mut settings = RenderSettings::new(kind, "Vth");
+            settings.size = egui::vec2(300., 200.);
+            let image = render(&center, &settings).unwrap();
+            let svg = image.to_svg();
+            assert!(svg.starts_with("<svg "), "{kind:?}");
+            assert!(svg.contains(">wafer</text>"), "{kind:?}");
+            let rgba = image.to_rgba();
+            assert_eq!(rgba.dimensions(), (300, 200));
+            // background is white, the plot draws on top of it
+            assert!(rgba.pixels().all(|p| p.0[3] == 255), "{kind:?}");
+            assert!(rgba.pixels().any(|p| p.0 != [255; 4]), "{kind:?}");
+        }
+        assert!(render(&center, &RenderSettings::new(PlotKind::Violin, "Idd")).is_err());
+
+        let args = ["--plot", "heatmap", "--limit", "Vth", "--size", "300x200"]
+            .into_iter()
+            .chain(["--output", "a.png", "a.mv01"])
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let args = RenderArgs::parse(&args).unwrap();
+        assert_eq!(args.settings.size, egui::vec2(300., 200.));
+        assert_eq!(args.output, std::path::PathBuf::from("a.png"));
+        assert!(RenderArgs::parse(&["--plot".into(), "pie".into()]).is_err());
+    }
+}
@@ -0,0 +1,390 @@
+use super::files::FileData;
+use super::limits::{LimitData, LimitSet};
+use super::statistics::{yield_percent, Summary};
+use super::DataCenter;
+use crate::data_types::finite_f32::FiniteF32;
+
+pub(crate) const USAGE: &str = "\
+Usage: michael_visualizer report [--format text|csv|json] [--limits <limit set>] <file>...
+
+Loads the files, applies their limits and prints yield, fail counts and statistics per file and limit.
+The limit set is a CSV (';'-separated), JSON or TOML file, chosen by extension,
+with 'label', 'lower', 'upper' and optionally 'unit' per limit,
+it replaces the limits of the files with the same label.";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ReportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+struct ReportArgs {
+    format: ReportFormat,
+    limit_set: Option<std::path::PathBuf>,
+    files: Vec<std::path::PathBuf>,
+}
+impl ReportArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut format = ReportFormat::Text;
+        let mut limit_set = None;
+        let mut files = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Option '{arg}' requires a value"))
+            };
+            match arg.as_str() {
+                "--format" => {
+                    format = match value()?.as_str() {
+                        "text" => ReportFormat::Text,
+                        "csv" => ReportFormat::Csv,
+                        "json" => ReportFormat::Json,
+                        other => return Err(format!("Unknown format '{other}'")),
+                    }
+                }
+                "--limits" => limit_set = Some(value()?.into()),
+                option if option.starts_with("--") => {
+                    return Err(format!("Unknown option '{option}'"))
+                }
+                file => files.push(file.into()),
+            }
+        }
+        if files.is_empty() {
+            return Err("No files given".into());
+        }
+        Ok(Self {
+            format,
+            limit_set,
+            files,
+        })
+    }
+}
+
+/// Entry point of the 'report' command, returns the exit code of the process
+pub(crate) fn run(args: &[String]) -> i32 {
+    let args = match ReportArgs::parse(args) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}\n\n{USAGE}");
+            return 2;
+        }
+    };
+    match load_center(&args.files, args.limit_set.as_deref()) {
+        Ok(center) => {
+            let report = Report::new(&center);
+            let text = match args.format {
+                ReportFormat::Text => report.to_text(),
+                ReportFormat::Csv => report.to_csv(),
+                ReportFormat::Json => report.to_json(),
+            };
+            print!("{text}");
+            0
+        }
+        Err(msg) => {
+            eprintln!("{msg}");
+            1
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct LimitReport {
+    label: String,
+    lower: Option<FiniteF32>,
+    upper: Option<FiniteF32>,
+    unit: Option<String>,
+    /// Rows outside of this limit, unmeasured values included
+    fails: usize,
+    yield_percent: f64,
+    /// Statistics of all measured values, independent of the other limits
+    statistics: Option<Summary>,
+}
+#[derive(serde::Serialize)]
+struct FileReport {
+    label: String,
+    rows: usize,
+    /// Rows which pass all limits
+    passed: usize,
+    yield_percent: f64,
+    limits: Vec<LimitReport>,
+}
+#[derive(serde::Serialize)]
+struct Report {
+    files: Vec<FileReport>,
+}
+
+/// Loads the files with their limits, the limit set replaces limits with the same label
+pub(super) fn load_center(
+    files: &[std::path::PathBuf],
+    limit_set: Option<&std::path::Path>,
+) -> Result<DataCenter, String> {
+    let limit_set = limit_set.map(LimitSet::read_path).transpose()?;
+    let mut loaded = Vec::new();
+    for path in files {
+        let parsed = super::file_loader::read_path(path, None, &Default::default())
+            .map_err(|e| format!("Failed to load '{}': {e}", path.display()))?;
+        let (file, _) =
+            parsed.map_err(|e| format!("'{}': {}", path.display(), e.message().english))?;
+        let label = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .to_string();
+        loaded.push((label, file));
+    }
+    Ok(center_from_files(loaded, limit_set.as_ref()))
+}
+
+/// Limits of the files are used, unless the limit set has an entry with the same label
+pub(super) fn center_from_files(
+    files: Vec<(String, FileData)>,
+    limit_set: Option<&LimitSet>,
+) -> DataCenter {
+    use michael_visualizer_basic::{DataEvent, FileEvent, LimitEvent};
+    let mut center = DataCenter::default();
+    for (label, file) in files {
+        let key = center.new_file_key();
+        let _ = center.progress(std::iter::once(DataEvent::File(FileEvent::Loaded {
+            key,
+            label: label.into(),
+            file,
+        })));
+    }
+    if let Some(limit_set) = limit_set {
+        let changed = center
+            .limits()
+            .filter_map(|(key, limit)| {
+                limit_set.get(limit.get_label()).map(|entry| {
+                    let mut limit = limit.clone();
+                    limit.apply(entry);
+                    DataEvent::Limit(LimitEvent::Value(key.clone(), limit))
+                })
+            })
+            .collect::<Vec<_>>();
+        let _ = center.progress(changed.into_iter());
+        let _ = center.wait();
+    }
+    center
+}
+
+impl Report {
+    fn new(center: &DataCenter) -> Self {
+        let files = center
+            .files()
+            .map(|(file_key, label, file)| {
+                let indices = center.limit_indices(file_key).expect("File is loaded");
+                let limits = center
+                    .limits()
+                    .filter(|(_, limit)| !limit.is_trivial())
+                    .filter_map(|(limit_key, limit)| {
+                        let column = file.get_column(*indices.get(limit_key)?);
+                        let LimitData {
+                            label,
+                            lower,
+                            upper,
+                            unit,
+                            ..
+                        } = limit.data();
+                        let fails = center
+                            .filter(file_key, limit_key)
+                            .map(|f| f.count_ones())
+                            .unwrap_or_default();
+                        Some(LimitReport {
+                            label: label.as_str().to_string(),
+                            lower,
+                            upper,
+                            unit,
+                            fails,
+                            yield_percent: yield_percent(
+                                file.data_count() - fails,
+                                file.data_count(),
+                            ),
+                            statistics: Summary::new(column.iter_float()),
+                        })
+                    })
+                    .collect();
+                let filters = center.filters_summed(file_key).unwrap_or_default();
+                let passed = filters.iter().filter(|&&n| n == 0).count();
+                FileReport {
+                    label: label.as_str().to_string(),
+                    rows: file.data_count(),
+                    passed,
+                    yield_percent: yield_percent(passed, file.data_count()),
+                    limits,
+                }
+            })
+            .collect();
+        Self { files }
+    }
+
+    fn to_text(&self) -> String {
+        let value = |v: Option<FiniteF32>| v.map(|v| v.to_string()).unwrap_or("-".into());
+        let mut text = String::new();
+        for file in &self.files {
+            text += &format!(
+                "{label}: {passed} of {rows} rows pass, yield {yield_:.2}%\n",
+                label = file.label,
+                passed = file.passed,
+                rows = file.rows,
+                yield_ = file.yield_percent
+            );
+            let mut table = vec![[
+                "Limit", "Lower", "Upper", "Unit", "Fails", "Yield", "Count", "Min", "Max", "Mean",
+                "Std dev",
+            ]
+            .map(String::from)];
+            for limit in &file.limits {
+                let statistics = |f: fn(&Summary) -> String| {
+                    limit.statistics.as_ref().map(f).unwrap_or("-".into())
+                };
+                table.push([
+                    limit.label.clone(),
+                    value(limit.lower),
+                    value(limit.upper),
+                    limit.unit.clone().unwrap_or_default(),
+                    limit.fails.to_string(),
+                    format!("{:.2}%", limit.yield_percent),
+                    statistics(|s| s.count.to_string()),
+                    statistics(|s| format!("{:.4}", s.min)),
+                    statistics(|s| format!("{:.4}", s.max)),
+                    statistics(|s| format!("{:.4}", s.mean)),
+                    statistics(|s| format!("{:.4}", s.std_dev)),
+                ]);
+            }
+            let widths = (0..table[0].len())
+                .map(|i| {
+                    table
+                        .iter()
+                        .map(|r| r[i].chars().count())
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect::<Vec<_>>();
+            for row in table {
+                let line = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, &width)| format!("{cell:width$}"))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                text += &format!("  {}\n", line.trim_end());
+            }
+            text += "\n";
+        }
+        text
+    }
+
+    /// One line per file and limit, the line with an empty limit is the yield of all limits
+    fn to_csv(&self) -> String {
+        use super::_helper::csv_cell;
+        let value = |v: Option<FiniteF32>| v.map(|v| v.to_string()).unwrap_or_default();
+        let mut text =
+            "file;limit;lower;upper;unit;rows;fails;yield_percent;count;min;max;mean;std_dev\n"
+                .to_string();
+        for file in &self.files {
+            text += &format!(
+                "{label};;;;;{rows};{fails};{yield_};;;;;\n",
+                label = csv_cell(&file.label),
+                rows = file.rows,
+                fails = file.rows - file.passed,
+                yield_ = file.yield_percent
+            );
+            for limit in &file.limits {
+                let statistics = limit
+                    .statistics
+                    .as_ref()
+                    .map(|s| format!("{};{};{};{};{}", s.count, s.min, s.max, s.mean, s.std_dev))
+                    .unwrap_or("0;;;;".into());
+                text += &format!(
+                    "{file};{label};{lower};{upper};{unit};{rows};{fails};{yield_};{statistics}\n",
+                    file = csv_cell(&file.label),
+                    label = csv_cell(&limit.label),
+                    lower = value(limit.lower),
+                    upper = value(limit.upper),
+                    unit = csv_cell(limit.unit.as_deref().unwrap_or_default()),
+                    rows = file.rows,
+                    fails = limit.fails,
+                    yield_ = limit.yield_percent
+                );
+            }
+        }
+        text
+    }
+
+    /// Non-finite numbers, e.g. the mean of a single value, are written as null
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Report consists of plain values") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{center_from_files, Report, ReportArgs, ReportFormat};
+    use crate::app::files::FileData;
+    use crate::app::limits::LimitSet;
+    use data_format::{ColumnInfo, DataFormat};
+
+    #[test]
+    fn report() {
+        let file = |values: Vec<f32>| {
+            let mut data = DataFormat::new(
+                "",
+                (0..values.len() as i32).collect(),
+                vec![0; values.len()],
+            )
+            .unwrap();
+            data.add_data("v", values, ColumnInfo::new(Some(0.), Some(2.)))
+                .unwrap();
+            FileData::from(data)
+        };
+        let a = file(vec![1., 3., 5., f32::NAN]);
+        let b = file(vec![1., 1.5]);
+        let report = Report::new(&center_from_files(
+            vec![("a".into(), a.clone()), ("b".into(), b)],
+            None,
+        ));
+        assert_eq!(
+            report
+                .files
+                .iter()
+                .map(|f| (f.rows, f.passed))
+                .collect::<Vec<_>>(),
+            [(4, 1), (2, 2)]
+        );
+        // Y has a single value, so it is skipped like in the limits tab
+        let limit = &report.files[0].limits[1];
+        assert_eq!((limit.label.as_str(), limit.fails), ("v", 3));
+        let statistics = limit.statistics.as_ref().unwrap();
+        assert_eq!((statistics.count, statistics.mean), (3, 3.));
+
+        let limit_set = LimitSet::parse_csv("label;lower;upper\nv;-;4").unwrap();
+        let report = Report::new(&center_from_files(vec![("a".into(), a)], Some(&limit_set)));
+        assert_eq!(report.files[0].passed, 2);
+        assert!(report.to_csv().contains("\na;v;;4;;4;2;50;3;1;5;3;"));
+        assert!(report
+            .to_json()
+            .starts_with(r#"{"files":[{"label":"a","rows":4,"passed":2,"yield_percent":50.0"#));
+        // labels with the delimiter or quotes are quoted
+        let quoted = Report::new(&center_from_files(
+            vec![("a;\"b\"".into(), file(vec![1.]))],
+            None,
+        ));
+        assert!(quoted
+            .to_csv()
+            .contains("\n\"a;\"\"b\"\"\";;;;;1;0;100;;;;;\n"));
+
+        assert_eq!(
+            ReportArgs::parse(&["--format".into(), "json".into(), "a.mv01".into()]),
+            Ok(ReportArgs {
+                format: ReportFormat::Json,
+                limit_set: None,
+                files: vec!["a.mv01".into()]
+            })
+        );
+        assert!(ReportArgs::parse(&[]).is_err());
+        assert!(ReportArgs::parse(&["--format".into()]).is_err());
+    }
+}
@@ -0,0 +1,545 @@
+use super::statistics::yield_percent;
+use super::{limits::LimitEvent, DataEvent};
+use crate::data_types::{FileKey, LimitKey};
+use crate::LocalizableStr;
+use michael_visualizer_basic::{Bitset, Filters};
+
+/// Named copy of the limits, e.g. "spec A", to compare with the current limits
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub(super) struct Scenario {
+    name: String,
+    limits: std::collections::HashMap<LimitKey, super::limits::Limit>,
+    /// Shown in the comparison
+    compared: bool,
+}
+impl Scenario {
+    fn new(name: &str, limits: &super::DataCenter) -> Self {
+        Self {
+            name: name.to_string(),
+            limits: limits
+                .limits()
+                .map(|(key, limit)| (key.clone(), limit.clone()))
+                .collect(),
+            compared: true,
+        }
+    }
+
+    /// Limits whose setting differs from the current one
+    fn differing<'a>(
+        &'a self,
+        limits: &'a super::DataCenter,
+    ) -> impl Iterator<Item = (&'a LimitKey, &'a super::limits::Limit)> {
+        self.limits.iter().filter(|(key, limit)| {
+            limits
+                .limit(key)
+                .is_some_and(|current| !current.has_same_setting(limit))
+        })
+    }
+
+    fn changed(
+        &self,
+        limits: &super::DataCenter,
+    ) -> std::collections::HashMap<LimitKey, super::limits::Limit> {
+        self.differing(limits)
+            .map(|(key, limit)| (key.clone(), limit.clone()))
+            .collect()
+    }
+}
+
+/// Scenarios in the order they were saved
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(super) struct Scenarios {
+    scenarios: Vec<Scenario>,
+    /// Own filters of the limits of a scenario in a file, computed when first needed
+    /// They only change with the scenario or the file, not with the current limits
+    #[serde(skip)]
+    own_filters:
+        std::collections::HashMap<(String, FileKey), std::collections::HashMap<LimitKey, Bitset>>,
+}
+impl Scenarios {
+    pub(super) fn names(&self) -> impl Iterator<Item = &str> {
+        self.scenarios.iter().map(|s| s.name.as_str())
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.scenarios.iter().position(|s| s.name == name)
+    }
+
+    /// Filtering of a file, if the limits were those of the scenario with this name
+    /// Limits added after the scenario was saved keep their current value
+    pub(super) fn filters(
+        &mut self,
+        name: &str,
+        file_key: &FileKey,
+        center: &super::DataCenter,
+    ) -> Option<Filters<LimitKey>> {
+        let scenario = &self.scenarios[self.position(name)?];
+        let changed = scenario.changed(center);
+        let known = self
+            .own_filters
+            .entry((name.to_string(), file_key.clone()))
+            .or_default();
+        for (key, limit) in &changed {
+            if !known.contains_key(key) {
+                if let Some(filter) = center.filter_with(file_key, key, limit) {
+                    known.insert(key.clone(), filter);
+                }
+            }
+        }
+        center.filters_with(file_key, &changed, known)
+    }
+
+    /// Summed filtering of each loaded file, if the limits were those of the scenario
+    pub(super) fn summed(
+        &mut self,
+        name: &str,
+        center: &super::DataCenter,
+    ) -> std::collections::HashMap<FileKey, Vec<u32>> {
+        let keys = center
+            .files()
+            .map(|(key, _, _)| key.clone())
+            .collect::<Vec<_>>();
+        keys.into_iter()
+            .filter_map(|key| {
+                self.filters(name, &key, center)
+                    .map(|filters| (key, filters.summed))
+            })
+            .collect()
+    }
+
+    /// Limit as saved in the scenario, or the current one, if the scenario does not have it
+    pub(super) fn limit<'a>(
+        &'a self,
+        name: &str,
+        key: &LimitKey,
+        center: &'a super::DataCenter,
+    ) -> Option<&'a super::limits::Limit> {
+        let current = center.limit(key)?;
+        Some(
+            self.position(name)
+                .and_then(|index| self.scenarios[index].limits.get(key))
+                .unwrap_or(current),
+        )
+    }
+
+    /// Cached filters of a file are dropped, when its columns change
+    pub(super) fn notify(&mut self, event: &DataEvent) {
+        use super::files::FileEvent;
+        match event {
+            DataEvent::File(
+                FileEvent::Loaded { key, .. } | FileEvent::Remove(key) | FileEvent::Reload(key),
+            ) => self.own_filters.retain(|(_, file_key), _| file_key != key),
+            DataEvent::Formulas => self.own_filters.clear(),
+            _ => {}
+        }
+    }
+
+    /// Choice between the current limits and a scenario, returns true if it changed
+    /// A scenario which was removed meanwhile is reset to the current limits
+    pub(super) fn ui_select(
+        &self,
+        ui: &mut egui::Ui,
+        id_source: &str,
+        current: &str,
+        hover_text: &str,
+        selected: &mut Option<String>,
+    ) -> bool {
+        let previous = selected.clone();
+        if selected
+            .as_ref()
+            .is_some_and(|name| self.position(name).is_none())
+        {
+            *selected = None;
+        }
+        egui::ComboBox::from_id_source(id_source)
+            .selected_text(selected.as_deref().unwrap_or(current))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(selected, None, current);
+                for name in self.names() {
+                    ui.selectable_value(selected, Some(name.to_string()), name);
+                }
+            })
+            .response
+            .on_hover_text(hover_text);
+        previous != *selected
+    }
+
+    /// Saves the current limits, replacing the scenario with the same name
+    fn save(&mut self, name: &str, limits: &super::DataCenter) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Name is empty".into());
+        }
+        let scenario = Scenario::new(name, limits);
+        self.own_filters.retain(|(scenario, _), _| scenario != name);
+        match self.position(name) {
+            Some(index) => self.scenarios[index] = scenario,
+            None => self.scenarios.push(scenario),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, index: usize) {
+        let scenario = self.scenarios.remove(index);
+        self.own_filters
+            .retain(|(name, _), _| *name != scenario.name);
+    }
+}
+
+/// Plot files with the filtering of a scenario, see [`Scenarios::summed`], other files are left out
+pub(super) fn with_filterings<'a>(
+    files: Vec<super::PlotFile<'a>>,
+    filterings: &'a std::collections::HashMap<FileKey, Vec<u32>>,
+) -> Vec<super::PlotFile<'a>> {
+    files
+        .into_iter()
+        .filter_map(|file| {
+            Some(super::PlotFile {
+                filtering: filterings.get(file.key)?,
+                ..file
+            })
+        })
+        .collect()
+}
+
+/// Yield of each shown file, for the current limits and each compared scenario
+struct Comparison {
+    /// Names of the compared scenarios
+    scenarios: Vec<String>,
+    /// Label, row count and passing rows, the current limits first, then the scenarios
+    files: Vec<(String, usize, Vec<usize>)>,
+}
+impl Comparison {
+    fn new(state: &mut super::AppState) -> Self {
+        let scenarios = state
+            .scenarios
+            .scenarios
+            .iter()
+            .filter(|s| s.compared)
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>();
+        let passing = |summed: &[u32]| summed.iter().filter(|&&n| n == 0).count();
+        let current = state
+            .plot_files()
+            .into_iter()
+            .map(|file| {
+                (
+                    file.key.clone(),
+                    file.label.as_str().to_string(),
+                    file.filtering.len(),
+                    passing(file.filtering),
+                )
+            })
+            .collect::<Vec<_>>();
+        let files = current
+            .into_iter()
+            .map(|(key, label, rows, current)| {
+                let mut yields = vec![current];
+                for name in &scenarios {
+                    yields.push(
+                        state
+                            .scenarios
+                            .filters(name, &key, state.center)
+                            .map(|filters| passing(&filters.summed))
+                            .unwrap_or_default(),
+                    );
+                }
+                (label, rows, yields)
+            })
+            .collect();
+        Self { scenarios, files }
+    }
+
+    /// Sum of all files, with the same layout as a file
+    fn total(&self) -> (usize, Vec<usize>) {
+        let mut passing = vec![0; self.scenarios.len() + 1];
+        let mut rows = 0;
+        for (_, file_rows, file_passing) in &self.files {
+            rows += file_rows;
+            for (sum, p) in passing.iter_mut().zip(file_passing) {
+                *sum += p;
+            }
+        }
+        (rows, passing)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(super) struct ScenarioTab {
+    name: String,
+    #[serde(skip)]
+    error: Option<String>,
+    #[serde(skip)]
+    comparison: Option<Comparison>,
+}
+impl ScenarioTab {
+    fn needs_recompute(&mut self) {
+        self.comparison = None;
+    }
+
+    fn show_scenarios(&mut self, state: &mut super::AppState, ui: &mut egui::Ui) {
+        let super::AppState {
+            language,
+            center,
+            data_events,
+            scenarios,
+            ..
+        } = state;
+        let language = *language;
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.name)
+                    .hint_text(LocalizableStr { english: "Name" }.localize(language))
+                    .desired_width(160.),
+            );
+            let text = if scenarios.position(self.name.trim()).is_none() {
+                LocalizableStr {
+                    english: "Save current limits",
+                }
+            } else {
+                LocalizableStr {
+                    english: "Replace with current limits",
+                }
+            };
+            if ui.button(text.localize(language)).clicked() {
+                match scenarios.save(&self.name, center) {
+                    Ok(()) => {
+                        self.name.clear();
+                        self.error = None;
+                        data_events.push(DataEvent::Scenarios);
+                    }
+                    Err(error) => self.error = Some(error),
+                }
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        let mut to_apply = None;
+        let mut to_remove = None;
+        egui::Grid::new("Scenarios").striped(true).show(ui, |ui| {
+            for (index, scenario) in scenarios.scenarios.iter_mut().enumerate() {
+                if ui
+                    .checkbox(&mut scenario.compared, &scenario.name)
+                    .changed()
+                {
+                    data_events.push(DataEvent::Scenarios);
+                }
+                ui.label(format!(
+                    "{}: {}",
+                    LocalizableStr {
+                        english: "Differing limits"
+                    }
+                    .localize(language),
+                    scenario.differing(center).count()
+                ));
+                if ui
+                    .button(LocalizableStr { english: "Apply" }.localize(language))
+                    .on_hover_text(
+                        LocalizableStr {
+                            english: "Replace the current limits by the limits of this scenario",
+                        }
+                        .localize(language),
+                    )
+                    .clicked()
+                {
+                    to_apply = Some(index);
+                }
+                if ui.button("\u{1F5D1}").clicked() {
+                    to_remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = to_apply {
+            for (key, limit) in scenarios.scenarios[index].changed(center) {
+                if let Some(current) = center.limit_mut(&key) {
+                    let renamed = current.get_label() != limit.get_label();
+                    current.restore(&limit);
+                    if renamed {
+                        data_events.push(DataEvent::Limit(LimitEvent::Label(key.clone())));
+                    }
+                    data_events.push(DataEvent::Limit(LimitEvent::Limit(key)));
+                }
+            }
+        }
+        if let Some(index) = to_remove {
+            scenarios.remove(index);
+            data_events.push(DataEvent::Scenarios);
+        }
+    }
+}
+
+impl super::DataEventNotifyable for ScenarioTab {
+    fn notify(&mut self, event: &DataEvent) -> Vec<DataEvent> {
+        match event {
+            DataEvent::Limit(limit) => match limit {
+                LimitEvent::LockableLimit(_) => {}
+                LimitEvent::Label(_) => {}
+                LimitEvent::Limit(_) => self.needs_recompute(),
+                LimitEvent::New(_) => self.needs_recompute(),
+            },
+            DataEvent::File(event) => match event {
+                super::files::FileEvent::LoadFromPath { .. } => {}
+                super::files::FileEvent::ParseFromBytes { .. } => {}
+                super::files::FileEvent::FromData { .. } => {}
+                super::files::FileEvent::ToShow(_) => self.needs_recompute(),
+                super::files::FileEvent::Remove(_) => self.needs_recompute(),
+                super::files::FileEvent::MoveUp(_) => self.needs_recompute(),
+                super::files::FileEvent::MoveDown(_) => self.needs_recompute(),
+                super::files::FileEvent::Label(_) => self.needs_recompute(),
+                super::files::FileEvent::Reload(_) => self.needs_recompute(),
+                super::files::FileEvent::Cancel(_) => {}
+                super::files::FileEvent::LoadError { .. } => {}
+                super::files::FileEvent::ParseError { .. } => {}
+                super::files::FileEvent::Loaded { .. } => self.needs_recompute(),
+            },
+            DataEvent::Filtering => self.needs_recompute(),
+            DataEvent::Formulas => {}
+            DataEvent::Scenarios => self.needs_recompute(),
+            DataEvent::LimitRequest(_) => {}
+            DataEvent::FileRequest(_) => {}
+            DataEvent::SelectionRequest(_) => {}
+            DataEvent::SelectionEvent(_) => {}
+        }
+        Default::default()
+    }
+
+    fn progress(&mut self, _state: &mut super::AppState) {}
+}
+
+impl super::TabTrait for ScenarioTab {
+    fn title(&self, state: &super::AppState) -> &str {
+        LocalizableStr {
+            english: "Scenarios",
+        }
+        .localize(state.language)
+    }
+
+    fn show(&mut self, state: &mut super::AppState, ui: &mut egui::Ui) {
+        let language = state.language;
+        self.show_scenarios(state, ui);
+        ui.separator();
+        let comparison = match self.comparison.take() {
+            Some(comparison) => comparison,
+            None => Comparison::new(state),
+        };
+        let Comparison { scenarios, files } = &comparison;
+
+        egui::Grid::new("ScenarioComparison")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong(LocalizableStr { english: "File" }.localize(language));
+                ui.strong(LocalizableStr { english: "Rows" }.localize(language));
+                ui.strong(LocalizableStr { english: "Current" }.localize(language));
+                for name in scenarios {
+                    ui.strong(name);
+                    ui.strong("Δ");
+                }
+                ui.end_row();
+                let (total_rows, total_passing) = comparison.total();
+                let total = LocalizableStr { english: "Total" }.localize(language);
+                for (label, rows, passing) in files
+                    .iter()
+                    .map(|(label, rows, passing)| (label.as_str(), *rows, passing))
+                    .chain(std::iter::once((total, total_rows, &total_passing)))
+                {
+                    ui.label(label);
+                    ui.label(rows.to_string());
+                    let current = yield_percent(passing[0], rows);
+                    ui.label(format!("{current:.2}%"));
+                    for &passing in &passing[1..] {
+                        let scenario = yield_percent(passing, rows);
+                        ui.label(format!("{scenario:.2}%"));
+                        ui.label(format!("{:+.2}%", scenario - current));
+                    }
+                    ui.end_row();
+                }
+            });
+        ui.separator();
+
+        let current = LocalizableStr { english: "Current" }.localize(language);
+        let names = std::iter::once(current).chain(scenarios.iter().map(|s| s.as_str()));
+        let width = 0.8 / (scenarios.len() + 1) as f64;
+        let labels = files
+            .iter()
+            .map(|(label, _, _)| label.clone())
+            .collect::<Vec<_>>();
+        let charts = names
+            .enumerate()
+            .map(|(index, name)| {
+                let bars = files
+                    .iter()
+                    .enumerate()
+                    .map(|(file, (label, rows, passing))| {
+                        let x = file as f64 + (index as f64 - scenarios.len() as f64 / 2.) * width;
+                        egui::plot::Bar::new(x, yield_percent(passing[index], *rows))
+                            .width(width)
+                            .name(label)
+                    })
+                    .collect();
+                egui::plot::BarChart::new(bars)
+                    .name(name)
+                    .color(state.get_color(index))
+            })
+            .collect::<Vec<_>>();
+        egui::plot::Plot::new(ui.id().with("scenario-yields"))
+            .legend(egui::plot::Legend::default())
+            .include_y(0.)
+            .include_y(100.)
+            .x_axis_formatter(move |x, _| {
+                let index = x.round();
+                if (x - index).abs() < 1e-6 && index >= 0. && (index as usize) < labels.len() {
+                    labels[index as usize].clone()
+                } else {
+                    String::new()
+                }
+            })
+            .y_axis_formatter(|y, _| format!("{y}%"))
+            .show(ui, |plot_ui| {
+                for chart in charts {
+                    plot_ui.bar_chart(chart);
+                }
+            });
+        self.comparison = Some(comparison);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::{app_with_example, DataEvent};
+
+    #[test]
+    fn scenarios() {
+        let (mut app, key) = app_with_example("Example");
+        app.scenarios.save("open", &app.center).unwrap();
+        assert!(app.scenarios.save(" ", &app.center).is_err());
+
+        // tighten a limit, the scenario keeps the previous filtering
+        let limit_key = app
+            .center
+            .limits()
+            .find(|(_, limit)| limit.get_label().as_str() == "X")
+            .map(|(key, _)| key.clone())
+            .unwrap();
+        app.center.limit_mut(&limit_key).unwrap().change(0, 4);
+        app.data_events
+            .push(DataEvent::Limit(super::LimitEvent::Limit(limit_key)));
+        app.process_data_events();
+        let _ = app.center.wait();
+        let passing = |summed: &[u32]| summed.iter().filter(|&&n| n == 0).count();
+        let summed = app.center.filters_summed(&key).unwrap();
+        let mut filters = |name| app.scenarios.filters(name, &key, &app.center);
+        let open = filters("open").unwrap();
+        assert_eq!(passing(&open.summed), summed.len());
+        assert!(passing(summed) < passing(&open.summed));
+        assert!(filters("missing").is_none());
+        // the second time, the cached own filters are used
+        assert_eq!(filters("open"), Some(open));
+        assert_eq!(app.scenarios.own_filters.len(), 1);
+        app.data_events.push(DataEvent::Formulas);
+        app.process_data_events();
+        assert!(app.scenarios.own_filters.is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    data_types::{finite_f32::FiniteF32, LimitKey},
-    Language, LocalizableStr,
+    data_types::{finite_f32::FiniteF32, FileKey, LimitKey},
+    Language, LocalizableStr, LocalizableString,
 };
 
 use super::DataEvent;
@@ -80,31 +80,17 @@ impl super::TabTrait for SelectionTab {
         }) = &state.selected
         {
             let mut columns = std::collections::VecDeque::with_capacity(selected.len());
-            for (selection_index, egui_heatmap::CoordinatePoint { x: xx, y: yy }) in
-                selected.iter().enumerate()
-            {
+            for (selection_index, point) in selected.iter().enumerate() {
                 for (_, (file_label, data, limit_sorting)) in state.files.iter_loaded() {
-                    if let (Some(x_column), Some(y_column)) =
-                        (limit_sorting.get(x_key), limit_sorting.get(y_key))
-                    {
-                        let x_data = data.get_column(*x_column).as_int();
-                        let y_data = data.get_column(*y_column).as_int();
-                        if let (Some(x_data), Some(y_data)) = (x_data, y_data) {
-                            if let Some(index) = x_data
-                                .iter()
-                                .zip(y_data.iter())
-                                .position(|(x, y)| x == xx && y == yy)
-                            {
-                                let mut column = vec![None; state.limits.len()];
-                                for (row, (limit_key, _)) in state.limits.iter().enumerate() {
-                                    if let Some(column_index) = limit_sorting.get(limit_key) {
-                                        let data = data.get_column(*column_index);
-                                        column[row] = Some(data.get_as_float(index));
-                                    }
-                                }
-                                columns.push_back((file_label, selection_index, column));
+                    if let Some(index) = find_row(data, limit_sorting, x_key, y_key, point) {
+                        let mut column = vec![None; state.center.limits().count()];
+                        for (row, (limit_key, _)) in state.center.limits().enumerate() {
+                            if let Some(column_index) = limit_sorting.get(limit_key) {
+                                let data = data.get_column(*column_index);
+                                column[row] = Some(data.get_as_float(index));
                             }
                         }
+                        columns.push_back((file_label, selection_index, column));
                     }
                 }
             }
@@ -152,11 +138,13 @@ impl super::TabTrait for SelectionTab {
                             }
                         })
                         .body(|body| {
-                            body.rows(row_height, state.limits.len(), |row, mut ui| {
+                            body.rows(row_height, state.center.limits().count(), |row, mut ui| {
                                 // First column - limit label
                                 self.label(
                                     &mut ui,
-                                    if let Some(limit) = state.limits.element_at(row) {
+                                    if let Some(limit) =
+                                        state.center.limits().nth(row).map(|(_, limit)| limit)
+                                    {
                                         limit.get_label().as_str()
                                     } else {
                                         "This should never happen"
@@ -179,12 +167,12 @@ impl super::TabTrait for SelectionTab {
                             })
                         });
                 } else {
-                    let limits = &state.limits;
+                    let limits = &state.center;
                     let galleys = {
                         let mut labels = Vec::with_capacity(columns.len() + 1);
                         labels.push(LocalizableStr { english: "File" }.localize(state.language));
                         limits
-                            .iter()
+                            .limits()
                             .for_each(|(_, limit)| labels.push(limit.get_label().as_str()));
                         super::_helper::galleys(labels, ui, header_height)
                     };
@@ -251,3 +239,262 @@ impl super::TabTrait for SelectionTab {
         }
     }
 }
+
+/// Row of the die at a position, matched by the columns of the x- and y-limit
+pub(super) fn find_row(
+    data: &super::files::FileData,
+    limit_sorting: &std::collections::HashMap<LimitKey, usize>,
+    x_key: &LimitKey,
+    y_key: &LimitKey,
+    &egui_heatmap::CoordinatePoint { x: xx, y: yy }: &egui_heatmap::CoordinatePoint,
+) -> Option<usize> {
+    let x_data = data.get_column(*limit_sorting.get(x_key)?).as_int()?;
+    let y_data = data.get_column(*limit_sorting.get(y_key)?).as_int()?;
+    x_data
+        .iter()
+        .zip(y_data.iter())
+        .position(|(&x, &y)| x == xx && y == yy)
+}
+
+/// Value of a die for one limit
+struct Measurement {
+    label: String,
+    value: Option<f32>,
+    lower: Option<FiniteF32>,
+    upper: Option<FiniteF32>,
+    failed: bool,
+}
+
+/// All measurements of one die, with the verdict of each limit
+pub(super) struct DieInspection {
+    file: String,
+    point: egui_heatmap::CoordinatePoint,
+    /// Same order as the limits
+    measurements: Vec<Measurement>,
+}
+impl DieInspection {
+    pub(super) fn new(
+        state: &super::AppState,
+        file_key: &FileKey,
+        x_key: &LimitKey,
+        y_key: &LimitKey,
+        point: &egui_heatmap::CoordinatePoint,
+    ) -> Option<Self> {
+        let (label, data, limit_sorting) = state.files.get(file_key)?.get_loaded()?;
+        let row = find_row(data, limit_sorting, x_key, y_key, point)?;
+        let measurements = state
+            .center
+            .limits()
+            .map(|(limit_key, limit)| {
+                let (lower, upper) = limit.get_limits();
+                Measurement {
+                    label: limit.get_label().as_str().to_string(),
+                    value: limit_sorting
+                        .get(limit_key)
+                        .map(|column| data.get_column(*column).get_as_float(row)),
+                    lower,
+                    upper,
+                    failed: state
+                        .center
+                        .filter(file_key, limit_key)
+                        .map(|filter| filter.get(row))
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+        Some(Self {
+            file: label.as_str().to_string(),
+            point: point.clone(),
+            measurements,
+        })
+    }
+
+    /// File, position and the first failed limit, in the order of the limits
+    pub(super) fn show_summary(&self, ui: &mut egui::Ui, language: Language) {
+        let egui_heatmap::CoordinatePoint { x, y } = self.point;
+        ui.strong(format!("{}: {x}/{y}", self.file));
+        let failed = self.measurements.iter().filter(|m| m.failed).count();
+        let text = match self.measurements.iter().find(|m| m.failed) {
+            Some(first) => LocalizableString {
+                english: format!(
+                    "Fails {failed} of {} limits, first: {}",
+                    self.measurements.len(),
+                    first.label
+                ),
+            },
+            None => LocalizableString {
+                english: "Passes all limits".into(),
+            },
+        };
+        ui.label(text.localize(language));
+    }
+
+    /// Summary and a table of all limits
+    pub(super) fn show(&self, ui: &mut egui::Ui, language: Language) {
+        self.show_summary(ui, language);
+        let n_a = LocalizableStr { english: "n/a" }.localize(language);
+        let bound = |bound: Option<FiniteF32>| {
+            bound
+                .map(|bound| bound.inner().to_string())
+                .unwrap_or_default()
+        };
+        egui::Grid::new(("DieInspection", &self.file))
+            .striped(true)
+            .show(ui, |ui| {
+                for header in ["Limit", "Value", "Lower", "Upper", "Result"] {
+                    ui.strong(LocalizableStr { english: header }.localize(language));
+                }
+                ui.end_row();
+                for measurement in &self.measurements {
+                    ui.label(&measurement.label);
+                    match measurement.value {
+                        Some(value) => ui.label(value.to_string()),
+                        None => ui.label(n_a),
+                    };
+                    ui.label(bound(measurement.lower));
+                    ui.label(bound(measurement.upper));
+                    if measurement.failed {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            LocalizableStr { english: "Fail" }.localize(language),
+                        );
+                    } else if measurement.value.is_some() {
+                        ui.label(LocalizableStr { english: "Pass" }.localize(language));
+                    } else {
+                        ui.label("");
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_row, DieInspection};
+    use crate::app::{
+        app_with_example, files::FileEvent, limits::LimitEvent, App, AppState, DataEvent,
+    };
+    use crate::data_types::finite_f32::FiniteF32;
+    use egui_heatmap::CoordinatePoint;
+
+    /// Limit labels and whether the die fails them
+    fn verdicts(die: &DieInspection) -> Vec<(&str, bool)> {
+        die.measurements
+            .iter()
+            .map(|m| (m.label.as_str(), m.failed))
+            .collect()
+    }
+
+    fn first_failed(die: &DieInspection) -> Option<&str> {
+        die.measurements
+            .iter()
+            .find(|m| m.failed)
+            .map(|m| m.label.as_str())
+    }
+
+    #[test]
+    fn inspect_dies() {
+        let (mut app, full) = app_with_example("Full");
+        let partial = app.file_key_generator.next();
+        // without the column 'd2'
+        app.data_events.push(DataEvent::File(FileEvent::FromData {
+            key: partial.clone(),
+            label: "Partial".into(),
+            data: data_format::DataFormat::example_rectangle(10, 0, 0, vec![("d1".into(), 0., 1.)])
+                .unwrap(),
+        }));
+        app.process_data_events();
+        let key = |app: &App, label: &str| {
+            app.center
+                .limits()
+                .find(|(_, limit)| limit.get_label().as_str() == label)
+                .map(|(key, _)| key.clone())
+                .unwrap()
+        };
+        let (x_key, y_key, d1, d2) = (
+            key(&app, "X"),
+            key(&app, "Y"),
+            key(&app, "d1"),
+            key(&app, "d2"),
+        );
+        // d1 is i/9 and fails from row 5, d2 is 1 + i and fails up to row 2
+        for (limit_key, lower, upper) in [(&d1, None, Some(0.5)), (&d2, Some(3.5), None)] {
+            app.center
+                .limit_mut(limit_key)
+                .unwrap()
+                .set_limits(lower.map(FiniteF32::new), upper.map(FiniteF32::new));
+            app.data_events
+                .push(DataEvent::Limit(LimitEvent::Limit(limit_key.clone())));
+        }
+        app.process_data_events();
+        let _ = app.center.wait();
+
+        let point = |i: i32| CoordinatePoint { x: i, y: i };
+        let (_, data, limit_sorting) = app.files.get(&full).unwrap().get_loaded().unwrap();
+        assert_eq!(
+            find_row(data, limit_sorting, &x_key, &y_key, &point(3)),
+            Some(3)
+        );
+        assert_eq!(
+            find_row(
+                data,
+                limit_sorting,
+                &x_key,
+                &y_key,
+                &CoordinatePoint { x: 1, y: 2 }
+            ),
+            None
+        );
+        // the axes need integer columns
+        assert_eq!(find_row(data, limit_sorting, &d1, &y_key, &point(0)), None);
+
+        let mut app_events = Vec::new();
+        let state = AppState {
+            language: app.language,
+            app_events: &mut app_events,
+            limits: &mut app.limits,
+            files: &mut app.files,
+            data_events: &mut app.data_events,
+            file_key_generator: &mut app.file_key_generator,
+            center: &mut app.center,
+            locked_limits: &mut app.locked_limits,
+            selected: &mut app.selected,
+            requested_screenshot: &mut app.requested_screenshot,
+            history: &mut app.history,
+            formulas: &mut app.formulas,
+            scenarios: &mut app.scenarios,
+        };
+        let inspect =
+            |file_key, i| DieInspection::new(&state, file_key, &x_key, &y_key, &point(i)).unwrap();
+        let die = inspect(&full, 4);
+        assert_eq!(die.file, "Full");
+        assert_eq!(
+            verdicts(&die),
+            [("X", false), ("Y", false), ("d1", false), ("d2", false)]
+        );
+        assert_eq!(first_failed(&die), None);
+        assert_eq!(die.measurements[3].value, Some(5.));
+        assert_eq!(die.measurements[3].lower, Some(FiniteF32::new(3.5)));
+        let die = inspect(&full, 2);
+        assert_eq!(verdicts(&die)[2..], [("d1", false), ("d2", true)]);
+        assert_eq!(first_failed(&die), Some("d2"));
+        let die = inspect(&full, 7);
+        assert_eq!(verdicts(&die)[2..], [("d1", true), ("d2", false)]);
+        assert_eq!(first_failed(&die), Some("d1"));
+
+        // the missing column has no value and does not fail
+        let die = inspect(&partial, 8);
+        assert_eq!(die.measurements[3].label, "d2");
+        assert_eq!(die.measurements[3].value, None);
+        assert_eq!(first_failed(&die), Some("d1"));
+        assert!(DieInspection::new(
+            &state,
+            &partial,
+            &x_key,
+            &y_key,
+            &CoordinatePoint { x: 0, y: 9 }
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,515 @@
+use super::DataEvent;
+use crate::LocalizableStr;
+
+/// Percentiles listed besides the median
+const PERCENTILES: [f64; 6] = [1., 5., 25., 75., 95., 99.];
+
+/// Header of each column, percentiles follow 'Std dev'
+const HEADER: [&str; 10] = [
+    "File", "Limit", "Count", "Filtered", "Yield", "Min", "Max", "Mean", "Median", "Std dev",
+];
+const CAPABILITY: [&str; 2] = ["Cp", "Cpk"];
+
+/// Distribution of the finite values of a column
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub(super) struct Summary {
+    pub(super) count: usize,
+    pub(super) min: f64,
+    pub(super) max: f64,
+    pub(super) mean: f64,
+    pub(super) median: f64,
+    pub(super) std_dev: f64,
+    /// At [`PERCENTILES`]
+    pub(super) percentiles: [f64; PERCENTILES.len()],
+}
+impl Summary {
+    pub(super) fn new(values: impl Iterator<Item = f32>) -> Option<Self> {
+        let mut values = values
+            .filter(|v| v.is_finite())
+            .map(f64::from)
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+        Some(Self {
+            count,
+            min: values[0],
+            max: values[count - 1],
+            mean,
+            median: percentile(&values, 50.),
+            std_dev: variance.sqrt(),
+            percentiles: PERCENTILES.map(|p| percentile(&values, p)),
+        })
+    }
+
+    /// Process capability (Cp, Cpk), Cp needs both bounds, Cpk uses the nearer one
+    fn capability(&self, lower: Option<f64>, upper: Option<f64>) -> (Option<f64>, Option<f64>) {
+        if self.std_dev <= 0. {
+            return (None, None);
+        }
+        let sigma = self.std_dev;
+        let cp = lower
+            .zip(upper)
+            .map(|(lower, upper)| (upper - lower) / (6. * sigma));
+        let cpk = [
+            lower.map(|lower| (self.mean - lower) / (3. * sigma)),
+            upper.map(|upper| (upper - self.mean) / (3. * sigma)),
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(f64::min);
+        (cp, cpk)
+    }
+}
+
+/// Yield in percent, zero for an empty file
+pub(super) fn yield_percent(passing: usize, rows: usize) -> f64 {
+    if rows == 0 {
+        0.
+    } else {
+        100. * passing as f64 / rows as f64
+    }
+}
+
+/// Linear interpolation between the closest ranks, `sorted` is not empty
+pub(super) fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let position = percent / 100. * (sorted.len() - 1) as f64;
+    let (below, above) = (position.floor() as usize, position.ceil() as usize);
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+enum Cell {
+    Text(String),
+    Count(usize),
+    Percent(f64),
+    Number(Option<f64>),
+}
+impl Cell {
+    fn text(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Count(count) => count.to_string(),
+            Cell::Percent(percent) => format!("{percent:.2}%"),
+            Cell::Number(number) => number.map(|x| format!("{x:.4}")).unwrap_or("-".into()),
+        }
+    }
+
+    /// Value without rounding, for export
+    fn raw(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Count(count) => count.to_string(),
+            Cell::Percent(percent) => percent.to_string(),
+            Cell::Number(number) => number.map(|x| x.to_string()).unwrap_or_default(),
+        }
+    }
+
+    /// Raw value, quoted for a ';'-separated file
+    fn csv(&self) -> String {
+        super::_helper::csv_cell(&self.raw()).into_owned()
+    }
+
+    /// Missing numbers are last
+    fn compare(&self, other: &Cell) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (Cell::Text(a), Cell::Text(b)) => a.cmp(b),
+            (Cell::Count(a), Cell::Count(b)) => a.cmp(b),
+            (Cell::Percent(a), Cell::Percent(b)) => a.total_cmp(b),
+            (Cell::Number(a), Cell::Number(b)) => match (a, b) {
+                (Some(a), Some(b)) => a.total_cmp(b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// One limit in one file
+struct Row {
+    cells: Vec<Cell>,
+}
+impl Row {
+    fn new(
+        file: &str,
+        limit: &super::limits::Limit,
+        rows: usize,
+        filtered: usize,
+        summary: Option<Summary>,
+        scenario_filtered: Option<usize>,
+    ) -> Self {
+        let yield_ = yield_percent(rows - filtered, rows);
+        let (lower, upper) = limit.get_limits();
+        let value = |f: fn(&Summary) -> f64| Cell::Number(summary.as_ref().map(f));
+        let mut cells = vec![
+            Cell::Text(file.to_string()),
+            Cell::Text(limit.get_label().as_str().to_string()),
+            Cell::Count(rows),
+            Cell::Count(filtered),
+            Cell::Percent(yield_),
+            value(|s| s.min),
+            value(|s| s.max),
+            value(|s| s.mean),
+            value(|s| s.median),
+            value(|s| s.std_dev),
+        ];
+        for index in 0..PERCENTILES.len() {
+            cells.push(Cell::Number(summary.as_ref().map(|s| s.percentiles[index])));
+        }
+        let (cp, cpk) = summary
+            .as_ref()
+            .map(|s| {
+                s.capability(
+                    lower.map(|l| l.inner() as f64),
+                    upper.map(|u| u.inner() as f64),
+                )
+            })
+            .unwrap_or_default();
+        cells.push(Cell::Number(cp));
+        cells.push(Cell::Number(cpk));
+        if let Some(filtered) = scenario_filtered {
+            let scenario_yield = yield_percent(rows - filtered, rows);
+            cells.push(Cell::Count(filtered));
+            cells.push(Cell::Percent(scenario_yield));
+            cells.push(Cell::Percent(scenario_yield - yield_));
+        }
+        Self { cells }
+    }
+}
+
+/// The columns of a compared scenario follow the capability
+fn header(scenario: Option<&str>) -> Vec<String> {
+    HEADER
+        .iter()
+        .map(|h| h.to_string())
+        .chain(PERCENTILES.iter().map(|p| format!("P{p}")))
+        .chain(CAPABILITY.iter().map(|h| h.to_string()))
+        .chain(scenario.into_iter().flat_map(|name| {
+            [
+                format!("Filtered ({name})"),
+                format!("Yield ({name})"),
+                "Δ Yield".to_string(),
+            ]
+        }))
+        .collect()
+}
+
+/// Count, yield, distribution and capability of each limit in each shown file
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub(super) struct StatisticsTab {
+    /// If false, only rows passing all limits are used for the distribution
+    all_rows: bool,
+    /// Column and whether it is sorted descending
+    sorting: Option<(usize, bool)>,
+    /// Scenario, whose filtering is shown next to the current one
+    #[serde(default)]
+    scenario: Option<String>,
+    #[serde(skip)]
+    rows: Option<Vec<Row>>,
+}
+impl StatisticsTab {
+    fn needs_recompute(&mut self) {
+        self.rows = None;
+    }
+
+    fn recompute(&self, state: &mut super::AppState) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (file_key, (file_label, file, sorting)) in state.files.iter_loaded() {
+            let Some(filtering) = state.center.filters_summed(file_key) else {
+                continue;
+            };
+            if !state.center.is_shown(file_key) {
+                continue;
+            }
+            let scenario = self
+                .scenario
+                .as_ref()
+                .and_then(|name| state.scenarios.filters(name, file_key, state.center));
+            for (limit_key, limit) in state.center.limits() {
+                let Some(column) = sorting.get(limit_key) else {
+                    continue;
+                };
+                if limit.is_trivial() {
+                    continue;
+                }
+                let column = file.get_column(*column);
+                let values = column.iter_float().zip(filtering);
+                let summary = if self.all_rows {
+                    Summary::new(values.map(|(v, _)| v))
+                } else {
+                    Summary::new(values.filter(|(_, &n)| n == 0).map(|(v, _)| v))
+                };
+                let filtered = state
+                    .center
+                    .filter(file_key, limit_key)
+                    .map(|f| f.count_ones())
+                    .unwrap_or_default();
+                rows.push(Row::new(
+                    file_label.as_str(),
+                    limit,
+                    file.data_count(),
+                    filtered,
+                    summary,
+                    scenario.as_ref().map(|filters| {
+                        filters
+                            .counted
+                            .get(limit_key)
+                            .map(|f| f.count_ones())
+                            .unwrap_or_default()
+                    }),
+                ));
+            }
+        }
+        rows
+    }
+
+    fn sort(&mut self) {
+        if let (Some((column, descending)), Some(rows)) = (self.sorting, self.rows.as_mut()) {
+            if rows.first().is_some_and(|row| row.cells.len() <= column) {
+                return;
+            }
+            rows.sort_by(|a, b| {
+                let ordering = a.cells[column].compare(&b.cells[column]);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+    }
+
+    /// Table in the shown order, one line per row, `cell` gives the text of each cell and header
+    fn to_text(&self, separator: &str, cell: fn(&Cell) -> String) -> String {
+        let mut text = header(self.scenario.as_deref())
+            .into_iter()
+            .map(|h| cell(&Cell::Text(h)))
+            .collect::<Vec<_>>()
+            .join(separator)
+            + "\n";
+        for row in self.rows.iter().flatten() {
+            text += &row
+                .cells
+                .iter()
+                .map(cell)
+                .collect::<Vec<_>>()
+                .join(separator);
+            text += "\n";
+        }
+        text
+    }
+}
+
+impl super::DataEventNotifyable for StatisticsTab {
+    fn notify(&mut self, event: &DataEvent) -> Vec<DataEvent> {
+        match event {
+            DataEvent::Limit(limit) => match limit {
+                super::limits::LimitEvent::LockableLimit(_) => {}
+                super::limits::LimitEvent::Label(_) => self.needs_recompute(),
+                super::limits::LimitEvent::Limit(_) => self.needs_recompute(),
+                super::limits::LimitEvent::New(_) => self.needs_recompute(),
+            },
+            DataEvent::File(event) => match event {
+                super::files::FileEvent::LoadFromPath { .. } => {}
+                super::files::FileEvent::ParseFromBytes { .. } => {}
+                super::files::FileEvent::FromData { .. } => {}
+                super::files::FileEvent::ToShow(_) => self.needs_recompute(),
+                super::files::FileEvent::Remove(_) => self.needs_recompute(),
+                super::files::FileEvent::MoveUp(_) => self.needs_recompute(),
+                super::files::FileEvent::MoveDown(_) => self.needs_recompute(),
+                super::files::FileEvent::Label(_) => self.needs_recompute(),
+                super::files::FileEvent::Reload(_) => self.needs_recompute(),
+                super::files::FileEvent::Cancel(_) => {}
+                super::files::FileEvent::LoadError { .. } => {}
+                super::files::FileEvent::ParseError { .. } => {}
+                super::files::FileEvent::Loaded { .. } => self.needs_recompute(),
+            },
+            DataEvent::Filtering => self.needs_recompute(),
+            DataEvent::Formulas => {}
+            DataEvent::Scenarios => self.needs_recompute(),
+            DataEvent::LimitRequest(_) => {}
+            DataEvent::FileRequest(_) => {}
+            DataEvent::SelectionRequest(_) => {}
+            DataEvent::SelectionEvent(_) => {}
+        }
+        Default::default()
+    }
+
+    fn progress(&mut self, _state: &mut super::AppState) {}
+}
+
+impl super::TabTrait for StatisticsTab {
+    fn title(&self, state: &super::AppState) -> &str {
+        LocalizableStr {
+            english: "Statistics",
+        }
+        .localize(state.language)
+    }
+
+    fn show(&mut self, state: &mut super::AppState, ui: &mut egui::Ui) {
+        let language = state.language;
+        if let Some(name) = &self.scenario {
+            if !state.scenarios.names().any(|n| n == name) {
+                self.scenario = None;
+                self.needs_recompute();
+            }
+        }
+        if self.rows.is_none() {
+            self.rows = Some(self.recompute(state));
+            self.sort();
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(
+                    &mut self.all_rows,
+                    LocalizableStr {
+                        english: "Include rows failing any limit",
+                    }
+                    .localize(language),
+                )
+                .changed()
+            {
+                self.needs_recompute();
+            }
+            let previous = self.scenario.clone();
+            let current = LocalizableStr {
+                english: "Current limits only",
+            }
+            .localize(language);
+            egui::ComboBox::from_id_source("StatisticsScenario")
+                .selected_text(self.scenario.as_deref().unwrap_or(current))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.scenario, None, current);
+                    for name in state.scenarios.names() {
+                        ui.selectable_value(&mut self.scenario, Some(name.to_string()), name);
+                    }
+                })
+                .response
+                .on_hover_text(
+                    LocalizableStr {
+                        english: "Scenario, whose filtering and yield are shown next to the current ones",
+                    }
+                    .localize(language),
+                );
+            if previous != self.scenario {
+                self.needs_recompute();
+            }
+            if ui
+                .button(
+                    LocalizableStr {
+                        english: "Copy to clipboard",
+                    }
+                    .localize(language),
+                )
+                .clicked()
+            {
+                let text = self.to_text("\t", Cell::text);
+                ui.output_mut(|o| o.copied_text = text);
+            }
+            if ui
+                .button(
+                    LocalizableStr {
+                        english: "Export CSV …",
+                    }
+                    .localize(language),
+                )
+                .clicked()
+            {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name("statistics.csv")
+                    .save_file()
+                {
+                    if let Err(e) = std::fs::write(&path, self.to_text(";", Cell::csv)) {
+                        let message = format!("Failed to write '{}': {e}", path.display());
+                        state
+                            .app_events
+                            .push(super::AppEvent::Dialog(crate::dialog::Dialog::new(
+                                LocalizableStr {
+                                    english: "Export failed",
+                                }
+                                .localize(language)
+                                .to_string(),
+                                Box::new(move |ui| {
+                                    ui.label(&message);
+                                    false
+                                }),
+                                crate::dialog::DialogKind::ok(),
+                            )));
+                    }
+                }
+            }
+        });
+        ui.separator();
+        let header = header(self.scenario.as_deref());
+        let mut sort_by = None;
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            egui_extras::TableBuilder::new(ui)
+                .striped(true)
+                .columns(egui_extras::Column::auto().resizable(true), header.len())
+                .header(20., |mut row| {
+                    for (index, title) in header.iter().enumerate() {
+                        row.col(|ui| {
+                            let arrow = match self.sorting {
+                                Some((column, false)) if column == index => " ⏶",
+                                Some((column, true)) if column == index => " ⏷",
+                                _ => "",
+                            };
+                            let title = LocalizableStr { english: title }.localize(language);
+                            if ui.button(format!("{title}{arrow}")).clicked() {
+                                sort_by = Some(index);
+                            }
+                        });
+                    }
+                })
+                .body(|body| {
+                    let rows = self.rows.as_deref().unwrap_or_default();
+                    body.rows(18., rows.len(), |index, mut row| {
+                        for cell in &rows[index].cells {
+                            row.col(|ui| {
+                                ui.label(cell.text());
+                            });
+                        }
+                    });
+                });
+        });
+        if let Some(index) = sort_by {
+            self.sorting = match self.sorting {
+                Some((column, descending)) if column == index => Some((column, !descending)),
+                _ => Some((index, false)),
+            };
+            self.sort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cell, Summary};
+
+    #[test]
+    fn summary() {
+        let summary = Summary::new([4., 1., f32::NAN, 3., 2., 5.].into_iter()).unwrap();
+        assert_eq!(summary.count, 5);
+        assert_eq!((summary.min, summary.max, summary.mean), (1., 5., 3.));
+        assert_eq!(summary.median, 3.);
+        assert_eq!(summary.percentiles[2], 2.);
+        assert!((summary.std_dev - 2f64.sqrt()).abs() < 1e-12);
+        let (cp, cpk) = summary.capability(Some(0.), Some(12.));
+        assert!((cp.unwrap() - 12. / (6. * 2f64.sqrt())).abs() < 1e-12);
+        assert!((cpk.unwrap() - 3. / (3. * 2f64.sqrt())).abs() < 1e-12);
+        assert_eq!(summary.capability(None, Some(12.)).0, None);
+        assert_eq!(Summary::new(std::iter::empty()), None);
+        assert_eq!(
+            Cell::Text("Vth; \"after\"".into()).csv(),
+            "\"Vth; \"\"after\"\"\""
+        );
+        assert_eq!(Cell::Number(Some(0.5)).csv(), "0.5");
+    }
+}
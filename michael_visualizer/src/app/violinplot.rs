@@ -12,6 +12,9 @@ pub struct ViolinTab {
     resolution: usize,
     #[serde(skip)]
     state: State,
+    /// Scenario, whose filtering is plotted instead of the current one
+    #[serde(default)]
+    scenario: Option<String>,
 }
 
 impl Default for ViolinTab {
@@ -21,6 +24,7 @@ impl Default for ViolinTab {
             state: Default::default(),
             to_show: Default::default(),
             to_color: Default::default(),
+            scenario: None,
         }
     }
 }
@@ -80,34 +84,12 @@ enum Normalization {
 impl ViolinPlot {
     fn show(&mut self, ui: &mut egui::Ui, state: &mut super::AppState) -> Vec<DataEvent> {
         let language = state.language;
-        let background = egui::Color32::WHITE;
-        let fontsize = 16.;
-        let axis_color = egui::Color32::BLACK;
-        let fontid = egui::FontId::proportional(fontsize);
-        let y_steps_count = 5;
-        let boundary_color = egui::Color32::BLACK;
-        let boundary_thickness = 1.0;
-        let normalization = Normalization::SameForAllFiles;
-        let legend_margin = 3.;
-        let legend_box_width = 10.;
-
         let (response, painter) = ui.allocate_painter(
             ui.available_size_before_wrap(),
             egui::Sense::click_and_drag(),
         );
         let rect = response.rect.shrink(5.);
-        /*let mut rect = response.rect;
-        let boundary = 5.;
-        if rect.height() > 4. * boundary {
-            *rect.top_mut() += boundary;
-            *rect.bottom_mut() -= boundary;
-        }
-        if rect.width() > 4. * boundary {
-            *rect.left_mut() += boundary;
-            *rect.right_mut() -= boundary;
-        }*/
         let original_size = rect.size();
-
         let mouse = if let Some(mouse) = response.hover_pos() {
             let mouse = mouse - rect.left_top();
             if mouse.x >= 0.
@@ -122,6 +104,156 @@ impl ViolinPlot {
         } else {
             egui::vec2(-1., -1.)
         };
+        let mouse_above_limit_label = self.paint(&painter, rect, mouse);
+
+        let mut new = None;
+        let previous = self.context_pos;
+        response.context_menu(|ui| {
+            // clipboard
+            {
+                if ui
+                    .button(
+                        LocalizableStr {
+                            english: "Clipboard",
+                        }
+                        .localize(language),
+                    )
+                    .clicked()
+                {
+                    state.request_screenshot(rect);
+                    ui.close_menu();
+                }
+            }
+            // change label
+            {
+                new = Some(mouse);
+                let id = egui::Id::new("LimitLabelChangeDialogViolinPlot");
+                let mouse_above_limit_label = if let Some((_, mouse_above_limit_label)) = &previous
+                {
+                    *mouse_above_limit_label
+                } else {
+                    ui.data_mut(|x| {
+                        x.remove::<String>(id);
+                    });
+                    mouse_above_limit_label
+                };
+                if mouse_above_limit_label
+                    && ui
+                        .button(
+                            LocalizableStr {
+                                english: "Change label",
+                            }
+                            .localize(language),
+                        )
+                        .clicked()
+                {
+                    let label = self.limit_label.as_str().to_string();
+                    let label1 = label.clone();
+                    let s1 = self.limit_label_change_sender.clone();
+                    let s2 = self.limit_label_change_sender.clone();
+                    state
+                        .app_events
+                        .push(crate::app::AppEvent::Dialog(Dialog::new(
+                            LocalizableString {
+                                english: "Limit label".into(),
+                            }
+                            .localize(language),
+                            Box::new(move |ui| {
+                                ui.heading(
+                                    LocalizableStr {
+                                        english: "Change limit label",
+                                    }
+                                    .localize(language),
+                                );
+                                ui.vertical(|ui| {
+                                    let label = label.clone();
+                                    let label1 = label1.clone();
+                                    ui.horizontal(|ui| {
+                                        ui.label(
+                                            LocalizableStr {
+                                                english: "Current: ",
+                                            }
+                                            .localize(language),
+                                        );
+                                        ui.label(&label);
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label(
+                                            LocalizableStr { english: "New: " }.localize(language),
+                                        );
+                                        let label_before = ui.data_mut(|x| {
+                                            x.get_temp_mut_or_insert_with::<String>(id, move || {
+                                                label1
+                                            })
+                                            .clone()
+                                        });
+                                        let mut label = label_before.clone();
+                                        ui.text_edit_singleline(&mut label);
+                                        if label != label_before {
+                                            ui.data_mut(|x| {
+                                                let t = x.get_temp_mut_or_insert_with::<String>(
+                                                    id,
+                                                    || label.clone(),
+                                                );
+                                                *t = label.clone();
+                                            });
+                                            let _ = s2.send(LimitLabelChange::Change(label));
+                                        }
+                                    });
+                                });
+                                false
+                            }),
+                            crate::dialog::DialogKind::Button {
+                                buttons: vec![
+                                    crate::dialog::Button {
+                                        label: LocalizableString {
+                                            english: "Cancel".into(),
+                                        }
+                                        .localize(language),
+                                        action: Box::new(|| true),
+                                    },
+                                    crate::dialog::Button {
+                                        label: LocalizableString {
+                                            english: "Ok".into(),
+                                        }
+                                        .localize(language),
+                                        action: Box::new(move || {
+                                            let _ = s1.send(LimitLabelChange::Ok);
+                                            true
+                                        }),
+                                    },
+                                ],
+                                has_exit: Some(0),
+                            },
+                        )));
+                    ui.close_menu();
+                }
+            }
+        });
+        if new.is_none() {
+            self.context_pos = None;
+        } else if self.context_pos.is_none() {
+            self.context_pos = new.map(|n| (n, mouse_above_limit_label));
+        }
+
+        Default::default()
+    }
+
+    /// Draw the plot into the rectangle, returns true if the mouse is above the limit label
+    /// Mouse is relative to the rectangle, negative if it is outside
+    fn paint(&mut self, painter: &egui::Painter, rect: egui::Rect, mouse: egui::Vec2) -> bool {
+        let background = egui::Color32::WHITE;
+        let fontsize = 16.;
+        let axis_color = egui::Color32::BLACK;
+        let fontid = egui::FontId::proportional(fontsize);
+        let y_steps_count = 5;
+        let boundary_color = egui::Color32::BLACK;
+        let boundary_thickness = 1.0;
+        let normalization = Normalization::SameForAllFiles;
+        let legend_margin = 3.;
+        let legend_box_width = 10.;
+
+        let original_size = rect.size();
         let mut mouse_above_limit_label = false;
 
         let to_outer_screen = egui::emath::RectTransform::from_to(
@@ -332,147 +464,29 @@ impl ViolinPlot {
         };
         let n = self.entries.len();
         for (index, d) in self.entries.iter().enumerate() {
-            painter.extend(d.to_shapes(
-                state,
-                to_inner_screen,
-                index,
-                n,
-                normalization,
-                &self.colors,
-            ));
+            painter.extend(d.to_shapes(to_inner_screen, index, n, normalization, &self.colors));
         }
+        mouse_above_limit_label
+    }
+}
 
-        let mut new = None;
-        let previous = self.context_pos;
-        response.context_menu(|ui| {
-            // clipboard
-            {
-                if ui
-                    .button(
-                        LocalizableStr {
-                            english: "Clipboard",
-                        }
-                        .localize(language),
-                    )
-                    .clicked()
-                {
-                    state.request_screenshot(rect);
-                    ui.close_menu();
-                }
-            }
-            // change label
-            {
-                new = Some(mouse);
-                let id = egui::Id::new("LimitLabelChangeDialogViolinPlot");
-                let mouse_above_limit_label = if let Some((_, mouse_above_limit_label)) = &previous
-                {
-                    *mouse_above_limit_label
-                } else {
-                    ui.data_mut(|x| {
-                        x.remove::<String>(id);
-                    });
-                    mouse_above_limit_label
-                };
-                if mouse_above_limit_label
-                    && ui
-                        .button(
-                            LocalizableStr {
-                                english: "Change label",
-                            }
-                            .localize(language),
-                        )
-                        .clicked()
-                {
-                    let label = self.limit_label.as_str().to_string();
-                    let label1 = label.clone();
-                    let s1 = self.limit_label_change_sender.clone();
-                    let s2 = self.limit_label_change_sender.clone();
-                    state
-                        .app_events
-                        .push(crate::app::AppEvent::Dialog(Dialog::new(
-                            LocalizableString {
-                                english: "Limit label".into(),
-                            }
-                            .localize(language),
-                            Box::new(move |ui| {
-                                ui.heading(
-                                    LocalizableStr {
-                                        english: "Change limit label",
-                                    }
-                                    .localize(language),
-                                );
-                                ui.vertical(|ui| {
-                                    let label = label.clone();
-                                    let label1 = label1.clone();
-                                    ui.horizontal(|ui| {
-                                        ui.label(
-                                            LocalizableStr {
-                                                english: "Current: ",
-                                            }
-                                            .localize(language),
-                                        );
-                                        ui.label(&label);
-                                    });
-                                    ui.horizontal(|ui| {
-                                        ui.label(
-                                            LocalizableStr { english: "New: " }.localize(language),
-                                        );
-                                        let label_before = ui.data_mut(|x| {
-                                            x.get_temp_mut_or_insert_with::<String>(id, move || {
-                                                label1
-                                            })
-                                            .clone()
-                                        });
-                                        let mut label = label_before.clone();
-                                        ui.text_edit_singleline(&mut label);
-                                        if label != label_before {
-                                            ui.data_mut(|x| {
-                                                let t = x.get_temp_mut_or_insert_with::<String>(
-                                                    id,
-                                                    || label.clone(),
-                                                );
-                                                *t = label.clone();
-                                            });
-                                            let _ = s2.send(LimitLabelChange::Change(label));
-                                        }
-                                    });
-                                });
-                                false
-                            }),
-                            crate::dialog::DialogKind::Button {
-                                buttons: vec![
-                                    crate::dialog::Button {
-                                        label: LocalizableString {
-                                            english: "Cancel".into(),
-                                        }
-                                        .localize(language),
-                                        action: Box::new(|| true),
-                                    },
-                                    crate::dialog::Button {
-                                        label: LocalizableString {
-                                            english: "Ok".into(),
-                                        }
-                                        .localize(language),
-                                        action: Box::new(move || {
-                                            let _ = s1.send(LimitLabelChange::Ok);
-                                            true
-                                        }),
-                                    },
-                                ],
-                                has_exit: Some(0),
-                            },
-                        )));
-                    ui.close_menu();
-                }
-            }
-        });
-        if new.is_none() {
-            self.context_pos = None;
-        } else if self.context_pos.is_none() {
-            self.context_pos = new.map(|n| (n, mouse_above_limit_label));
+/// Draw the violin plot of a limit without a GUI, e.g. for reports
+pub(super) fn paint(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    limit_key: &crate::data_types::LimitKey,
+    limit: &super::limits::Limit,
+    to_color_key: Option<&crate::data_types::LimitKey>,
+    files: &[super::PlotFile],
+    resolution: usize,
+) -> Result<(), LocalizableString> {
+    match State::plot(limit_key, limit, to_color_key, files, resolution) {
+        State::Plot(mut plot) => {
+            plot.paint(painter, rect, egui::vec2(-1., -1.));
+            Ok(())
         }
-
-        Default::default()
+        State::Error(msg) => Err(msg),
+        State::NeedsRecompute | State::NoLimitSelected => unreachable!("Limit is given"),
     }
 }
 
@@ -617,15 +631,21 @@ impl State {
                 DataEvent::File(event) => match event {
                     FileEvent::LoadFromPath { .. } => unaffected,
                     FileEvent::ParseFromBytes { .. } => unaffected,
+                    FileEvent::FromData { .. } => unaffected,
                     FileEvent::ToShow(_) => affected,
                     FileEvent::Remove(key) => condition(entries.iter().any(|x| &x.key == key)),
                     FileEvent::MoveUp(_) => affected,
                     FileEvent::MoveDown(_) => affected,
                     FileEvent::Label(key) => condition(entries.iter().any(|x| &x.key == key)),
+                    FileEvent::Reload(key) => condition(entries.iter().any(|x| &x.key == key)),
                     FileEvent::LoadError { .. } => unaffected,
+                    FileEvent::ParseError { .. } => unaffected,
+                    FileEvent::Cancel(_) => unaffected,
                     FileEvent::Loaded { .. } => affected,
                 },
                 DataEvent::Filtering => affected,
+                DataEvent::Formulas => unaffected,
+                DataEvent::Scenarios => affected,
                 DataEvent::LimitRequest(_) => unaffected,
                 DataEvent::FileRequest(_) => unaffected,
                 DataEvent::SelectionRequest(_) => unaffected,
@@ -637,118 +657,30 @@ impl State {
 }
 
 impl ViolinTab {
-    fn recompute(&mut self, state: &super::AppState) -> State {
+    fn recompute(&mut self, state: &mut super::AppState) -> State {
         if let Some(limit_key) = self.to_show.get(state.locked_limits).1 {
-            if let Some(limit) = state.limits.get(limit_key) {
-                let super::limits::LimitData {
-                    label: limit_label,
-                    lower,
-                    upper,
-                    info: _,
-                    data_kind: _,
-                } = limit.data();
-                let min = lower.unwrap_or(FiniteF32::new(f32::MIN));
-                let max = upper.unwrap_or(FiniteF32::new(f32::MAX));
-                let mut entries = Vec::new();
+            // rows and range as with the limits of the scenario
+            let filterings = self
+                .scenario
+                .as_deref()
+                .map(|name| state.scenarios.summed(name, state.center));
+            let limit = match self.scenario.as_deref() {
+                Some(name) => state.scenarios.limit(name, limit_key, state.center),
+                None => state.center.limit(limit_key),
+            };
+            if let Some(limit) = limit {
                 let to_color_key = self.to_color.as_ref().and_then(|k| {
                     k.get(state.locked_limits)
                         .1
                         .filter(|&to_color_key| to_color_key != limit_key)
                 });
-                for file_key in state.get_files_for_limit(limit_key) {
-                    let filtering = state.total_filterings.get(file_key);
-                    let file = state.files.get(file_key).and_then(|x| x.get_loaded());
-                    if let (Some((label, file, sorting)), Some(filtering)) = (file, filtering) {
-                        if let Some(column) = sorting.get(limit_key) {
-                            let data = file.get_column(*column);
-                            assert_eq!(data.len(), filtering.len());
-                            let data = data.filter(filtering, min, max);
-                            let to_color = to_color_key
-                                .and_then(|k| sorting.get(k))
-                                .and_then(|column| file.get_column(*column).as_int());
-                            if data.is_empty() {
-                                continue;
-                            }
-                            let data = if let Some(to_color) = to_color {
-                                let min = if let Some(min) = to_color.iter().min() {
-                                    min
-                                } else {
-                                    continue;
-                                };
-                                let max = if let Some(max) = to_color.iter().max() {
-                                    max
-                                } else {
-                                    continue;
-                                };
-                                let mut colors = Vec::new();
-                                for i in *min..(*max + 1) {
-                                    let d = data
-                                        .iter()
-                                        .zip(to_color.iter())
-                                        .filter(|(_, &c)| c == i)
-                                        .map(|(d, _)| *d)
-                                        .collect();
-                                    colors.push((Some(i), d));
-                                }
-                                colors
-                            } else {
-                                vec![(None, data)]
-                            };
-                            entries.push((file_key.clone(), label.clone(), data));
-                        }
+                let files = match &filterings {
+                    Some(filterings) => {
+                        super::scenarios::with_filterings(state.plot_files(), filterings)
                     }
-                }
-                if entries.is_empty() {
-                    return State::Error(LocalizableString {
-                        english: "No data after filtering".into(),
-                    });
-                }
-                let min: FiniteF32 = lower.unwrap_or_else(|| {
-                    entries
-                        .iter()
-                        .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().min()).min())
-                        .min()
-                        .cloned()
-                        .unwrap_or(min)
-                });
-                let max: FiniteF32 = upper.unwrap_or_else(|| {
-                    entries
-                        .iter()
-                        .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().max()).max())
-                        .max()
-                        .cloned()
-                        .unwrap_or(max)
-                });
-                let entries = entries
-                    .into_iter()
-                    .filter_map(|(key, label, data)| {
-                        ColoredViolinEntry::new(key, label, data, self.resolution, min, max)
-                    })
-                    .collect::<Vec<_>>();
-                let mut colors = entries
-                    .iter()
-                    .flat_map(|e| e.entries.iter().flat_map(|x| x.0))
-                    .collect::<Vec<_>>();
-                colors.sort();
-                let colors = colors
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, c)| (c, state.get_color(i)))
-                    .collect();
-                let (s, r) = std::sync::mpsc::channel();
-                State::Plot(ViolinPlot {
-                    limit_key: limit_key.clone(),
-                    limit_label,
-                    min,
-                    max,
-                    entries,
-                    context_pos: Default::default(),
-                    limit_label_change_sender: s,
-                    limit_label_change_receiver: r,
-                    limit_label_change_value: Default::default(),
-                    colors,
-                    legend_left_top: Default::default(),
-                })
+                    None => state.plot_files(),
+                };
+                State::plot(limit_key, limit, to_color_key, &files, self.resolution)
             } else {
                 State::Error(LocalizableString {
                     english: "No limits available".into(),
@@ -760,6 +692,125 @@ impl ViolinTab {
     }
 }
 
+impl State {
+    fn plot(
+        limit_key: &crate::data_types::LimitKey,
+        limit: &super::limits::Limit,
+        to_color_key: Option<&crate::data_types::LimitKey>,
+        files: &[super::PlotFile],
+        resolution: usize,
+    ) -> Self {
+        let super::limits::LimitData {
+            label: limit_label,
+            lower,
+            upper,
+            info: _,
+            unit: _,
+            data_kind: _,
+        } = limit.data();
+        let min = lower.unwrap_or(FiniteF32::new(f32::MIN));
+        let max = upper.unwrap_or(FiniteF32::new(f32::MAX));
+        let mut entries = Vec::new();
+        for super::PlotFile {
+            key: file_key,
+            label,
+            data: file,
+            sorting,
+            filtering,
+        } in files
+        {
+            if let Some(column) = sorting.get(limit_key) {
+                let data = file.get_column(*column);
+                assert_eq!(data.len(), filtering.len());
+                let data = data.filter(filtering, min, max);
+                let to_color = to_color_key
+                    .and_then(|k| sorting.get(k))
+                    .and_then(|column| file.get_column(*column).as_int());
+                if data.is_empty() {
+                    continue;
+                }
+                let data = if let Some(to_color) = to_color {
+                    let min = if let Some(min) = to_color.iter().min() {
+                        min
+                    } else {
+                        continue;
+                    };
+                    let max = if let Some(max) = to_color.iter().max() {
+                        max
+                    } else {
+                        continue;
+                    };
+                    let mut colors = Vec::new();
+                    for i in *min..(*max + 1) {
+                        let d = data
+                            .iter()
+                            .zip(to_color.iter())
+                            .filter(|(_, &c)| c == i)
+                            .map(|(d, _)| *d)
+                            .collect();
+                        colors.push((Some(i), d));
+                    }
+                    colors
+                } else {
+                    vec![(None, data)]
+                };
+                entries.push(((*file_key).clone(), (*label).clone(), data));
+            }
+        }
+        if entries.is_empty() {
+            return Self::Error(LocalizableString {
+                english: "No data after filtering".into(),
+            });
+        }
+        let min: FiniteF32 = lower.unwrap_or_else(|| {
+            entries
+                .iter()
+                .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().min()).min())
+                .min()
+                .cloned()
+                .unwrap_or(min)
+        });
+        let max: FiniteF32 = upper.unwrap_or_else(|| {
+            entries
+                .iter()
+                .flat_map(|(_, _, e)| e.iter().flat_map(|(_, x)| x.iter().max()).max())
+                .max()
+                .cloned()
+                .unwrap_or(max)
+        });
+        let entries = entries
+            .into_iter()
+            .filter_map(|(key, label, data)| {
+                ColoredViolinEntry::new(key, label, data, resolution, min, max)
+            })
+            .collect::<Vec<_>>();
+        let mut colors = entries
+            .iter()
+            .flat_map(|e| e.entries.iter().flat_map(|x| x.0))
+            .collect::<Vec<_>>();
+        colors.sort();
+        let colors = colors
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| (c, super::distinguishable_color(i)))
+            .collect();
+        let (s, r) = std::sync::mpsc::channel();
+        Self::Plot(ViolinPlot {
+            limit_key: limit_key.clone(),
+            limit_label,
+            min,
+            max,
+            entries,
+            context_pos: Default::default(),
+            limit_label_change_sender: s,
+            limit_label_change_receiver: r,
+            limit_label_change_value: Default::default(),
+            colors,
+            legend_left_top: Default::default(),
+        })
+    }
+}
+
 struct ColoredViolinEntry {
     key: crate::data_types::FileKey,
     label: crate::data_types::FileLabel,
@@ -786,6 +837,21 @@ impl super::TabTrait for ViolinTab {
             if state.ui_coloring_limit(ui, &mut self.to_color) {
                 self.state = State::NeedsRecompute;
             }
+            if state.scenarios.ui_select(
+                ui,
+                "ViolinScenario",
+                LocalizableStr {
+                    english: "Current limits",
+                }
+                .localize(state.language),
+                LocalizableStr {
+                    english: "Limits whose filtering is plotted",
+                }
+                .localize(state.language),
+                &mut self.scenario,
+            ) {
+                self.state = State::NeedsRecompute;
+            }
         });
 
         if let &State::NeedsRecompute = &self.state {
@@ -859,7 +925,6 @@ impl ColoredViolinEntry {
 
     fn to_shapes(
         &self,
-        state: &super::AppState,
         to_inner_screen: egui::emath::RectTransform,
         coloring_index: usize,
         entries_count: usize,
@@ -871,7 +936,7 @@ impl ColoredViolinEntry {
             return Default::default();
         }
         if colors.is_empty() {
-            let color = state.get_color(coloring_index);
+            let color = super::distinguishable_color(coloring_index);
             entries.first().unwrap().1.to_shapes(
                 color,
                 to_inner_screen,
@@ -10,6 +10,14 @@ pub use localization::{Language, LocalizableStr, LocalizableString};
 use eframe::egui;
 
 fn main() -> Result<(), eframe::Error> {
+    // commands run without a window, any other arguments start the GUI
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("report") => std::process::exit(app::report(&args[1..])),
+        Some("render") => std::process::exit(app::render(&args[1..])),
+        _ => {}
+    }
+
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(1000.0, 800.0)),
         ..Default::default()
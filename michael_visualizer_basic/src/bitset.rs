@@ -0,0 +1,116 @@
+const BITS: usize = u64::BITS as usize;
+
+/// One bit per row, e.g. true if the row is outside of a limit
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bitset {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitset {
+    /// All bits are false
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(BITS)],
+            len,
+        }
+    }
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize) -> bool) -> Self {
+        let words = (0..len.div_ceil(BITS))
+            .map(|word| {
+                let start = word * BITS;
+                (start..len.min(start + BITS))
+                    .enumerate()
+                    .fold(0, |bits, (bit, row)| bits | (u64::from(f(row)) << bit))
+            })
+            .collect();
+        Self { words, len }
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len, "Index {index} is out of range");
+        self.words[index / BITS] & (1 << (index % BITS)) != 0
+    }
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "Index {index} is out of range");
+        let mask = 1 << (index % BITS);
+        if value {
+            self.words[index / BITS] |= mask;
+        } else {
+            self.words[index / BITS] &= !mask;
+        }
+    }
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|index| self.get(index))
+    }
+    /// Indices of the true bits, in increasing order
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        set_bits(self.words.iter().copied())
+    }
+    /// Indices, which are true in exactly one of both, together with the value in `self`
+    /// Words which agree are skipped, so a small change is cheap, even for long sets
+    pub(crate) fn changes<'a>(
+        &'a self,
+        other: &'a Bitset,
+    ) -> impl Iterator<Item = (usize, bool)> + 'a {
+        assert_eq!(self.len, other.len, "Bitsets of different length");
+        set_bits(self.words.iter().zip(&other.words).map(|(a, b)| a ^ b))
+            .map(|index| (index, self.get(index)))
+    }
+}
+
+/// Indices of the set bits of consecutive words
+fn set_bits(words: impl Iterator<Item = u64>) -> impl Iterator<Item = usize> {
+    words.enumerate().flat_map(|(word, mut bits)| {
+        std::iter::from_fn(move || {
+            (bits != 0).then(|| {
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                word * BITS + bit
+            })
+        })
+    })
+}
+
+/// True if true in both
+impl std::ops::BitAndAssign<&Bitset> for Bitset {
+    fn bitand_assign(&mut self, rhs: &Bitset) {
+        assert_eq!(self.len, rhs.len, "Bitsets of different length");
+        for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+            *a &= b;
+        }
+    }
+}
+/// True if true in any
+impl std::ops::BitOrAssign<&Bitset> for Bitset {
+    fn bitor_assign(&mut self, rhs: &Bitset) {
+        assert_eq!(self.len, rhs.len, "Bitsets of different length");
+        for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+            *a |= b;
+        }
+    }
+}
+
+impl FromIterator<bool> for Bitset {
+    fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
+        let mut set = Self::default();
+        for value in iter {
+            if set.len % BITS == 0 {
+                set.words.push(0);
+            }
+            if value {
+                *set.words.last_mut().expect("Word was added") |= 1 << (set.len % BITS);
+            }
+            set.len += 1;
+        }
+        set
+    }
+}
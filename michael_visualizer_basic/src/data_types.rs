@@ -1,6 +1,14 @@
 pub use std::hash::Hash;
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct FileLabel(String);
+impl FileLabel {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+    pub fn get_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+}
 impl From<String> for FileLabel {
     fn from(value: String) -> Self {
         Self(value)
@@ -70,8 +78,9 @@ impl<Key: Eq + Hash, Value> OrderedMap<Key, Value> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Key, &mut Value)> {
         self.data.iter_mut()
     }
-    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
-        self.data.iter()
+    /// In insertion order, as changed by swap
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> + Clone {
+        self.keys.iter().map(|k| (k, &self.data[k]))
     }
 
     pub fn get(&self, key: &Key) -> Option<&Value>
@@ -13,6 +13,20 @@ pub struct ShowHideEvent<Key> {
     pub(crate) hidden_or_shown: HiddenOrShown,
     pub(crate) single_or_all: Option<Key>,
 }
+impl<Key> ShowHideEvent<Key> {
+    pub fn single(key: Key, hidden_or_shown: HiddenOrShown) -> Self {
+        Self {
+            hidden_or_shown,
+            single_or_all: Some(key),
+        }
+    }
+    pub fn all(hidden_or_shown: HiddenOrShown) -> Self {
+        Self {
+            hidden_or_shown,
+            single_or_all: None,
+        }
+    }
+}
 
 pub enum DataEvent<FileKey, LimitKey, File, Limit> {
     File(FileEvent<FileKey, File>),
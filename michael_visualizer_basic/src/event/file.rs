@@ -1,17 +1,17 @@
 use crate::data_types::FileLabel;
 
+/// Files are read and parsed by the caller, the engine only receives the result
 pub enum FileEvent<Key, File> {
     Loaded {
         key: Key,
         label: FileLabel,
         file: File,
     },
-    LoadFromContent {
-        label: String,
-        content: Vec<u8>,
-    },
-    LoadFromPath {
-        path: std::path::PathBuf,
+    /// Content of a loaded file changed, e.g. derived columns were added
+    /// Label, position and visibility are kept
+    Replaced {
+        key: Key,
+        file: File,
     },
     Removed(Key),
     Title(Key, FileLabel),
@@ -3,6 +3,8 @@ use crate::data_types::LimitLabel;
 pub enum LimitEvent<Key, LimitData> {
     Value(Key, LimitData),
     Label(Key, LimitLabel),
+    /// Limit was edited in place, see [`crate::DataCenter::limit_mut`]
+    Edited(Key),
     ToPlot(Key),
     //FormulaAdded(Key),
     //FormulaRemoved(Key),
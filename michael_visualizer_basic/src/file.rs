@@ -1,5 +1,7 @@
 use std::hash::Hash;
+use std::sync::Arc;
 
+use crate::bitset::Bitset;
 use crate::LimitTrait;
 
 use super::event::HiddenOrShown;
@@ -8,17 +10,22 @@ use super::data_types::FileLabel;
 
 pub trait FileTrait {
     type Limit;
-    fn limits(&self) -> &[Self::Limit];
+    /// Limit of each column, as given by the file
+    fn limits(&self) -> Vec<Self::Limit>;
     fn row_count(&self) -> usize;
-    fn apply_limit(&mut self, limit_index: usize, limit: &Self::Limit) -> Vec<bool>;
+    /// Set for each row which is outside of the limit, i.e. filtered
+    /// Called from several threads at once, for different columns or files
+    fn apply_limit(&self, limit_index: usize, limit: &Self::Limit) -> Bitset;
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
 pub struct FileWrapper<File, LimitKey: Eq + Hash> {
     label: FileLabel,
     hidden_or_shown: HiddenOrShown,
-    content: File,
+    /// Shared with the threads which compute filters
+    content: Arc<File>,
     limit_indices: std::collections::HashMap<LimitKey, usize>,
+    /// Labels of the columns which are ignored, since an earlier column has the same label
+    duplicates: Vec<crate::LimitLabel>,
     filters_summed: Vec<u32>,
 }
 
@@ -26,9 +33,23 @@ impl<File, LimitKey: Eq + Hash> FileWrapper<File, LimitKey> {
     pub(crate) fn get_label(&self) -> &FileLabel {
         &self.label
     }
-    pub(crate) fn get_mut_label(&mut self) -> &mut FileLabel {
-        &mut self.label
+    pub(crate) fn content(&self) -> &File {
+        &self.content
     }
+    pub(crate) fn shared_content(&self) -> &Arc<File> {
+        &self.content
+    }
+    pub(crate) fn limit_indices(&self) -> &std::collections::HashMap<LimitKey, usize> {
+        &self.limit_indices
+    }
+    pub(crate) fn duplicates(&self) -> &[crate::LimitLabel] {
+        &self.duplicates
+    }
+    pub(crate) fn filters_summed(&self) -> &[u32] {
+        &self.filters_summed
+    }
+    /// Limits of the file have to be added before
+    /// If several columns have the same label, the first one is used
     pub(crate) fn new(
         label: FileLabel,
         content: File,
@@ -40,32 +61,34 @@ impl<File, LimitKey: Eq + Hash> FileWrapper<File, LimitKey> {
         <File as FileTrait>::Limit: crate::LimitTrait,
     {
         let mut limit_indices = std::collections::HashMap::new();
-        let mut used_labels = Vec::new();
+        let mut duplicates = Vec::new();
         for (index, limit) in content.limits().iter().enumerate() {
-            if let Some((key, limit)) = limit_keys
+            let (key, _) = limit_keys
                 .iter()
-                .filter(|(_, l)| l.has_same_label(limit))
-                .next()
-            {
-                if used_labels.contains(limit.label()) {
-                    panic!("Limit label is used multiple times: {:?}", limit.label());
+                .find(|(_, l)| l.has_same_label(limit))
+                .expect("Before this is called, its limits are already added");
+            match limit_indices.entry(key.clone()) {
+                std::collections::hash_map::Entry::Occupied(_) => {
+                    duplicates.push(limit.original_label().clone())
+                }
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(index);
                 }
-                used_labels.push(limit.label().clone());
-                let temp = limit_indices.insert(key.clone(), index);
-                assert!(temp.is_none());
-            } else {
-                unreachable!("Before this is called, its limits are already added");
             }
         }
         Self {
             label,
             hidden_or_shown: HiddenOrShown::Shown,
             filters_summed: vec![0; content.row_count()],
-            content,
+            content: Arc::new(content),
             limit_indices,
+            duplicates,
         }
     }
 
+    pub(crate) fn hidden_or_shown(&self) -> HiddenOrShown {
+        self.hidden_or_shown
+    }
     pub(crate) fn is_shown(&self) -> bool {
         self.hidden_or_shown == HiddenOrShown::Shown
     }
@@ -73,50 +96,25 @@ impl<File, LimitKey: Eq + Hash> FileWrapper<File, LimitKey> {
         self.hidden_or_shown = hidden_or_shown;
     }
 
-    pub(crate) fn apply_limit(&mut self, key: &LimitKey, limit: &File::Limit) -> Option<Vec<bool>>
-    where
-        File: FileTrait,
-        LimitKey: std::hash::Hash + Eq,
-    {
-        if let Some(index) = self.limit_indices.get(key) {
-            Some(self.content.apply_limit(*index, limit))
-        } else {
-            None
-        }
+    pub(crate) fn column(&self, key: &LimitKey) -> Option<usize> {
+        self.limit_indices.get(key).copied()
     }
 
-    pub(crate) fn limits(&self) -> &[File::Limit]
-    where
-        File: FileTrait,
-    {
-        self.content.limits()
-    }
-
-    pub(crate) fn filters_new(&mut self, filters: &[bool]) {
-        self.filters_summed
-            .iter_mut()
-            .zip(filters.iter())
-            .filter(|(_, f)| **f)
-            .for_each(|(x, _)| *x += 1);
-    }
-    pub(crate) fn filters_adjusted(&mut self, new_filters: &[bool], old_filters: &[bool]) {
-        self.filters_summed
-            .iter_mut()
-            .zip(new_filters.iter())
-            .zip(old_filters.iter())
-            .for_each(|((x, new), old)| {
-                *x = match (new, old) {
-                    (true, true) => *x,
-                    (true, false) => *x + 1,
-                    (false, true) => *x - 1,
-                    (false, false) => *x,
-                }
-            })
+    pub(crate) fn filters_new(&mut self, filters: &Bitset) {
+        for row in filters.ones() {
+            self.filters_summed[row] += 1;
+        }
     }
-    pub(crate) fn get_to_befiltered(&self) -> impl Iterator<Item = bool> + '_ {
-        self.filters_summed.iter().map(|x| x > &0)
+    /// Only rows whose filter changed are visited
+    pub(crate) fn filters_adjusted(&mut self, new_filters: &Bitset, old_filters: &Bitset) {
+        for (row, filtered) in new_filters.changes(old_filters) {
+            if filtered {
+                self.filters_summed[row] += 1;
+            } else {
+                self.filters_summed[row] -= 1;
+            }
+        }
     }
-
     pub(crate) fn change_label(&mut self, label: FileLabel) {
         self.label = label;
     }
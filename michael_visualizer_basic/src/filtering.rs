@@ -0,0 +1,121 @@
+use std::hash::Hash;
+use std::sync::{mpsc, Arc};
+
+use crate::bitset::Bitset;
+use crate::file::FileTrait;
+
+/// Applies `f` to each item, the items are split among the available cores
+pub(crate) fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(items.len());
+    if threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let chunk = items.len().div_ceil(threads);
+    let f = &f;
+    std::thread::scope(|scope| {
+        items
+            .chunks(chunk)
+            .map(|items| scope.spawn(move || items.iter().map(f).collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Filtering thread panicked"))
+            .collect()
+    })
+}
+
+/// Column of a file, which a limit is applied to
+pub(crate) struct Target<FileKey, File> {
+    pub file_key: FileKey,
+    pub file: Arc<File>,
+    pub column: usize,
+}
+
+/// Filters of a limit for the files which have it
+pub(crate) struct Outcome<FileKey, LimitKey, File> {
+    pub limit_key: LimitKey,
+    /// The file is compared on arrival, a reloaded file with the same key is not affected
+    pub filters: Vec<(FileKey, Arc<File>, Bitset)>,
+}
+
+/// Computes the filters of changed limits on a background thread
+/// Only one job per limit runs at a time, further changes meanwhile are merged into one,
+/// which starts with the newest value, so dragging a limit does not pile up outdated work
+pub(crate) struct Worker<FileKey, LimitKey, File> {
+    sender: mpsc::Sender<Outcome<FileKey, LimitKey, File>>,
+    receiver: mpsc::Receiver<Outcome<FileKey, LimitKey, File>>,
+    running: std::collections::HashSet<LimitKey>,
+    queued: std::collections::HashSet<LimitKey>,
+}
+impl<FileKey, LimitKey, File> Default for Worker<FileKey, LimitKey, File> {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            running: Default::default(),
+            queued: Default::default(),
+        }
+    }
+}
+impl<FileKey, LimitKey, File> Worker<FileKey, LimitKey, File>
+where
+    FileKey: Send + Sync + 'static,
+    LimitKey: Hash + Eq + Clone + Send + 'static,
+    File: FileTrait + Send + Sync + 'static,
+    File::Limit: Send + Sync + 'static,
+{
+    /// Returns false, if a job of this limit is running, it has to be submitted again afterwards
+    pub(crate) fn submit(
+        &mut self,
+        limit_key: LimitKey,
+        limit: File::Limit,
+        targets: impl FnOnce() -> Vec<Target<FileKey, File>>,
+    ) -> bool {
+        if self.running.contains(&limit_key) {
+            self.queued.insert(limit_key);
+            false
+        } else {
+            let targets = targets();
+            self.running.insert(limit_key.clone());
+            let sender = self.sender.clone();
+            std::thread::spawn(move || {
+                let filters = parallel_map(&targets, |target| {
+                    target.file.apply_limit(target.column, &limit)
+                });
+                let filters = targets
+                    .into_iter()
+                    .zip(filters)
+                    .map(|(target, filters)| (target.file_key, target.file, filters))
+                    .collect();
+                // the receiver is gone, if the data center was dropped meanwhile
+                let _ = sender.send(Outcome { limit_key, filters });
+            });
+            true
+        }
+    }
+
+    /// Finished job and whether its limit has changed meanwhile
+    pub(crate) fn receive(
+        &mut self,
+        block: bool,
+    ) -> Option<(Outcome<FileKey, LimitKey, File>, bool)> {
+        if self.running.is_empty() {
+            return None;
+        }
+        let outcome = if block {
+            self.receiver.recv().ok()?
+        } else {
+            self.receiver.try_recv().ok()?
+        };
+        self.running.remove(&outcome.limit_key);
+        let queued = self.queued.remove(&outcome.limit_key);
+        Some((outcome, queued))
+    }
+
+    pub(crate) fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+}
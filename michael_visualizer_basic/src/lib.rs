@@ -1,11 +1,14 @@
+mod bitset;
 mod data_types;
 mod event;
 mod file;
+mod filtering;
 
 mod simple_keys;
 pub use simple_keys::{SimpleFileKey, SimpleLimitKey};
 use std::hash::Hash;
 
+pub use bitset::Bitset;
 pub use data_types::FileLabel;
 pub use data_types::LimitLabel;
 use data_types::OrderedMap;
@@ -13,15 +16,33 @@ pub use event::*;
 pub use file::FileTrait;
 
 mod key_generator;
+pub use key_generator::Incrementable;
 
+#[cfg(test)]
+mod test;
+
+/// Files, limits and the resulting filtering, without any GUI
+/// Loaded files are not persisted, they have to be loaded again, limits and key generators are
 #[derive(serde::Deserialize, serde::Serialize)]
+#[serde(bound(
+    serialize = "FileKey: serde::Serialize, LimitKey: serde::Serialize, Limit: serde::Serialize",
+    deserialize = "FileKey: serde::Deserialize<'de>, LimitKey: serde::Deserialize<'de>, Limit: serde::Deserialize<'de>"
+))]
 pub struct DataCenter<FileKey: Eq + Hash, LimitKey: Eq + Hash, File, Limit> {
+    #[serde(skip)]
     files: OrderedMap<FileKey, file::FileWrapper<File, LimitKey>>,
     limits: OrderedMap<LimitKey, Limit>,
-    filters: std::collections::HashMap<(FileKey, LimitKey), Vec<bool>>,
+    #[serde(skip)]
+    filters: std::collections::HashMap<(FileKey, LimitKey), Bitset>,
+    /// Filters which are counted, i.e. the own filters merged with the combined limits
+    #[serde(skip)]
+    combined: std::collections::HashMap<(FileKey, LimitKey), Bitset>,
     limit_to_plot: Option<LimitKey>,
     file_key_generator: key_generator::KeyGenerator<FileKey>,
     limit_key_generator: key_generator::KeyGenerator<LimitKey>,
+    /// Changed limits are applied in the background, until then the previous filters are kept
+    #[serde(skip)]
+    worker: filtering::Worker<FileKey, LimitKey, File>,
 }
 impl<FileKey: Default + Eq + Hash, LimitKey: Default + Eq + Hash, File, Limit> Default
     for DataCenter<FileKey, LimitKey, File, Limit>
@@ -31,13 +52,15 @@ impl<FileKey: Default + Eq + Hash, LimitKey: Default + Eq + Hash, File, Limit> D
             files: Default::default(),
             limits: Default::default(),
             filters: Default::default(),
+            combined: Default::default(),
             limit_to_plot: Default::default(),
             file_key_generator: Default::default(),
             limit_key_generator: Default::default(),
+            worker: Default::default(),
         }
     }
 }
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct RedrawSelection {
     pub files: bool,
     pub limits: bool,
@@ -71,18 +94,39 @@ impl RedrawSelection {
     }
 }
 
+/// How a limit is combined with other limits, a row is judged per file
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combination {
+    /// A row passes, if it passes this limit and all of the others
+    And,
+    /// A row passes, if it passes this limit or any of the others
+    Or,
+}
+
 pub trait LimitTrait {
     fn has_same_label(&self, other: &Self) -> bool;
     fn change_label(&mut self, label: LimitLabel) -> bool;
     fn label(&self) -> &LimitLabel;
+    /// Label as given by the file, which does not change if the limit is renamed
+    fn original_label(&self) -> &LimitLabel;
+    /// Other limits, given by their original label, which are combined with this one
+    fn combination(&self) -> Option<(Combination, &[LimitLabel])> {
+        None
+    }
+    /// False if the limit only matters as part of a combination
+    fn is_counted(&self) -> bool {
+        true
+    }
+    /// Called if a file with the same limit is loaded, e.g. to widen the range of its values
+    fn merge(&mut self, _other: &Self) {}
 }
 
 impl<FileKey, LimitKey, File, Limit> DataCenter<FileKey, LimitKey, File, Limit>
 where
-    FileKey: Hash + Eq + Clone + key_generator::Incrementable,
-    LimitKey: Hash + Eq + Clone + key_generator::Incrementable,
-    File: FileTrait<Limit = Limit>,
-    Limit: LimitTrait + Clone,
+    FileKey: Hash + Eq + Clone + key_generator::Incrementable + Send + Sync + 'static,
+    LimitKey: Hash + Eq + Clone + key_generator::Incrementable + Send + Sync + 'static,
+    File: FileTrait<Limit = Limit> + Send + Sync + 'static,
+    Limit: LimitTrait + Clone + Send + Sync + 'static,
 {
     #[must_use]
     pub fn progress(
@@ -95,18 +139,27 @@ where
             let a = match event {
                 DataEvent::File(event) => match event {
                     FileEvent::Loaded { key, label, file } => {
-                        self.add_limits(file.limits());
-                        let mut file = file::FileWrapper::new(label, file, &self.limits);
-                        for (limit_key, limit) in self.limits.iter() {
-                            file.apply_limit(limit_key, limit);
-                        }
-                        let x = self.files.insert(key, file);
-                        assert!(x.is_none());
+                        self.load(key, label, file, HiddenOrShown::Shown);
                         Some(RedrawSelection::redraw())
                     }
+                    FileEvent::Replaced { key, file } => {
+                        if let Some((label, hidden_or_shown)) = self
+                            .files
+                            .get(&key)
+                            .map(|f| (f.get_label().clone(), f.hidden_or_shown()))
+                        {
+                            self.filters.retain(|(file_key, _), _| file_key != &key);
+                            self.combined.retain(|(file_key, _), _| file_key != &key);
+                            self.load(key, label, file, hidden_or_shown);
+                            Some(RedrawSelection::redraw())
+                        } else {
+                            None
+                        }
+                    }
                     FileEvent::Removed(key) => {
                         if self.files.remove(&key).is_some() {
-                            self.remove_unnecessary_limits();
+                            self.filters.retain(|(file_key, _), _| file_key != &key);
+                            self.combined.retain(|(file_key, _), _| file_key != &key);
                             Some(RedrawSelection::redraw())
                         } else {
                             None
@@ -145,23 +198,24 @@ where
                             None
                         }
                     }
-                    FileEvent::LoadFromContent { label, content } => todo!(),
-                    FileEvent::LoadFromPath { path } => todo!(),
                 },
                 DataEvent::Limit(event) => match event {
                     LimitEvent::Value(limit_key, new) => self.limit_value(limit_key, new),
                     LimitEvent::Label(key, label) => self.limit_label(key, label),
+                    LimitEvent::Edited(limit_key) => self.limit_edited(limit_key),
                     LimitEvent::ToPlot(key) => {
                         if self.limit_to_plot.as_ref() != Some(&key) {
                             self.limit_to_plot = Some(key);
                             Some(RedrawSelection::limit())
-                        }else{None}
+                        } else {
+                            None
+                        }
                     }
                     //event::LimitEvent::FormulaAdded(_) => todo!(),
                     //event::LimitEvent::FormulaRemoved(_) => todo!(),
                 },
                 DataEvent::Heatmap(event) => match event {
-                   HeatmapEvent::ShowHide(show_hide_event) => {
+                    HeatmapEvent::ShowHide(show_hide_event) => {
                         self.show_hide_event(show_hide_event)
                     }
                     //event::HeatmapEvent::Selection => todo!(),
@@ -180,41 +234,178 @@ where
         action
     }
 
+    /// Adds the file, or replaces it at its position if the key is in use
+    fn load(&mut self, key: FileKey, label: FileLabel, file: File, hidden_or_shown: HiddenOrShown) {
+        self.add_limits(&file.limits());
+        let mut file = file::FileWrapper::new(label, file, &self.limits);
+        file.change_shown(hidden_or_shown);
+        // all columns of the new file at once
+        let columns = self
+            .limits
+            .iter()
+            .filter_map(|(limit_key, limit)| {
+                file.column(limit_key)
+                    .map(|column| (limit_key, column, limit))
+            })
+            .collect::<Vec<_>>();
+        let content = file.content();
+        let filters = filtering::parallel_map(&columns, |(_, column, limit)| {
+            content.apply_limit(*column, limit)
+        });
+        let limit_keys = columns
+            .into_iter()
+            .map(|(limit_key, _, _)| limit_key.clone())
+            .collect::<Vec<_>>();
+        for (limit_key, filters) in limit_keys.iter().zip(filters) {
+            self.filters
+                .insert((key.clone(), limit_key.clone()), filters);
+        }
+        self.files.insert(key.clone(), file);
+        for limit_key in &limit_keys {
+            self.combine(&key, limit_key);
+        }
+    }
+
+    /// The new filters are computed in the background, see [`Self::poll`]
     #[must_use]
     fn limit_value(&mut self, limit_key: LimitKey, new: Limit) -> Option<RedrawSelection> {
-        let Self {
-            files,
-            limits,
-            filters,
-            limit_to_plot: _,
-            file_key_generator: _,
-            limit_key_generator: _,
-        } = self;
-        if let Some(previous) = limits.get_mut(&limit_key) {
-            let mut filters_changed = false;
-            for (file_key, file) in files.iter_mut() {
-                if let Some(new_filters) = file.apply_limit(&limit_key, &new) {
-                    if let Some(old_filters) =
-                        filters.get_mut(&(file_key.clone(), limit_key.clone()))
-                    {
-                        filters_changed |= new_filters
-                            .iter()
-                            .zip(old_filters.iter())
-                            .any(|(a, b)| a != b);
-                        file.filters_adjusted(&new_filters, old_filters);
-                    } else {
-                        filters_changed |= new_filters.iter().any(|x| *x);
-                        file.filters_new(&new_filters);
-                        filters.insert((file_key.clone(), limit_key.clone()), new_filters);
-                    }
+        if let Some(previous) = self.limits.get_mut(&limit_key) {
+            *previous = new.clone();
+            self.submit(limit_key, new);
+        }
+        None
+    }
+    #[must_use]
+    fn limit_edited(&mut self, limit_key: LimitKey) -> Option<RedrawSelection> {
+        if let Some(limit) = self.limits.get(&limit_key).cloned() {
+            self.submit(limit_key, limit);
+        }
+        None
+    }
+    fn submit(&mut self, limit_key: LimitKey, limit: Limit) {
+        let Self { files, worker, .. } = self;
+        let targets = || {
+            files
+                .iter()
+                .filter_map(|(file_key, file)| {
+                    file.column(&limit_key).map(|column| filtering::Target {
+                        file_key: file_key.clone(),
+                        file: file.shared_content().clone(),
+                        column,
+                    })
+                })
+                .collect()
+        };
+        let _ = worker.submit(limit_key.clone(), limit, targets);
+    }
+    /// Takes over the filters computed in the background, if `block` is set it waits for all of them
+    #[must_use]
+    fn receive_filters(&mut self, block: bool) -> RedrawSelection {
+        let mut filters_changed = false;
+        while let Some((outcome, queued)) = self.worker.receive(block) {
+            let filtering::Outcome { limit_key, filters } = outcome;
+            for (file_key, content, new_filters) in filters {
+                let Some(file) = self.files.get_mut(&file_key) else {
+                    continue;
+                };
+                if !std::sync::Arc::ptr_eq(file.shared_content(), &content) {
+                    // the file was reloaded meanwhile
+                    continue;
+                }
+                self.filters
+                    .insert((file_key.clone(), limit_key.clone()), new_filters);
+                filters_changed |= self.recombine(&file_key, &limit_key);
+            }
+            if queued {
+                if let Some(limit) = self.limits.get(&limit_key) {
+                    self.submit(limit_key.clone(), limit.clone());
                 }
             }
-            *previous = new;
-            filters_changed.then_some(RedrawSelection::limit())
+        }
+        if filters_changed {
+            RedrawSelection::limit()
         } else {
-            None
+            RedrawSelection::default()
         }
     }
+    /// Filters of changed limits, which are finished by now
+    #[must_use]
+    pub fn poll(&mut self) -> RedrawSelection {
+        self.receive_filters(false)
+    }
+    /// Waits until the filters of all changed limits are computed
+    #[must_use]
+    pub fn wait(&mut self) -> RedrawSelection {
+        self.receive_filters(true)
+    }
+    /// Own filter of a limit in a file, as if the limit had another value, see [`Self::filters_with`]
+    /// None if the file has no column for this limit
+    pub fn filter_with(
+        &self,
+        file_key: &FileKey,
+        limit_key: &LimitKey,
+        limit: &Limit,
+    ) -> Option<Bitset> {
+        let file = self.files.get(file_key)?;
+        let column = file.column(limit_key)?;
+        Some(file.content().apply_limit(column, limit))
+    }
+    /// Filters of a file, as if some limits had other values, e.g. to compare limit scenarios
+    /// Limits which are not given keep their current value, the data center is not changed
+    /// Own filters of the other limits, which are in `known`, are used instead of computing them again
+    pub fn filters_with(
+        &self,
+        file_key: &FileKey,
+        other_limits: &std::collections::HashMap<LimitKey, Limit>,
+        known: &std::collections::HashMap<LimitKey, Bitset>,
+    ) -> Option<Filters<LimitKey>> {
+        let file = self.files.get(file_key)?;
+        let limits = self
+            .limits
+            .iter()
+            .map(|(key, limit)| (key, other_limits.get(key).unwrap_or(limit)))
+            .collect::<Vec<_>>();
+        let changed = limits
+            .iter()
+            .filter(|(key, _)| other_limits.contains_key(key) && !known.contains_key(key))
+            .filter_map(|(key, limit)| file.column(key).map(|column| (*key, column, *limit)))
+            .collect::<Vec<_>>();
+        let content = file.content();
+        let changed_filters = filtering::parallel_map(&changed, |(_, column, limit)| {
+            content.apply_limit(*column, limit)
+        });
+        let own = changed
+            .iter()
+            .map(|(key, _, _)| *key)
+            .zip(changed_filters)
+            .collect::<std::collections::HashMap<_, _>>();
+        let own = |key: &LimitKey| {
+            own.get(key)
+                .or_else(|| known.get(key).filter(|_| other_limits.contains_key(key)))
+                .or_else(|| self.filters.get(&(file_key.clone(), key.clone())))
+        };
+        let mut summed = vec![0; content.row_count()];
+        let mut counted_filters = std::collections::HashMap::new();
+        for &(key, _) in &limits {
+            if file.column(key).is_none() {
+                continue;
+            }
+            if let Some(counted) = counted(key, limits.iter().copied(), own) {
+                for row in counted.ones() {
+                    summed[row] += 1;
+                }
+                counted_filters.insert(key.clone(), counted);
+            }
+        }
+        Some(Filters {
+            counted: counted_filters,
+            summed,
+        })
+    }
+    /// True while changed limits are applied in the background
+    pub fn is_filtering(&self) -> bool {
+        self.worker.is_busy()
+    }
     #[must_use]
     fn limit_label(&mut self, key: LimitKey, label: LimitLabel) -> Option<RedrawSelection> {
         if let Some(limit) = self.limits.get_mut(&key) {
@@ -249,40 +440,155 @@ where
             violin: true,
         })
     }
-    fn remove_unnecessary_limits(&mut self) {
-        let mut to_remove = Vec::new();
-        'outer: for (key, limit) in self.limits.iter() {
-            for file_limits in self.files.iter().flat_map(|(_, f)| f.limits()) {
-                if file_limits.has_same_label(limit) {
-                    continue 'outer;
-                }
-            }
-            to_remove.push(key.clone());
-        }
-        for key in to_remove {
-            self.limits.remove(&key);
-        }
-    }
+    /// Limits outlive the files they came from, so edits survive removing and reloading a file
     fn add_limits(&mut self, limits: &[Limit]) {
         for limit in limits {
-            let has_limit = self.limits.iter().any(|(_, l)| l.has_same_label(limit));
-            if !has_limit {
+            let existing = self
+                .limits
+                .iter()
+                .find(|(_, l)| l.has_same_label(limit))
+                .map(|(key, _)| key.clone());
+            if let Some(key) = existing {
+                if let Some(current) = self.limits.get_mut(&key) {
+                    current.merge(limit);
+                }
+            } else {
                 let key = self.new_limit_key();
                 // for each existing file, find data filtered by this limit
-                for (file_key, file) in self.files.iter_mut() {
-                    if let Some(filters) = file.apply_limit(&key, limit) {
-                        file.filters_new(&filters);
-                        let x = self
-                            .filters
-                            .insert((file_key.clone(), key.clone()), filters);
-                        assert!(x.is_none());
-                    }
-                }
-                let x = self.limits.insert(key, limit.clone());
+                let targets = self
+                    .files
+                    .iter()
+                    .filter_map(|(file_key, file)| {
+                        file.column(&key)
+                            .map(|column| (file_key.clone(), file.content(), column))
+                    })
+                    .collect::<Vec<_>>();
+                let filters = filtering::parallel_map(&targets, |(_, content, column)| {
+                    content.apply_limit(*column, limit)
+                });
+                let targets = targets
+                    .into_iter()
+                    .map(|(file_key, _, _)| file_key)
+                    .collect::<Vec<_>>();
+                let x = self.limits.insert(key.clone(), limit.clone());
                 assert!(x.is_none());
+                for (file_key, filters) in targets.into_iter().zip(filters) {
+                    let x = self
+                        .filters
+                        .insert((file_key.clone(), key.clone()), filters);
+                    assert!(x.is_none());
+                    self.recombine(&file_key, &key);
+                }
+            }
+        }
+    }
+    /// Updates the counted filter of a limit and of the limits combined with it
+    /// Returns true, if any counted filter changed
+    fn recombine(&mut self, file_key: &FileKey, limit_key: &LimitKey) -> bool {
+        let Some(label) = self
+            .limits
+            .get(limit_key)
+            .map(|l| l.original_label().clone())
+        else {
+            return false;
+        };
+        let dependents = self
+            .limits
+            .iter()
+            .filter(|(key, limit)| {
+                *key != limit_key
+                    && limit
+                        .combination()
+                        .map(|(_, labels)| labels.contains(&label))
+                        .unwrap_or(false)
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        let mut changed = self.combine(file_key, limit_key);
+        for dependent in dependents {
+            changed |= self.combine(file_key, &dependent);
+        }
+        changed
+    }
+    /// Updates the counted filter of a limit in a file, returns true if it changed
+    /// Combined limits, which the file does not have, are ignored
+    fn combine(&mut self, file_key: &FileKey, limit_key: &LimitKey) -> bool {
+        let key = (file_key.clone(), limit_key.clone());
+        let Self {
+            files,
+            limits,
+            filters,
+            combined,
+            ..
+        } = self;
+        let Some(file) = files.get_mut(file_key) else {
+            return false;
+        };
+        let Some(counted) = counted(limit_key, limits.iter(), |other_key| {
+            filters.get(&(file_key.clone(), other_key.clone()))
+        }) else {
+            return false;
+        };
+        match combined.get_mut(&key) {
+            Some(old) if *old == counted => false,
+            Some(old) => {
+                file.filters_adjusted(&counted, old);
+                *old = counted;
+                true
+            }
+            None => {
+                file.filters_new(&counted);
+                let changed = counted.count_ones() > 0;
+                combined.insert(key, counted);
+                changed
             }
         }
     }
+    /// Removes the limits which no loaded file has, e.g. after files were removed
+    /// Returns them with their keys, so that they can be restored, see [`Self::restore_limits`]
+    #[must_use]
+    pub fn prune_limits(&mut self) -> Vec<(LimitKey, Limit)> {
+        let stale = self
+            .limits
+            .iter()
+            .filter(|(key, _)| {
+                !self
+                    .files
+                    .iter()
+                    .any(|(_, file)| file.column(key).is_some())
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        if self
+            .limit_to_plot
+            .as_ref()
+            .is_some_and(|key| stale.contains(key))
+        {
+            self.limit_to_plot = None;
+        }
+        stale
+            .into_iter()
+            .filter_map(|key| self.limits.remove(&key).map(|limit| (key, limit)))
+            .collect()
+    }
+    /// Adds pruned limits again, before the files which have them are loaded again
+    /// A limit is skipped, if its key or its label is in use meanwhile
+    pub fn restore_limits(&mut self, limits: impl IntoIterator<Item = (LimitKey, Limit)>) {
+        for (key, limit) in limits {
+            if !self
+                .limits
+                .iter()
+                .any(|(k, l)| *k == key || l.has_same_label(&limit))
+            {
+                self.limits.insert(key, limit);
+            }
+        }
+    }
+    /// Limit to be edited in place, e.g. by the text fields of a GUI
+    /// Its filters are computed again with [`LimitEvent::Edited`]
+    pub fn limit_mut(&mut self, key: &LimitKey) -> Option<&mut Limit> {
+        self.limits.get_mut(key)
+    }
     #[must_use]
     fn new_limit_key(&mut self) -> LimitKey {
         self.limit_key_generator.generate()
@@ -292,3 +598,95 @@ where
         self.file_key_generator.generate()
     }
 }
+
+// Getter
+impl<FileKey, LimitKey, File, Limit> DataCenter<FileKey, LimitKey, File, Limit>
+where
+    FileKey: Hash + Eq + Clone,
+    LimitKey: Hash + Eq + Clone,
+{
+    /// Loaded files in their current order
+    pub fn files(&self) -> impl Iterator<Item = (&FileKey, &FileLabel, &File)> {
+        self.files
+            .iter()
+            .map(|(key, file)| (key, file.get_label(), file.content()))
+    }
+    pub fn file(&self, key: &FileKey) -> Option<&File> {
+        self.files.get(key).map(|f| f.content())
+    }
+    pub fn is_shown(&self, key: &FileKey) -> bool {
+        self.files.get(key).map(|f| f.is_shown()).unwrap_or(false)
+    }
+    pub fn limits(&self) -> impl Iterator<Item = (&LimitKey, &Limit)> {
+        self.limits.iter()
+    }
+    pub fn limit(&self, key: &LimitKey) -> Option<&Limit> {
+        self.limits.get(key)
+    }
+    pub fn limit_to_plot(&self) -> Option<&LimitKey> {
+        self.limit_to_plot.as_ref()
+    }
+    /// Column of each limit within the file
+    pub fn limit_indices(
+        &self,
+        key: &FileKey,
+    ) -> Option<&std::collections::HashMap<LimitKey, usize>> {
+        self.files.get(key).map(|f| f.limit_indices())
+    }
+    pub fn has_limit(&self, file_key: &FileKey, limit_key: &LimitKey) -> bool {
+        self.files
+            .get(file_key)
+            .map(|f| f.limit_indices().contains_key(limit_key))
+            .unwrap_or(false)
+    }
+    /// Set for each row which is outside of this limit, including the limits combined with it
+    pub fn filter(&self, file_key: &FileKey, limit_key: &LimitKey) -> Option<&Bitset> {
+        self.combined.get(&(file_key.clone(), limit_key.clone()))
+    }
+    /// Labels of the columns which are ignored, since an earlier column of the file has the same label
+    pub fn duplicate_labels(&self, key: &FileKey) -> Option<&[LimitLabel]> {
+        self.files.get(key).map(|f| f.duplicates())
+    }
+    /// Count of limits which filter each row, zero if the row passes all limits
+    pub fn filters_summed(&self, key: &FileKey) -> Option<&[u32]> {
+        self.files.get(key).map(|f| f.filters_summed())
+    }
+}
+
+/// Filtering of a file, computed apart from the data center, see [`DataCenter::filters_with`]
+#[derive(Debug, PartialEq)]
+pub struct Filters<LimitKey: Eq + Hash> {
+    /// Set for each row which is outside of the limit, including the limits combined with it
+    pub counted: std::collections::HashMap<LimitKey, Bitset>,
+    /// Count of limits which filter each row
+    pub summed: Vec<u32>,
+}
+
+/// Own filter of a limit, merged with the filters of the limits combined with it
+/// Empty if the limit is not counted, None if the limit or its own filter is missing
+fn counted<'a, LimitKey: Eq + 'a, Limit: LimitTrait + 'a>(
+    limit_key: &LimitKey,
+    limits: impl Iterator<Item = (&'a LimitKey, &'a Limit)> + Clone,
+    own: impl Fn(&LimitKey) -> Option<&'a Bitset>,
+) -> Option<Bitset> {
+    let (_, limit) = limits.clone().find(|(key, _)| *key == limit_key)?;
+    let own_filter = own(limit_key)?;
+    if !limit.is_counted() {
+        return Some(Bitset::new(own_filter.len()));
+    }
+    let mut counted = own_filter.clone();
+    if let Some((combination, labels)) = limit.combination() {
+        for (other_key, other) in limits {
+            if other_key == limit_key || !labels.contains(other.original_label()) {
+                continue;
+            }
+            if let Some(other) = own(other_key) {
+                match combination {
+                    Combination::And => counted |= other,
+                    Combination::Or => counted &= other,
+                }
+            }
+        }
+    }
+    Some(counted)
+}
@@ -1,6 +1,4 @@
-use michael_visualizer_basic::*;
-
-
+use crate::*;
 
 struct SimpleFile {
     limits: Vec<SimpleLimit>,
@@ -9,23 +7,21 @@ struct SimpleFile {
 impl FileTrait for SimpleFile {
     type Limit = SimpleLimit;
 
-    fn limits(&self) -> &[Self::Limit] {
-        &self.limits
+    fn limits(&self) -> Vec<Self::Limit> {
+        self.limits.clone()
     }
 
     fn row_count(&self) -> usize {
         self.data[0].len()
     }
 
-    fn apply_limit(&mut self, limit_index: usize, limit: &Self::Limit) -> Vec<bool> {
-        let data = self
-            .data
-            .get_mut(limit_index)
-            .expect("Wrong limit index given");
+    fn apply_limit(&self, limit_index: usize, limit: &Self::Limit) -> Bitset {
+        let data = self.data.get(limit_index).expect("Wrong limit index given");
         let Self::Limit {
             label: _,
             lower,
             upper,
+            ..
         } = limit;
         let check = |f| {
             if let Some(lower) = lower {
@@ -48,10 +44,12 @@ struct SimpleLimit {
     label: LimitLabel,
     lower: Option<f32>,
     upper: Option<f32>,
+    combination: Option<(Combination, Vec<LimitLabel>)>,
+    counted: bool,
 }
 impl LimitTrait for SimpleLimit {
     fn has_same_label(&self, other: &Self) -> bool {
-        &self.label == &other.label
+        self.label == other.label
     }
 
     fn change_label(&mut self, label: LimitLabel) -> bool {
@@ -66,116 +64,300 @@ impl LimitTrait for SimpleLimit {
     fn label(&self) -> &LimitLabel {
         &self.label
     }
+
+    fn original_label(&self) -> &LimitLabel {
+        &self.label
+    }
+
+    fn combination(&self) -> Option<(Combination, &[LimitLabel])> {
+        self.combination
+            .as_ref()
+            .map(|(combination, labels)| (*combination, &labels[..]))
+    }
+
+    fn is_counted(&self) -> bool {
+        self.counted
+    }
+}
+
+fn file(suffix: &str) -> SimpleFile {
+    let limit = |label: &str, lower, upper| SimpleLimit {
+        label: format!("{label}{suffix}").into(),
+        lower,
+        upper,
+        combination: None,
+        counted: true,
+    };
+    let data = vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5];
+    SimpleFile {
+        limits: vec![
+            limit("LimitNone", None, None),
+            limit("LimitLower", Some(1.), None),
+            limit("LimitUpper", None, Some(3.)),
+            limit("LimitAll", Some(2.), Some(4.)),
+        ],
+        data: vec![data; 4],
+    }
 }
 
 #[test]
 fn simple_test() {
     let mut center =
         DataCenter::<SimpleFileKey, SimpleLimitKey, SimpleFile, SimpleLimit>::default();
+    let keys = [(); 3].map(|_| center.new_file_key());
     let events = vec![
-        Event::File(FileEvent::Loaded(
-            center.new_file_key(),
-            "FileA".to_string().into(),
-            SimpleFile {
-                limits: vec![
-                    SimpleLimit {
-                        label: "LimitNone".to_string().into(),
-                        lower: None,
-                        upper: None,
-                    },
-                    SimpleLimit {
-                        label: "LimitLower".to_string().into(),
-                        lower: Some(1.),
-                        upper: None,
-                    },
-                    SimpleLimit {
-                        label: "LimitUpper".to_string().into(),
-                        lower: None,
-                        upper: Some(3.),
-                    },
-                    SimpleLimit {
-                        label: "LimitAll".to_string().into(),
-                        lower: Some(2.),
-                        upper: Some(4.),
-                    },
-                ],
-                data: vec![
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                ],
-            },
-        )),
-        Event::File(FileEvent::Loaded(
-            center.new_file_key(),
-            "FileB".to_string().into(),
-            SimpleFile {
-                limits: vec![
-                    SimpleLimit {
-                        label: "LimitNone".to_string().into(),
-                        lower: None,
-                        upper: None,
-                    },
-                    SimpleLimit {
-                        label: "LimitLower".to_string().into(),
-                        lower: Some(1.),
-                        upper: None,
-                    },
-                    SimpleLimit {
-                        label: "LimitUpper".to_string().into(),
-                        lower: None,
-                        upper: Some(3.),
-                    },
-                    SimpleLimit {
-                        label: "LimitAll".to_string().into(),
-                        lower: Some(2.),
-                        upper: Some(4.),
-                    },
-                ],
-                data: vec![
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                ],
-            },
-        )),
-        Event::File(FileEvent::Loaded(
-            center.new_file_key(),
-            "FileC".to_string().into(),
-            SimpleFile {
-                limits: vec![
-                    SimpleLimit {
-                        label: "LimitNone2".to_string().into(),
-                        lower: None,
-                        upper: None,
-                    },
-                    SimpleLimit {
-                        label: "LimitLower2".to_string().into(),
-                        lower: Some(1.),
-                        upper: None,
-                    },
-                    SimpleLimit {
-                        label: "LimitUpper2".to_string().into(),
-                        lower: None,
-                        upper: Some(3.),
-                    },
-                    SimpleLimit {
-                        label: "LimitAll2".to_string().into(),
-                        lower: Some(2.),
-                        upper: Some(4.),
-                    },
-                ],
-                data: vec![
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                    vec![-1., 0., 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5],
-                ],
-            },
-        )),
-        Event::Limit(LimitEvent::ToPlot(SimpleLimitKey(1))),
+        DataEvent::File(FileEvent::Loaded {
+            key: keys[0],
+            label: "FileA".to_string().into(),
+            file: file(""),
+        }),
+        DataEvent::File(FileEvent::Loaded {
+            key: keys[1],
+            label: "FileB".to_string().into(),
+            file: file(""),
+        }),
+        DataEvent::File(FileEvent::Loaded {
+            key: keys[2],
+            label: "FileC".to_string().into(),
+            file: file("2"),
+        }),
+        DataEvent::Limit(LimitEvent::ToPlot(SimpleLimitKey(1))),
     ];
-    dbg!(center.progress(events.into_iter()));
+    assert_eq!(
+        center.progress(events.into_iter()),
+        RedrawSelection::redraw()
+    );
+    // files with the same labels share limits
+    assert_eq!(center.limits().count(), 8);
+    assert_eq!(
+        center
+            .files()
+            .map(|(_, l, _)| l.as_str())
+            .collect::<Vec<_>>(),
+        ["FileA", "FileB", "FileC"]
+    );
+    assert!(center.has_limit(&keys[1], &SimpleLimitKey(1)));
+    assert!(!center.has_limit(&keys[2], &SimpleLimitKey(1)));
+    let expected = [2, 2, 2, 1, 1, 0, 0, 0, 1, 1, 2];
+    for key in &keys {
+        assert_eq!(center.filters_summed(key), Some(&expected[..]));
+    }
+
+    // changed limit is applied to all files which have it, in the background
+    let lower = |lower| {
+        DataEvent::Limit(LimitEvent::Value(
+            SimpleLimitKey(1),
+            SimpleLimit {
+                label: "LimitLower".to_string().into(),
+                lower: Some(lower),
+                upper: None,
+                combination: None,
+                counted: true,
+            },
+        ))
+    };
+    assert_eq!(
+        center.progress(std::iter::once(lower(2.))),
+        RedrawSelection::default()
+    );
+    assert_eq!(center.wait(), RedrawSelection::limit());
+    assert!(!center.is_filtering());
+    let changed = [2, 2, 2, 2, 2, 0, 0, 0, 1, 1, 2];
+    assert_eq!(center.filters_summed(&keys[0]), Some(&changed[..]));
+    assert_eq!(center.filters_summed(&keys[1]), Some(&changed[..]));
+    assert_eq!(center.filters_summed(&keys[2]), Some(&expected[..]));
+    assert_eq!(
+        center
+            .filter(&keys[0], &SimpleLimitKey(1))
+            .map(|f| f.ones().collect::<Vec<_>>()),
+        Some(vec![0, 1, 2, 3, 4])
+    );
+    // same value again does not change the filtering
+    let _ = center.progress(std::iter::once(lower(2.)));
+    assert_eq!(center.wait(), RedrawSelection::default());
+    assert_eq!(center.filters_summed(&keys[0]), Some(&changed[..]));
+    // changes while a job runs are merged, the last value wins
+    let _ = center.progress([lower(4.), lower(0.), lower(1.)].into_iter());
+    let _ = center.wait();
+    assert_eq!(center.filters_summed(&keys[0]), Some(&expected[..]));
+
+    // replaced content keeps the position of the file
+    let _ = center.progress(std::iter::once(DataEvent::File(FileEvent::Replaced {
+        key: keys[0],
+        file: file("2"),
+    })));
+    assert_eq!(
+        center
+            .files()
+            .map(|(_, l, _)| l.as_str())
+            .collect::<Vec<_>>(),
+        ["FileA", "FileB", "FileC"]
+    );
+    assert!(!center.has_limit(&keys[0], &SimpleLimitKey(1)));
+    assert_eq!(center.filters_summed(&keys[0]), Some(&expected[..]));
+
+    // limits are kept when their files are removed
+    let _ = center.progress(std::iter::once(DataEvent::File(FileEvent::Removed(
+        keys[0],
+    ))));
+    assert_eq!(center.filters_summed(&keys[0]), None);
+    assert_eq!(center.filter(&keys[0], &SimpleLimitKey(1)), None);
+    assert_eq!(center.limits().count(), 8);
+
+    // until they are pruned, which no file has any more
+    assert!(center.prune_limits().is_empty());
+    let _ = center.progress(std::iter::once(DataEvent::File(FileEvent::Removed(
+        keys[2],
+    ))));
+    let pruned = center.prune_limits();
+    assert_eq!(
+        pruned
+            .iter()
+            .map(|(_, limit)| limit.label.as_str())
+            .collect::<Vec<_>>(),
+        ["LimitNone2", "LimitLower2", "LimitUpper2", "LimitAll2"]
+    );
+    assert_eq!(center.limits().count(), 4);
+    // restored limits keep their keys, when the file is loaded again
+    let pruned_keys = pruned.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+    center.restore_limits(pruned);
+    let _ = center.progress(std::iter::once(DataEvent::File(FileEvent::Loaded {
+        key: keys[2],
+        label: "FileC".to_string().into(),
+        file: file("2"),
+    })));
+    assert_eq!(center.limits().count(), 8);
+    assert!(pruned_keys
+        .iter()
+        .all(|key| center.has_limit(&keys[2], key)));
+}
+
+#[test]
+fn edit_in_place() {
+    let mut center =
+        DataCenter::<SimpleFileKey, SimpleLimitKey, SimpleFile, SimpleLimit>::default();
+    let key = center.new_file_key();
+    let mut file = file("");
+    // the second column with the same label is ignored
+    file.limits[2].label = file.limits[1].label.clone();
+    let _ = center.progress(std::iter::once(DataEvent::File(FileEvent::Loaded {
+        key,
+        label: "File".to_string().into(),
+        file,
+    })));
+    assert_eq!(center.limits().count(), 3);
+    assert_eq!(
+        center
+            .duplicate_labels(&key)
+            .map(|labels| labels.iter().map(|l| l.as_str()).collect::<Vec<_>>()),
+        Some(vec!["LimitLower"])
+    );
+
+    let lower_key = SimpleLimitKey(1);
+    center.limit_mut(&lower_key).unwrap().lower = Some(4.);
+    assert_eq!(
+        center.progress(std::iter::once(DataEvent::Limit(LimitEvent::Edited(
+            lower_key
+        )))),
+        RedrawSelection::default()
+    );
+    assert_eq!(center.wait(), RedrawSelection::limit());
+    assert_eq!(
+        center.filter(&key, &lower_key).map(|f| f.count_ones()),
+        Some(9)
+    );
+}
+
+#[test]
+fn combination() {
+    let mut center =
+        DataCenter::<SimpleFileKey, SimpleLimitKey, SimpleFile, SimpleLimit>::default();
+    let key = center.new_file_key();
+    let mut file = file("");
+    let base = file.limits[1].clone();
+    let lower = |combination| SimpleLimit {
+        combination: Some((combination, vec!["LimitUpper".to_string().into()])),
+        ..base.clone()
+    };
+    file.limits[1] = lower(Combination::Or);
+    file.limits[2].counted = false;
+    let _ = center.progress(std::iter::once(DataEvent::File(FileEvent::Loaded {
+        key,
+        label: "File".to_string().into(),
+        file,
+    })));
+    let (lower_key, upper_key) = (SimpleLimitKey(1), SimpleLimitKey(2));
+    // no row is below 1 and above 3, so only 'LimitAll' is left
+    assert_eq!(
+        center.filters_summed(&key),
+        Some(&[1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1][..])
+    );
+    assert_eq!(
+        center.filter(&key, &upper_key).map(|f| f.count_ones()),
+        Some(0)
+    );
+
+    let _ = center.progress(std::iter::once(DataEvent::Limit(LimitEvent::Value(
+        lower_key,
+        lower(Combination::And),
+    ))));
+    assert_eq!(center.wait(), RedrawSelection::limit());
+    assert_eq!(
+        center.filters_summed(&key),
+        Some(&[2, 2, 2, 1, 1, 0, 0, 0, 1, 1, 2][..])
+    );
+    assert_eq!(
+        center
+            .filter(&key, &lower_key)
+            .map(|f| f.ones().collect::<Vec<_>>()),
+        Some(vec![0, 1, 2, 8, 9, 10])
+    );
+
+    // the previous value, computed apart from the data center
+    let other = [(lower_key, lower(Combination::Or))].into_iter().collect();
+    let filters = center
+        .filters_with(&key, &other, &Default::default())
+        .unwrap();
+    assert_eq!(filters.summed, [1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(filters.counted[&upper_key].count_ones(), 0);
+    // with the own filter computed before
+    let known = other
+        .iter()
+        .filter_map(|(limit_key, limit)| {
+            center
+                .filter_with(&key, limit_key, limit)
+                .map(|filter| (*limit_key, filter))
+        })
+        .collect();
+    assert!(center.filters_with(&key, &other, &known) == Some(filters));
+    assert_eq!(
+        center.filters_summed(&key),
+        Some(&[2, 2, 2, 1, 1, 0, 0, 0, 1, 1, 2][..])
+    );
+}
+
+#[test]
+fn bitset() {
+    let bools = (0..150).map(|i| i % 3 == 0 || i == 149).collect::<Vec<_>>();
+    let mut set = bools.iter().copied().collect::<Bitset>();
+    assert_eq!(set, Bitset::from_fn(150, |i| bools[i]));
+    assert_eq!((set.len(), set.count_ones()), (150, 51));
+    assert_eq!(set.iter().collect::<Vec<_>>(), bools);
+    assert_eq!(set.ones().take(3).collect::<Vec<_>>(), [0, 3, 6]);
+
+    let before = set.clone();
+    set.set(0, false);
+    set.set(100, true);
+    assert!(!set.get(0) && set.get(100) && set.get(149));
+    assert_eq!(
+        set.changes(&before).collect::<Vec<_>>(),
+        [(0, false), (100, true)]
+    );
+    assert_eq!(Bitset::new(70).count_ones(), 0);
+
+    let mut and = before.clone();
+    and &= &set;
+    let mut or = before.clone();
+    or |= &set;
+    assert_eq!((and.count_ones(), or.count_ones()), (50, 52));
 }